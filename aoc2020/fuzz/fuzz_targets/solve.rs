@@ -0,0 +1,20 @@
+//! Feeds arbitrary input to every day. The first byte picks the day, the rest is the puzzle
+//! input. Any panic, or a solve that never returns, is a bug: bad input must come back as an
+//! error.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let (&selector, input) = match data.split_first() {
+        Some(split) => split,
+        None => return,
+    };
+    let input = match std::str::from_utf8(input) {
+        Ok(input) => input,
+        Err(_) => return,
+    };
+
+    let solvers = aoc2020::get_solvers();
+    let solver = &solvers[usize::from(selector) % solvers.len()];
+    let _ = solver.solve(input);
+});
@@ -0,0 +1,45 @@
+/*
+ * The interface aoc2020 loads solver plugins through.
+ *
+ * A plugin is a shared library in the plugin directory that exports `aoc2020_plugin`. It solves
+ * one day under an implementation name, which `aoc2020 --impl <name>` selects. See
+ * src/plugins.rs for where plugins are looked for.
+ */
+#ifndef AOC2020_PLUGIN_H
+#define AOC2020_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Bumped whenever this struct changes, so that old plugins are refused rather than misread. */
+#define AOC2020_PLUGIN_ABI 1
+
+typedef struct {
+    /* Always AOC2020_PLUGIN_ABI. */
+    uint32_t abi;
+    /*
+     * The implementation's name, as given to --impl. Only ASCII letters, digits, `_` and `-`, and
+     * not "builtin".
+     */
+    const char *name;
+    /* Bumped whenever the answers could change, so that cached ones are recomputed. */
+    uint32_t version;
+    /* The day solved, from 1 to 25. */
+    uint8_t (*day)(void);
+    /*
+     * Solve the `len` bytes at `input`, which end in a newline and contain no carriage returns.
+     * On success, point `part1` and `part2` at the answers and return 0. On failure, return
+     * anything else and point `part1` at an error message, or leave it NULL. Either way, every
+     * string returned is freed with `free_string`.
+     */
+    int32_t (*solve)(const uint8_t *input, size_t len, char **part1, char **part2);
+    void (*free_string)(char *string);
+} aoc2020_plugin_t;
+
+/*
+ * The plugin's description. It must stay valid for as long as the library is loaded, and its
+ * functions may be called from any thread, though never from two at once.
+ */
+const aoc2020_plugin_t *aoc2020_plugin(void);
+
+#endif
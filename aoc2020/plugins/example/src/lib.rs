@@ -0,0 +1,111 @@
+//! An example aoc2020 plugin: day 1, solved by sorting the expenses and closing in from both ends
+//! instead of with the built-in solver's hash set.
+//!
+//! It deliberately doesn't depend on aoc2020, to show that a plugin only needs the interface in
+//! `aoc2020_plugin.h`. Build it with `cargo build --release` and copy
+//! `target/release/libaoc2020_example_plugin.so` (or `.dylib`, or `.dll`) into the plugin
+//! directory, then run `aoc2020 --impl example --day 1`.
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::slice;
+
+const ABI_VERSION: u32 = 1;
+
+/// Mirrors `aoc2020_plugin_t`.
+#[repr(C)]
+pub struct Plugin {
+    abi: u32,
+    name: *const c_char,
+    version: u32,
+    day: extern "C" fn() -> u8,
+    solve: extern "C" fn(*const u8, usize, *mut *mut c_char, *mut *mut c_char) -> i32,
+    free_string: extern "C" fn(*mut c_char),
+}
+
+// Only ever read.
+unsafe impl Sync for Plugin {}
+
+static PLUGIN: Plugin = Plugin {
+    abi: ABI_VERSION,
+    name: b"example\0".as_ptr() as *const c_char,
+    version: 1,
+    day,
+    solve,
+    free_string,
+};
+
+#[no_mangle]
+pub extern "C" fn aoc2020_plugin() -> *const Plugin {
+    &PLUGIN
+}
+
+extern "C" fn day() -> u8 {
+    1
+}
+
+extern "C" fn solve(
+    input: *const u8,
+    len: usize,
+    part1: *mut *mut c_char,
+    part2: *mut *mut c_char,
+) -> i32 {
+    // Safety: aoc2020 passes a valid buffer and pointers to write the answers to.
+    let input = unsafe { slice::from_raw_parts(input, len) };
+    let (status, answers) = match answers(input) {
+        Ok((first, second)) => (0, (first, Some(second))),
+        Err(error) => (1, (error, None)),
+    };
+    unsafe {
+        *part1 = into_raw(answers.0);
+        if let Some(second) = answers.1 {
+            *part2 = into_raw(second);
+        }
+    }
+    status
+}
+
+extern "C" fn free_string(string: *mut c_char) {
+    // Safety: every string handed out came from `into_raw`.
+    drop(unsafe { CString::from_raw(string) });
+}
+
+fn into_raw(string: String) -> *mut c_char {
+    // Neither answers nor errors contain a null byte.
+    CString::new(string).unwrap().into_raw()
+}
+
+fn answers(input: &[u8]) -> Result<(String, String), String> {
+    let input = std::str::from_utf8(input).map_err(|_| "Input isn't UTF-8".to_owned())?;
+    let mut expenses = input
+        .lines()
+        .map(|line| {
+            line.parse::<u64>()
+                .map_err(|e| format!("Invalid expense '{}': {}", line, e))
+        })
+        .collect::<Result<Vec<u64>, String>>()?;
+    expenses.sort_unstable();
+
+    let pair = pair_summing_to(&expenses, 2020).ok_or("No two expenses add up to 2020")?;
+    let triple = (0..expenses.len())
+        .find_map(|i| {
+            let rest = 2020_u64.checked_sub(expenses[i])?;
+            let (a, b) = pair_summing_to(&expenses[i + 1..], rest)?;
+            Some(expenses[i] * a * b)
+        })
+        .ok_or("No three expenses add up to 2020")?;
+
+    Ok(((pair.0 * pair.1).to_string(), triple.to_string()))
+}
+
+/// Two entries of the sorted `expenses` adding up to `total`.
+fn pair_summing_to(expenses: &[u64], total: u64) -> Option<(u64, u64)> {
+    let (mut low, mut high) = (0, expenses.len().checked_sub(1)?);
+    while low < high {
+        match expenses[low] + expenses[high] {
+            sum if sum == total => return Some((expenses[low], expenses[high])),
+            sum if sum < total => low += 1,
+            _ => high -= 1,
+        }
+    }
+    None
+}
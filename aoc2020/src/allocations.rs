@@ -0,0 +1,131 @@
+//! Counts the heap allocations a solve makes, so that work on allocation can be measured, and
+//! checked again after later changes.
+//!
+//! The test binary's global allocator counts every allocation and reallocation by the thread
+//! making it, so tests running alongside don't skew the numbers. To see them for the days whose
+//! allocations have been worked on:
+//!
+//! ```text
+//! cargo test --lib allocations -- --nocapture
+//! ```
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::ops::Sub;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Allocations {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl Sub for Allocations {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Self {
+            count: self.count - other.count,
+            bytes: self.bytes - other.bytes,
+        }
+    }
+}
+
+thread_local! {
+    static ALLOCATIONS: Cell<Allocations> = const {
+        Cell::new(Allocations { count: 0, bytes: 0 })
+    };
+}
+
+struct Counting;
+
+impl Counting {
+    fn record(size: usize) {
+        // Allocations while the thread is being torn down go uncounted.
+        let _ = ALLOCATIONS.try_with(|allocations| {
+            let mut counted = allocations.get();
+            counted.count += 1;
+            counted.bytes += size;
+            allocations.set(counted);
+        });
+    }
+}
+
+// Safety: every call is passed straight on to the system allocator.
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::record(new_size);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Run `f`, returning what it allocated on this thread.
+pub(crate) fn count<T>(f: impl FnOnce() -> T) -> (T, Allocations) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (result, ALLOCATIONS.with(Cell::get) - before)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::get_solvers;
+    use crate::inputs::for_test;
+
+    /// Allocations for a full solve of the real input, as of the last change to bring them down.
+    /// The check allows a little slack, as the standard library's growth strategies can change.
+    const RECORDED: &[(u8, usize)] = &[(2, 11), (7, 1_978), (14, 2_231), (20, 5_590)];
+
+    #[test]
+    fn test_count() {
+        let (_, allocations) = count(|| vec![0_u8; 100]);
+        assert_eq!(
+            allocations,
+            Allocations {
+                count: 1,
+                bytes: 100
+            }
+        );
+    }
+
+    #[test]
+    fn allocations() {
+        let solvers = get_solvers();
+        for &(day, recorded) in RECORDED {
+            let input = match for_test(day) {
+                Some(input) => input,
+                None => continue,
+            };
+            let solver = &solvers[usize::from(day) - 1];
+            let (solution, allocations) = count(|| solver.solve(&input));
+            solution.unwrap();
+
+            eprintln!(
+                "Day {:02}: {} allocations, {} bytes (recorded {})",
+                day, allocations.count, allocations.bytes, recorded
+            );
+            assert!(
+                allocations.count <= recorded + recorded / 10,
+                "Day {:02} made {} allocations, up from {}",
+                day,
+                allocations.count,
+                recorded
+            );
+        }
+    }
+}
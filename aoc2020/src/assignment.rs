@@ -0,0 +1,275 @@
+//! Solve "each item gets exactly one distinct value" puzzles from candidate sets.
+//!
+//! Candidates are first narrowed down by elimination: an item with a single candidate claims it
+//! and it's removed from every other item. If that stalls before every item is resolved, the
+//! remaining items are solved as a bipartite matching, which is then checked for uniqueness so
+//! the items that are ambiguous or can't be satisfied can be reported.
+use std::fmt;
+use std::hash::Hash;
+
+use crate::bitset::BitSet;
+use crate::graph::Interner;
+use crate::hash::{FastMap, FastSet};
+
+/// Why a set of candidates doesn't determine exactly one assignment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssignmentError {
+    /// No assignment gives every item a distinct value. Holds the items left without a value.
+    Contradiction(Vec<usize>),
+    /// More than one assignment fits. Holds the items that could take more than one value.
+    Ambiguous(Vec<usize>),
+}
+
+impl AssignmentError {
+    /// Indices of the offending items.
+    pub fn items(&self) -> &[usize] {
+        match self {
+            Self::Contradiction(items) | Self::Ambiguous(items) => items,
+        }
+    }
+}
+
+impl fmt::Display for AssignmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contradiction(items) => write!(f, "no valid value for items {:?}", items),
+            Self::Ambiguous(items) => write!(f, "more than one valid value for items {:?}", items),
+        }
+    }
+}
+
+impl std::error::Error for AssignmentError {}
+
+/// Pick a distinct value for every item, where `candidates[i]` holds the values item `i` may take.
+///
+/// Returns the chosen value for each item, in item order, or the items that stop the assignment
+/// from being unique.
+pub fn assign<T>(candidates: &[FastSet<T>]) -> Result<Vec<T>, AssignmentError>
+where
+    T: Hash + Eq + Clone,
+{
+    let mut values = Interner::new();
+    let options: Vec<BitSet> = candidates
+        .iter()
+        .map(|c| c.iter().map(|v| values.intern(v.clone())).collect())
+        .collect();
+
+    Ok(assign_indices(&options)?
+        .into_iter()
+        .map(|id| values.resolve(id).clone())
+        .collect())
+}
+
+/// [`assign`] for values numbered from 0, where `candidates[i]` holds the numbers item `i` may
+/// take.
+pub fn assign_indices(candidates: &[BitSet]) -> Result<Vec<usize>, AssignmentError> {
+    let mut options = candidates.to_vec();
+    let mut assigned = eliminate(&mut options)?;
+    let unresolved = assigned.iter().filter(|a| a.is_none()).count();
+    aoc_debug!(
+        "Elimination resolved {} of {} items",
+        assigned.len() - unresolved,
+        assigned.len()
+    );
+
+    if unresolved > 0 {
+        match_remaining(&options, &mut assigned)?;
+    }
+
+    Ok(assigned.into_iter().map(Option::unwrap).collect())
+}
+
+/// Repeatedly let items with a single candidate claim it.
+fn eliminate(options: &mut [BitSet]) -> Result<Vec<Option<usize>>, AssignmentError> {
+    let mut assigned = vec![None; options.len()];
+
+    loop {
+        let empty: Vec<usize> = (0..options.len())
+            .filter(|&i| assigned[i].is_none() && options[i].is_empty())
+            .collect();
+        if !empty.is_empty() {
+            return Err(AssignmentError::Contradiction(empty));
+        }
+
+        let singles: Vec<usize> = (0..options.len())
+            .filter(|&i| assigned[i].is_none() && options[i].len() == 1)
+            .collect();
+        if singles.is_empty() {
+            return Ok(assigned);
+        }
+
+        for i in singles {
+            // An earlier single this round may have claimed the same value.
+            let value = match options[i].iter().next() {
+                Some(value) => value,
+                None => continue,
+            };
+            assigned[i] = Some(value);
+            aoc_trace!("Item {} can only take value #{}", i, value);
+
+            for (j, other) in options.iter_mut().enumerate() {
+                if assigned[j].is_none() {
+                    other.remove(value);
+                }
+            }
+        }
+    }
+}
+
+/// Assign the items elimination couldn't resolve with a maximum bipartite matching, then make sure
+/// no other matching exists.
+fn match_remaining(
+    options: &[BitSet],
+    assigned: &mut [Option<usize>],
+) -> Result<(), AssignmentError> {
+    let unresolved: Vec<usize> = (0..options.len())
+        .filter(|&i| assigned[i].is_none())
+        .collect();
+
+    let mut matching = Matching::new(options);
+    let unmatched: Vec<usize> = unresolved
+        .iter()
+        .copied()
+        .filter(|&i| !matching.augment(i, None))
+        .collect();
+    if !unmatched.is_empty() {
+        return Err(AssignmentError::Contradiction(unmatched));
+    }
+
+    // The matching is unique iff no item can be re-matched once its current value is banned.
+    let ambiguous: Vec<usize> = unresolved
+        .iter()
+        .copied()
+        .filter(|&i| {
+            let mut alternative = matching.clone();
+            let value = alternative.unmatch(i);
+            alternative.augment(i, Some((i, value)))
+        })
+        .collect();
+    if !ambiguous.is_empty() {
+        return Err(AssignmentError::Ambiguous(ambiguous));
+    }
+
+    for i in unresolved {
+        assigned[i] = matching.value_of(i);
+    }
+
+    Ok(())
+}
+
+#[derive(Clone)]
+struct Matching<'o> {
+    options: &'o [BitSet],
+    /// Which item each value is currently matched to.
+    owners: FastMap<usize, usize>,
+}
+
+impl<'o> Matching<'o> {
+    fn new(options: &'o [BitSet]) -> Self {
+        Self {
+            options,
+            owners: FastMap::default(),
+        }
+    }
+
+    fn value_of(&self, item: usize) -> Option<usize> {
+        self.owners
+            .iter()
+            .find(|&(_, &owner)| owner == item)
+            .map(|(&value, _)| value)
+    }
+
+    fn unmatch(&mut self, item: usize) -> usize {
+        let value = self.value_of(item).expect("item is matched");
+        self.owners.remove(&value);
+        value
+    }
+
+    /// Find an augmenting path that matches `item` without using the `banned` `(item, value)` pair
+    /// (Kuhn's algorithm).
+    fn augment(&mut self, item: usize, banned: Option<(usize, usize)>) -> bool {
+        let mut visited = FastSet::default();
+        self.try_item(item, banned, &mut visited)
+    }
+
+    fn try_item(
+        &mut self,
+        item: usize,
+        banned: Option<(usize, usize)>,
+        visited: &mut FastSet<usize>,
+    ) -> bool {
+        for value in &self.options[item] {
+            if Some((item, value)) == banned || !visited.insert(value) {
+                continue;
+            }
+
+            let free = match self.owners.get(&value) {
+                None => true,
+                Some(&owner) => self.try_item(owner, banned, visited),
+            };
+            if free {
+                self.owners.insert(value, item);
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sets(candidates: &[&[char]]) -> Vec<FastSet<char>> {
+        candidates
+            .iter()
+            .map(|c| c.iter().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_elimination() {
+        let candidates = sets(&[&['a', 'b', 'c'], &['b'], &['b', 'c']]);
+        assert_eq!(assign(&candidates), Ok(vec!['a', 'b', 'c']));
+    }
+
+    #[test]
+    fn test_matching() {
+        // No item has a single candidate, so elimination can't make any progress.
+        let candidates = sets(&[&['a', 'b'], &['a', 'b', 'c'], &['b', 'c', 'd'], &['c', 'd']]);
+        assert_eq!(
+            assign(&candidates),
+            Err(AssignmentError::Ambiguous(vec![0, 1, 2, 3]))
+        );
+
+        let candidates = sets(&[&['a', 'b'], &['a', 'b'], &['a', 'b']]);
+        let actual = assign(&candidates).unwrap_err();
+        assert!(matches!(actual, AssignmentError::Contradiction(_)));
+        assert_eq!(actual.items().len(), 1);
+    }
+
+    #[test]
+    fn test_contradiction() {
+        let candidates = sets(&[&['a'], &['a'], &['b', 'c']]);
+        assert_eq!(
+            assign(&candidates),
+            Err(AssignmentError::Contradiction(vec![1]))
+        );
+
+        let candidates = sets(&[&['a', 'b'], &['b'], &['a', 'c'], &['c']]);
+        assert_eq!(
+            assign(&candidates),
+            Err(AssignmentError::Contradiction(vec![2]))
+        );
+    }
+
+    #[test]
+    fn test_ambiguous() {
+        let candidates = sets(&[&['a', 'b'], &['a', 'b'], &['c']]);
+        assert_eq!(
+            assign(&candidates),
+            Err(AssignmentError::Ambiguous(vec![0, 1]))
+        );
+    }
+}
@@ -0,0 +1,291 @@
+//! Sets of small numbers kept a bit each, for the days that juggle masks.
+//!
+//! A [`BitSet`] grows to fit the largest number it holds, so it has none of the fixed width of
+//! the integer it replaces: past 64 members it just takes another word.
+
+use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Sub, SubAssign};
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    /// Bit `i % 64` of word `i / 64` is set if `i` is in the set. The last word is never zero, so
+    /// that equal sets are stored the same.
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every number below `len`.
+    pub fn full(len: usize) -> Self {
+        let mut words = vec![u64::MAX; len / WORD_BITS];
+        if !len.is_multiple_of(WORD_BITS) {
+            words.push((1 << (len % WORD_BITS)) - 1);
+        }
+        Self { words }
+    }
+
+    /// Add `i`, returning whether it was missing.
+    pub fn insert(&mut self, i: usize) -> bool {
+        let (word, bit) = (i / WORD_BITS, 1 << (i % WORD_BITS));
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let missing = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        missing
+    }
+
+    /// Take out `i`, returning whether it was there.
+    pub fn remove(&mut self, i: usize) -> bool {
+        let (word, bit) = (i / WORD_BITS, 1 << (i % WORD_BITS));
+        let present = self.contains(i);
+        if present {
+            self.words[word] &= !bit;
+            self.trim();
+        }
+        present
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        let (word, bit) = (i / WORD_BITS, 1 << (i % WORD_BITS));
+        self.words.get(word).is_some_and(|&w| w & bit != 0)
+    }
+
+    /// How many numbers are in the set.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    /// The numbers in the set, smallest first.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: &self.words,
+            index: 0,
+            word: self.words.first().copied().unwrap_or(0),
+        }
+    }
+
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.words.len() <= other.words.len()
+            && self
+                .words
+                .iter()
+                .zip(&other.words)
+                .all(|(a, b)| a & !b == 0)
+    }
+
+    /// The set as the bits of a `u64`, or `None` if it holds anything from 64 up.
+    pub fn to_u64(&self) -> Option<u64> {
+        match self.words.as_slice() {
+            [] => Some(0),
+            [word] => Some(*word),
+            _ => None,
+        }
+    }
+
+    fn trim(&mut self) {
+        while self.words.last() == Some(&0) {
+            self.words.pop();
+        }
+    }
+}
+
+impl From<u64> for BitSet {
+    /// The positions of the bits set in `word`.
+    fn from(word: u64) -> Self {
+        let mut set = Self { words: vec![word] };
+        set.trim();
+        set
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<T: IntoIterator<Item = usize>>(&mut self, iter: T) {
+        for i in iter {
+            self.insert(i);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a> {
+    words: &'a [u64],
+    index: usize,
+    /// What's left of `words[index]`.
+    word: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+        let bit = self.word.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.word &= self.word - 1;
+        Some(self.index * WORD_BITS + bit)
+    }
+}
+
+impl BitOrAssign<&BitSet> for BitSet {
+    fn bitor_assign(&mut self, other: &BitSet) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+}
+
+impl BitAndAssign<&BitSet> for BitSet {
+    fn bitand_assign(&mut self, other: &BitSet) {
+        self.words.truncate(other.words.len());
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= b;
+        }
+        self.trim();
+    }
+}
+
+impl SubAssign<&BitSet> for BitSet {
+    fn sub_assign(&mut self, other: &BitSet) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= !b;
+        }
+        self.trim();
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    /// The union.
+    fn bitor(self, other: &BitSet) -> BitSet {
+        let mut union = self.clone();
+        union |= other;
+        union
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    /// The intersection.
+    fn bitand(self, other: &BitSet) -> BitSet {
+        let mut intersection = self.clone();
+        intersection &= other;
+        intersection
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    /// The numbers in `self` but not `other`.
+    fn sub(self, other: &BitSet) -> BitSet {
+        let mut difference = self.clone();
+        difference -= other;
+        difference
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_insert_remove() {
+        let mut set = BitSet::new();
+
+        assert!(set.insert(3));
+        assert!(!set.insert(3));
+        assert!(set.insert(130));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(130));
+        assert!(!set.contains(64));
+        assert!(set.remove(130));
+        assert!(!set.remove(130));
+        // Emptying the high words leaves a set equal to one that never had them.
+        assert_eq!(set, BitSet::from(1 << 3));
+        assert_eq!(set.to_u64(), Some(8));
+        assert!(set.remove(3));
+        assert!(set.is_empty());
+        assert_eq!(set, BitSet::new());
+    }
+
+    #[test]
+    fn test_full() {
+        assert_eq!(BitSet::full(0), BitSet::new());
+        assert_eq!(BitSet::full(26).to_u64(), Some((1 << 26) - 1));
+        assert_eq!(BitSet::full(64).to_u64(), Some(u64::MAX));
+        assert_eq!(BitSet::full(100).len(), 100);
+        assert_eq!(BitSet::full(100).to_u64(), None);
+        assert!(!BitSet::full(100).contains(100));
+    }
+
+    #[test]
+    fn test_iter() {
+        let numbers = vec![0, 5, 63, 64, 65, 200];
+        let set: BitSet = numbers.iter().copied().collect();
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), numbers);
+        assert_eq!(format!("{:?}", set), "{0, 5, 63, 64, 65, 200}");
+        assert_eq!(BitSet::new().iter().next(), None);
+    }
+
+    #[test]
+    fn test_algebra() {
+        let a: BitSet = vec![1, 2, 70, 100].into_iter().collect();
+        let b: BitSet = vec![2, 3, 100].into_iter().collect();
+        let set = |numbers: &[usize]| numbers.iter().copied().collect::<BitSet>();
+
+        assert_eq!(&a | &b, set(&[1, 2, 3, 70, 100]));
+        assert_eq!(&a & &b, set(&[2, 100]));
+        assert_eq!(&a - &b, set(&[1, 70]));
+        assert_eq!(&b - &a, set(&[3]));
+        assert_eq!(&a & &set(&[1]), set(&[1]));
+        assert!(set(&[2, 100]).is_subset(&a));
+        assert!(!b.is_subset(&a));
+        assert!(BitSet::new().is_subset(&b));
+    }
+}
@@ -0,0 +1,310 @@
+//! Answers saved on disk, so that running the slow days again is instant.
+//!
+//! An answer is keyed by its day, [implementation](Solver::name), [version](Solver::version) and
+//! a hash of the input, so changing the input or the version misses the cache. The cache lives in
+//! `cache-dir` from the [config file](crate::config) if set, otherwise `$XDG_CACHE_HOME/aoc2020`
+//! (or `~/.cache/aoc2020`). Each answer is a file holding the input's length and 128-bit hash,
+//! then part 1 and part 2, each on its own line. The file name only has room for a 64-bit hash,
+//! so a hit is trusted only if the length and the longer hash match too.
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+
+use crate::config::Config;
+use crate::solution::Solution;
+use crate::Solver;
+
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The cache in its usual place, or `None` if there's nowhere to put it.
+    pub fn open() -> anyhow::Result<Option<Self>> {
+        if let Some(dir) = Config::load()?.get_path("cache-dir") {
+            return Ok(Some(Self::new(dir)));
+        }
+
+        let cache_home = match (env::var_os("XDG_CACHE_HOME"), env::var_os("HOME")) {
+            (Some(dir), _) => PathBuf::from(dir),
+            (None, Some(home)) => Path::new(&home).join(".cache"),
+            (None, None) => return Ok(None),
+        };
+        Ok(Some(Self::new(cache_home.join("aoc2020"))))
+    }
+
+    /// The saved answer for `solver` on `input`, if any. An unreadable entry counts as a miss.
+    pub fn get(&self, solver: &dyn Solver, input: &[u8]) -> Option<Solution> {
+        let path = self.path(solver, input);
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) => {
+                if error.kind() != io::ErrorKind::NotFound {
+                    aoc_debug!("Unable to read {}: {}", path.display(), error);
+                }
+                return None;
+            }
+        };
+
+        let mut lines = contents.split('\n');
+        match (
+            lines.next(),
+            lines.next(),
+            lines.next(),
+            lines.next(),
+            lines.next(),
+        ) {
+            (Some(identity), Some(part1), Some(part2), Some(""), None) => {
+                if identity == input_identity(input) {
+                    Some(Solution::new(part1, part2))
+                } else {
+                    aoc_debug!("Ignoring cache entry {} for another input", path.display());
+                    None
+                }
+            }
+            _ => {
+                aoc_debug!("Ignoring malformed cache entry {}", path.display());
+                None
+            }
+        }
+    }
+
+    /// Save `solution` as the answer for `solver` on `input`, dropping any the day saved under
+    /// another version.
+    pub fn put(
+        &self,
+        solver: &dyn Solver,
+        input: &[u8],
+        solution: &Solution,
+    ) -> anyhow::Result<()> {
+        fs::create_dir_all(&self.dir)
+            .with_context(|| format!("Unable to create {}", self.dir.display()))?;
+
+        for entry in fs::read_dir(&self.dir)?.flatten() {
+            let file_name = entry.file_name();
+            let stale = match EntryName::parse(&file_name.to_string_lossy()) {
+                Some(entry) => {
+                    entry.day == solver.day()
+                        && entry.name == solver.name()
+                        && entry.version != solver.version()
+                }
+                None => false,
+            };
+            if stale {
+                fs::remove_file(entry.path())?;
+            }
+        }
+
+        let path = self.path(solver, input);
+        fs::write(
+            &path,
+            format!(
+                "{}\n{}\n{}\n",
+                input_identity(input),
+                solution.part1(),
+                solution.part2()
+            ),
+        )
+        .with_context(|| format!("Unable to write {}", path.display()))
+    }
+
+    fn path(&self, solver: &dyn Solver, input: &[u8]) -> PathBuf {
+        self.dir.join(format!(
+            "day{:02}-{}-v{}-{:016x}.txt",
+            solver.day(),
+            solver.name(),
+            solver.version(),
+            fnv1a(input)
+        ))
+    }
+}
+
+/// What a cache entry records about its input, beyond the hash in its file name.
+fn input_identity(input: &[u8]) -> String {
+    format!("{} {:032x}", input.len(), fnv1a_128(input))
+}
+
+/// The parts of a cache file name, `dayDD-NAME-vVERSION-HASH.txt`. Names may contain `-`, so the
+/// version and hash are taken from the end.
+#[derive(Debug, PartialEq, Eq)]
+struct EntryName<'a> {
+    day: u8,
+    name: &'a str,
+    version: u32,
+}
+
+impl<'a> EntryName<'a> {
+    fn parse(file_name: &'a str) -> Option<Self> {
+        let stem = file_name.strip_suffix(".txt")?;
+        let mut parts = stem.rsplitn(3, '-');
+        let hash = parts.next()?;
+        let version = parts.next()?.strip_prefix('v')?.parse().ok()?;
+        let (day, name) = parts.next()?.strip_prefix("day")?.split_once('-')?;
+        if hash.len() != 16 || !hash.bytes().all(|b| b.is_ascii_hexdigit()) || name.is_empty() {
+            return None;
+        }
+        Some(Self {
+            day: day.parse().ok()?,
+            name,
+            version,
+        })
+    }
+}
+
+/// 64-bit FNV-1a. Unlike std's hasher, it's guaranteed to give the same hash in every build, which
+/// the file names depend on.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+/// 128-bit FNV-1a, so that telling inputs apart doesn't rest on 64 bits alone.
+fn fnv1a_128(bytes: &[u8]) -> u128 {
+    const OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+    const PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+    bytes.iter().fold(OFFSET_BASIS, |hash, &byte| {
+        (hash ^ u128::from(byte)).wrapping_mul(PRIME)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Day, BUILTIN};
+
+    struct Versioned(&'static str, u32);
+
+    impl Day for Versioned {
+        type Parsed<'a> = &'a str;
+        type Part1 = usize;
+        type Part2 = String;
+
+        fn day(&self) -> u8 {
+            3
+        }
+
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn version(&self) -> u32 {
+            self.1
+        }
+
+        fn parse<'a>(&self, input: &'a str) -> anyhow::Result<&'a str> {
+            Ok(input)
+        }
+
+        fn part1(&self, input: &&str) -> anyhow::Result<usize> {
+            Ok(input.len())
+        }
+
+        fn part2(&self, _: &&str) -> anyhow::Result<String> {
+            Ok(String::new())
+        }
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_128(b""), 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d);
+        assert_eq!(fnv1a_128(b"a"), 0xd228_cb69_6f1a_8caf_7891_2b70_4e4a_8964);
+    }
+
+    #[test]
+    fn test_entry_name() {
+        let expected = EntryName {
+            day: 15,
+            name: "flat-fast",
+            version: 2,
+        };
+
+        assert_eq!(
+            EntryName::parse("day15-flat-fast-v2-0123456789abcdef.txt"),
+            Some(expected)
+        );
+        assert_eq!(EntryName::parse("day15-flat-v2.txt"), None);
+        assert_eq!(EntryName::parse("day15--v2-0123456789abcdef.txt"), None);
+        assert_eq!(EntryName::parse("notes.txt"), None);
+    }
+
+    /// A fresh cache directory for one test.
+    fn temp_dir(test: &str) -> PathBuf {
+        env::temp_dir().join(format!("aoc2020-cache-{}-{}", test, std::process::id()))
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = temp_dir("round-trip");
+        let cache = Cache::new(&dir);
+        let (v1, v2) = (Versioned(BUILTIN, 1), Versioned(BUILTIN, 2));
+        let solution: Solution = (7, String::new()).into();
+
+        let empty = cache.get(&v1, b"..#\n");
+        cache.put(&v1, b"..#\n", &solution).unwrap();
+        let hit = cache.get(&v1, b"..#\n");
+        let other_input = cache.get(&v1, b"#..\n");
+        let other_version = cache.get(&v2, b"..#\n");
+        cache.put(&v2, b"..#\n", &solution).unwrap();
+        let pruned = cache.get(&v1, b"..#\n");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(empty, None);
+        assert_eq!(hit, Some(solution));
+        assert_eq!(other_input, None);
+        assert_eq!(other_version, None);
+        assert_eq!(pruned, None);
+    }
+
+    #[test]
+    fn test_prune_exact_name() {
+        let dir = temp_dir("prune");
+        let cache = Cache::new(&dir);
+        let (flat, flat_fast) = (Versioned("flat", 1), Versioned("flat-fast", 1));
+        let solution: Solution = (7, String::new()).into();
+
+        cache.put(&flat_fast, b"..#\n", &solution).unwrap();
+        cache.put(&flat, b"..#\n", &solution).unwrap();
+        cache
+            .put(&Versioned("flat", 2), b"..#\n", &solution)
+            .unwrap();
+        let kept = cache.get(&flat_fast, b"..#\n");
+        let pruned = cache.get(&flat, b"..#\n");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(kept, Some(solution));
+        assert_eq!(pruned, None);
+    }
+
+    #[test]
+    fn test_hash_collision() {
+        let dir = temp_dir("collision");
+        let cache = Cache::new(&dir);
+        let solver = Versioned(BUILTIN, 1);
+        let solution: Solution = (7, String::new()).into();
+
+        // Stand in for another input whose 64-bit hash matches, by swapping the entry's identity.
+        cache.put(&solver, b"..#\n", &solution).unwrap();
+        let path = cache.path(&solver, b"..#\n");
+        let contents = fs::read_to_string(&path).unwrap();
+        let contents = contents.replacen(&input_identity(b"..#\n"), &input_identity(b"#.."), 1);
+        fs::write(&path, contents).unwrap();
+        let collided = cache.get(&solver, b"..#\n");
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(collided, None);
+    }
+}
@@ -0,0 +1,126 @@
+//! The user's config file, for settings that would be a chore to pass on every run.
+//!
+//! It lives at `$AOC_CONFIG` if that's set, otherwise `$XDG_CONFIG_HOME/aoc2020/config` (or
+//! `~/.config/aoc2020/config`), and holds `key = value` lines:
+//!
+//! ```text
+//! # Where my puzzle inputs live.
+//! input-dir = ~/advent/2020
+//! ```
+//!
+//! Blank lines and lines starting with `#` are ignored. A missing file is the same as an empty one.
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+#[derive(Debug, Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+    /// Relative paths in the file are relative to the directory holding it.
+    directory: PathBuf,
+}
+
+impl Config {
+    /// Read the config file, if there is one.
+    pub fn load() -> anyhow::Result<Self> {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => {
+                return Err(error).with_context(|| format!("Unable to read {}", path.display()))
+            }
+        };
+
+        let mut config = Self::parse(&contents)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config.directory = path.parent().map(Path::to_owned).unwrap_or_default();
+        Ok(config)
+    }
+
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut values = HashMap::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some(pair) => pair,
+                None => bail!("Expected 'key = value', found '{}'", line),
+            };
+            values.insert(key.trim().to_owned(), value.trim().to_owned());
+        }
+
+        Ok(Self {
+            values,
+            directory: PathBuf::new(),
+        })
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    /// Like [`Config::get`], but for a path: `~` is expanded and relative paths are resolved
+    /// against the config file's directory.
+    pub fn get_path(&self, key: &str) -> Option<PathBuf> {
+        let value = self.get(key)?;
+        let path = match (value.strip_prefix("~/"), env::var_os("HOME")) {
+            (Some(rest), Some(home)) => Path::new(&home).join(rest),
+            _ => PathBuf::from(value),
+        };
+
+        Some(self.directory.join(path))
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    if let Some(path) = env::var_os("AOC_CONFIG") {
+        return Some(PathBuf::from(path));
+    }
+
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) => PathBuf::from(dir),
+        None => Path::new(&env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("aoc2020").join("config"))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse("# comment\n\ninput-dir = inputs \nprofile=real").unwrap();
+
+        assert_eq!(config.get("input-dir"), Some("inputs"));
+        assert_eq!(config.get("profile"), Some("real"));
+        assert_eq!(config.get("missing"), None);
+        assert!(Config::parse("input-dir inputs").is_err());
+    }
+
+    #[test]
+    fn test_get_path() {
+        let mut config = Config::parse("relative = inputs\nabsolute = /srv/inputs").unwrap();
+        config.directory = PathBuf::from("/home/elf/.config/aoc2020");
+
+        assert_eq!(
+            config.get_path("relative"),
+            Some(PathBuf::from("/home/elf/.config/aoc2020/inputs"))
+        );
+        assert_eq!(
+            config.get_path("absolute"),
+            Some(PathBuf::from("/srv/inputs"))
+        );
+    }
+}
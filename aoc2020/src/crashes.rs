@@ -0,0 +1,89 @@
+//! Replays every input under `fuzz/crashes` against every built-in implementation of its day.
+//!
+//! Each file is named `dayNN-<what it does>.txt` and once made that day panic or hang. They are
+//! kept so the fixes stay fixed: the solver must now return, quickly, with either an answer or an
+//! error. `fuzz/CRASHES.md` has the story behind each one.
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+use crate::impls::Registry;
+
+const CRASH_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fuzz/crashes");
+
+/// Generous, since tests run unoptimised, but far short of what a hang would take.
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Every crash file, along with the day its name starts with.
+fn discover(directory: &Path) -> Vec<(u8, PathBuf)> {
+    let mut crashes: Vec<_> = fs::read_dir(directory)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let day = entry
+                .file_name()
+                .to_str()
+                .and_then(|name| name.strip_prefix("day"))
+                .and_then(|name| name.get(..2))
+                .and_then(|day| day.parse().ok())?;
+            Some((day, entry.path()))
+        })
+        .collect();
+
+    crashes.sort();
+    crashes
+}
+
+fn run(day: u8, path: &Path) -> Result<(), String> {
+    let input = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let (sender, receiver) = mpsc::channel();
+
+    // A hung solver can't be stopped, so it's left behind on its own thread.
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let registry = Registry::builtin();
+            let implementations = registry.day(day);
+            if implementations.is_empty() {
+                return Err(format!("No solver for day {}", day));
+            }
+            // Whether they answer or report an error doesn't matter, only that they return.
+            for implementation in implementations {
+                let _ = implementation.solve(&input);
+            }
+            Ok(())
+        }))
+        .unwrap_or_else(|_| Err("Solver panicked".to_owned()));
+        let _ = sender.send(result);
+    });
+
+    receiver
+        .recv_timeout(TIMEOUT)
+        .unwrap_or_else(|_| Err(format!("Solver still running after {:?}", TIMEOUT)))
+}
+
+#[test]
+fn crashes() {
+    let crashes = discover(Path::new(CRASH_DIR));
+    assert!(!crashes.is_empty(), "No crashes found in {}", CRASH_DIR);
+
+    let failures: Vec<String> = crashes
+        .iter()
+        .filter_map(|(day, path)| {
+            run(*day, path)
+                .err()
+                .map(|e| format!("{}: {}", path.display(), e))
+        })
+        .collect();
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} crashes are back:\n{}",
+        failures.len(),
+        crashes.len(),
+        failures.join("\n")
+    );
+}
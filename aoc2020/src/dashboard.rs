@@ -0,0 +1,530 @@
+//! An interactive terminal dashboard for running every day and inspecting the results.
+//!
+//! Each day is listed with its status, answers and time. Up and down (or `k` and `j`) pick a day,
+//! `r` or enter runs it again, `a` runs every day and `q` or escape quits. The side pane shows the
+//! picked day's error, or its [explanation](crate::explain) if it has one.
+//!
+//! Days are solved on a worker thread, so the dashboard stays responsive while a slow one runs.
+//! Inputs are checked for changes every second, and a day whose input changed is run again.
+use std::fmt;
+use std::io::{self, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, queue, style, terminal};
+
+use crate::explain::Explanation;
+use crate::inputs::{Answers, SearchPath};
+use crate::solution::{Solution, Timings};
+use crate::Solver;
+
+/// How often inputs are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The worker's name, so that its panics can be kept off the screen.
+const WORKER: &str = "dashboard-worker";
+
+/// How wide the list of days is. The side pane gets the rest.
+const LIST_WIDTH: usize = 66;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    NotRun,
+    Running,
+    Pass,
+    /// Solved, but there are no recorded answers to check against.
+    Solved,
+    /// An error, or an answer that doesn't match the recorded one.
+    Fail,
+    Panic,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::NotRun => "not run",
+            Self::Running => "running",
+            Self::Pass => "pass",
+            Self::Solved => "solved",
+            Self::Fail => "FAIL",
+            Self::Panic => "PANIC",
+        };
+        f.pad(text)
+    }
+}
+
+/// A day to solve, sent to the worker.
+struct Job {
+    index: usize,
+    day: u8,
+    input: String,
+}
+
+/// What became of a [`Job`].
+struct Done {
+    index: usize,
+    outcome: Outcome,
+}
+
+enum Outcome {
+    /// The answers, timings and explanation, which is empty if the day has none.
+    Solved(Solution, Timings, String),
+    Error(String),
+    Panic(String),
+}
+
+struct Row {
+    day: u8,
+    status: Status,
+    solution: Option<Solution>,
+    timings: Option<Timings>,
+    /// The error or explanation for the side pane.
+    detail: String,
+    /// The input file and when it was last modified, as of the last check.
+    input: Option<(PathBuf, SystemTime)>,
+}
+
+struct Dashboard {
+    rows: Vec<Row>,
+    selected: usize,
+    search_path: SearchPath,
+    answers: Answers,
+    jobs: Sender<Job>,
+}
+
+impl Dashboard {
+    fn new(days: &[u8], search_path: SearchPath, jobs: Sender<Job>) -> anyhow::Result<Self> {
+        let rows = days
+            .iter()
+            .map(|&day| Row {
+                day,
+                status: Status::NotRun,
+                solution: None,
+                timings: None,
+                detail: String::new(),
+                input: input_stamp(&search_path, day),
+            })
+            .collect();
+
+        Ok(Self {
+            rows,
+            selected: 0,
+            answers: search_path.answers()?,
+            search_path,
+            jobs,
+        })
+    }
+
+    fn select(&mut self, offset: isize) {
+        let last = self.rows.len().saturating_sub(1) as isize;
+        self.selected = (self.selected as isize + offset).clamp(0, last) as usize;
+    }
+
+    /// Send row `index`'s day to the worker, unless it's already running.
+    fn run(&mut self, index: usize) {
+        let row = &mut self.rows[index];
+        if row.status == Status::Running {
+            return;
+        }
+
+        row.input = input_stamp(&self.search_path, row.day);
+        match self.search_path.read(row.day) {
+            Ok(input) => {
+                row.status = Status::Running;
+                let job = Job {
+                    index,
+                    day: row.day,
+                    input,
+                };
+                if self.jobs.send(job).is_err() {
+                    row.status = Status::Fail;
+                    row.detail = "The worker has stopped".to_owned();
+                }
+            }
+            Err(error) => {
+                row.status = Status::Fail;
+                row.detail = format!("{:#}", error);
+            }
+        }
+    }
+
+    fn run_all(&mut self) {
+        for index in 0..self.rows.len() {
+            self.run(index);
+        }
+    }
+
+    fn finish(&mut self, done: Done) {
+        let row = &mut self.rows[done.index];
+        match done.outcome {
+            Outcome::Solved(solution, timings, explanation) => {
+                let wrong = self.answers.check(row.day, &solution);
+                row.status = match &wrong {
+                    Some(wrong) if wrong.is_empty() => Status::Pass,
+                    Some(_) => Status::Fail,
+                    None => Status::Solved,
+                };
+                row.detail = String::new();
+                for part in wrong.unwrap_or_default() {
+                    row.detail += &format!(
+                        "Part {} doesn't match the recorded answer {}\n",
+                        part,
+                        self.answers.get(row.day, part).unwrap_or_default()
+                    );
+                }
+                row.detail += &explanation;
+                row.solution = Some(solution);
+                row.timings = Some(timings);
+            }
+            Outcome::Error(error) => {
+                row.status = Status::Fail;
+                row.detail = error;
+                row.solution = None;
+                row.timings = None;
+            }
+            Outcome::Panic(message) => {
+                row.status = Status::Panic;
+                row.detail = message;
+                row.solution = None;
+                row.timings = None;
+            }
+        }
+    }
+
+    /// Run every day whose input has changed since it was last looked at.
+    fn rerun_changed(&mut self) {
+        let changed: Vec<usize> = (0..self.rows.len())
+            .filter(|&index| {
+                let row = &self.rows[index];
+                row.status != Status::Running
+                    && input_stamp(&self.search_path, row.day) != row.input
+            })
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        // The recorded answers may well have changed along with the inputs.
+        if let Ok(answers) = self.search_path.answers() {
+            self.answers = answers;
+        }
+        for index in changed {
+            self.run(index);
+        }
+    }
+
+    /// The whole screen, as `height` lines of exactly `width` characters.
+    fn render(&self, width: usize, height: usize) -> Vec<String> {
+        let list_width = LIST_WIDTH.min(width);
+        let pane_width = width.saturating_sub(list_width + 3);
+
+        let mut list = vec![format!(
+            "  Day  {:8} {:20} {:20} {:>8}",
+            "Status", "Part 1", "Part 2", "Time"
+        )];
+        for (index, row) in self.rows.iter().enumerate() {
+            let marker = if index == self.selected { '>' } else { ' ' };
+            let (part1, part2) = match &row.solution {
+                Some(solution) => (solution.part1(), solution.part2()),
+                None => ("", ""),
+            };
+            let time = row
+                .timings
+                .map(|timings| format_duration(timings.total()))
+                .unwrap_or_default();
+            list.push(format!(
+                "{} {:02}   {:8} {:20} {:20} {:>8}",
+                marker,
+                row.day,
+                row.status,
+                truncate(part1, 20),
+                truncate(part2, 20),
+                time
+            ));
+        }
+
+        let row = &self.rows[self.selected];
+        let mut pane = vec![format!("Day {:02}: {}", row.day, row.status)];
+        if let Some((path, _)) = &row.input {
+            pane.push(path.display().to_string());
+        }
+        pane.push(String::new());
+        for line in row.detail.lines() {
+            pane.extend(wrap(line, pane_width));
+        }
+
+        let footer = "up/down pick  r run  a run all  q quit";
+        let body = height.saturating_sub(1);
+        let mut lines: Vec<String> = (0..body)
+            .map(|i| {
+                let left = pad(list.get(i).map_or("", String::as_str), list_width);
+                let right = pane.get(i).map_or("", String::as_str);
+                let line = format!("{} | {}", left, right);
+                pad(&line, width)
+            })
+            .collect();
+        if height > 0 {
+            lines.push(pad(footer, width));
+        }
+        lines
+    }
+}
+
+/// Where day `day`'s input is and when it last changed, if it can be found.
+fn input_stamp(search_path: &SearchPath, day: u8) -> Option<(PathBuf, SystemTime)> {
+    let path = search_path.find(day).ok()?;
+    let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+    Some((path, modified))
+}
+
+fn format_duration(duration: Duration) -> String {
+    match duration.as_micros() {
+        micros if micros < 1000 => format!("{}us", micros),
+        micros if micros < 1_000_000 => format!("{:.1}ms", micros as f64 / 1000.0),
+        micros => format!("{:.2}s", micros as f64 / 1_000_000.0),
+    }
+}
+
+/// `text` cut down to `width` characters, marking the cut with `~`.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_owned();
+    }
+    let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+    cut.push('~');
+    cut
+}
+
+/// `text` padded with spaces, or cut, to exactly `width` characters.
+fn pad(text: &str, width: usize) -> String {
+    let cut: String = text.chars().take(width).collect();
+    format!("{:width$}", cut, width = width)
+}
+
+/// `line` split into pieces no wider than `width`.
+fn wrap(line: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = line.chars().collect();
+    if chars.is_empty() || width == 0 {
+        return vec![String::new()];
+    }
+    chars
+        .chunks(width)
+        .map(|chunk| chunk.iter().collect())
+        .collect()
+}
+
+/// Start the thread that solves days, handing it the solvers.
+fn spawn_worker(solvers: Vec<Box<dyn Solver>>) -> io::Result<(Sender<Job>, Receiver<Done>)> {
+    let (job_sender, jobs) = mpsc::channel::<Job>();
+    let (done_sender, done) = mpsc::channel();
+
+    thread::Builder::new()
+        .name(WORKER.to_owned())
+        .spawn(move || {
+            for job in jobs {
+                let outcome = match solvers.iter().find(|solver| solver.day() == job.day) {
+                    Some(solver) => solve(&**solver, &job.input),
+                    None => Outcome::Error(format!("No solver for day {:02}", job.day)),
+                };
+                let done = Done {
+                    index: job.index,
+                    outcome,
+                };
+                if done_sender.send(done).is_err() {
+                    break;
+                }
+            }
+        })?;
+
+    Ok((job_sender, done))
+}
+
+fn solve(solver: &dyn Solver, input: &str) -> Outcome {
+    let result = panic::catch_unwind(AssertUnwindSafe(|| -> anyhow::Result<_> {
+        let (solution, timings) = solver.solve_timed(input)?;
+        let mut explanation = Explanation::new();
+        if let Some(explainer) = solver.explainer() {
+            explainer.explain(input, &mut explanation)?;
+        }
+        Ok((solution, timings, explanation.to_string()))
+    }));
+
+    match result {
+        Ok(Ok((solution, timings, explanation))) => Outcome::Solved(solution, timings, explanation),
+        Ok(Err(error)) => Outcome::Error(format!("{:#}", error)),
+        Err(payload) => {
+            let message = match (
+                payload.downcast_ref::<&str>(),
+                payload.downcast_ref::<String>(),
+            ) {
+                (Some(message), _) => message.to_string(),
+                (_, Some(message)) => message.clone(),
+                _ => "Solver panicked".to_owned(),
+            };
+            Outcome::Panic(message)
+        }
+    }
+}
+
+/// Puts the terminal in raw mode on an alternate screen, and puts it back when dropped.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn draw(stdout: &mut impl Write, dashboard: &Dashboard) -> io::Result<()> {
+    let (width, height) = terminal::size()?;
+    for (y, line) in (0..).zip(dashboard.render(width.into(), height.into())) {
+        queue!(stdout, cursor::MoveTo(0, y), style::Print(line))?;
+    }
+    stdout.flush()
+}
+
+/// Show the dashboard for `solvers` until it's quit.
+pub fn run(solvers: Vec<Box<dyn Solver>>, search_path: SearchPath) -> anyhow::Result<()> {
+    let days: Vec<u8> = solvers.iter().map(|solver| solver.day()).collect();
+    let (jobs, done) = spawn_worker(solvers)?;
+    let mut dashboard = Dashboard::new(&days, search_path, jobs)?;
+
+    // A panicking solver is reported in its row. Printing it as well would scribble on the screen.
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if thread::current().name() != Some(WORKER) {
+            default_hook(info);
+        }
+    }));
+
+    let screen = Screen::enter()?;
+    let mut stdout = io::stdout();
+    let mut last_poll = Instant::now();
+    loop {
+        while let Ok(done) = done.try_recv() {
+            dashboard.finish(done);
+        }
+        draw(&mut stdout, &dashboard)?;
+
+        if event::poll(Duration::from_millis(100))? {
+            match event::read()? {
+                Event::Key(key) if key.kind != KeyEventKind::Release => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Up | KeyCode::Char('k') => dashboard.select(-1),
+                    KeyCode::Down | KeyCode::Char('j') => dashboard.select(1),
+                    KeyCode::Enter | KeyCode::Char('r') => dashboard.run(dashboard.selected),
+                    KeyCode::Char('a') => dashboard.run_all(),
+                    _ => {}
+                },
+                Event::Resize(..) => execute!(stdout, terminal::Clear(terminal::ClearType::All))?,
+                _ => {}
+            }
+        }
+
+        if last_poll.elapsed() >= POLL_INTERVAL {
+            dashboard.rerun_changed();
+            last_poll = Instant::now();
+        }
+    }
+
+    drop(screen);
+    let _ = panic::take_hook();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::get_solvers;
+
+    fn dashboard(days: &[u8]) -> (Dashboard, Receiver<Job>) {
+        let (jobs, received) = mpsc::channel();
+        let search_path = SearchPath::new(None).unwrap();
+        (Dashboard::new(days, search_path, jobs).unwrap(), received)
+    }
+
+    #[test]
+    fn test_run_and_finish() {
+        let (mut dashboard, jobs) = dashboard(&[1, 2]);
+        if dashboard.rows[0].input.is_none() {
+            eprintln!("Skipping test, no input for day 01");
+            return;
+        }
+
+        dashboard.run(0);
+        dashboard.run(0);
+        let job = jobs.try_recv().unwrap();
+        assert_eq!((job.index, job.day), (0, 1));
+        assert!(jobs.try_recv().is_err(), "A running day was sent again");
+        assert_eq!(dashboard.rows[0].status, Status::Running);
+
+        let solvers = get_solvers();
+        dashboard.finish(Done {
+            index: 0,
+            outcome: solve(&*solvers[0], &job.input),
+        });
+        assert_ne!(dashboard.rows[0].status, Status::Running);
+        assert!(dashboard.rows[0].solution.is_some());
+
+        dashboard.finish(Done {
+            index: 1,
+            outcome: Outcome::Panic("index out of bounds".to_owned()),
+        });
+        assert_eq!(dashboard.rows[1].status, Status::Panic);
+        assert_eq!(dashboard.rows[1].detail, "index out of bounds");
+    }
+
+    #[test]
+    fn test_render() {
+        let (mut dashboard, _jobs) = dashboard(&[1, 2]);
+        dashboard.rows[1].input = None;
+        dashboard.finish(Done {
+            index: 1,
+            outcome: Outcome::Error("Invalid password policy: 1-3 a".to_owned()),
+        });
+        dashboard.select(5);
+
+        let screen = dashboard.render(90, 6);
+        let pane: Vec<&str> = screen
+            .iter()
+            .map(|line| line[LIST_WIDTH..].trim_end())
+            .collect();
+
+        assert_eq!(screen.len(), 6);
+        assert!(screen.iter().all(|line| line.chars().count() == 90));
+        assert!(screen[1].starts_with("  01   not run"));
+        assert!(screen[2].starts_with("> 02   FAIL"));
+        assert_eq!(
+            pane[..4],
+            [
+                " | Day 02: FAIL",
+                " |",
+                " | Invalid password poli",
+                " | cy: 1-3 a"
+            ]
+        );
+        assert!(screen[5].starts_with("up/down pick"));
+    }
+
+    #[test]
+    fn test_helpers() {
+        assert_eq!(truncate("rbpg,xvtrfz,tgmzqjz", 8), "rbpg,xv~");
+        assert_eq!(truncate("1018336", 8), "1018336");
+        assert_eq!(wrap("abcdefg", 3), vec!["abc", "def", "g"]);
+        assert_eq!(format_duration(Duration::from_micros(1500)), "1.5ms");
+        assert_eq!(format_duration(Duration::from_secs(2)), "2.00s");
+    }
+}
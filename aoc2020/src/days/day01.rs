@@ -1,3 +1,12 @@
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+use anyhow::{bail, ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::inputs;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 const TARGET: usize = 2020;
@@ -12,115 +21,188 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+/// The expenses, both as a lookup table and in ascending order.
+pub(crate) struct Expenses {
+    present: [bool; TARGET],
+    sorted: Vec<usize>,
+}
+
+impl crate::Day for Solver {
+    type Parsed<'a> = Expenses;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         1
     }
 
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789"),
+            ..Shape::default()
+        }
+    }
+
     // Use a `[bool; 2020]` array to mark which values are present.
     // We can trivially sort them by traversing the array in order and inserting present values.
-    // This allows us to break early from the inner loop as soon as `a + 2 * b > 2020`.
-    fn solve(&self, input: &str) -> Solution {
-        let mut solution = (0, 0);
+    fn parse(&self, input: &str) -> anyhow::Result<Expenses> {
+        self.parse_bytes(input.as_bytes())
+    }
 
-        let mut nums = [false; 2020];
-
-        let mut num = 0;
-        let mut count = 0;
-        for c in input.bytes() {
-            if c == b'\n' {
-                nums[num] = true;
-                num = 0;
-                count += 1;
-            } else {
-                num = num * 10 + (c - b'0') as usize;
-            }
+    fn parse_bytes(&self, input: &[u8]) -> anyhow::Result<Expenses> {
+        let mut present = [false; TARGET];
+
+        for line in inputs::lines(input) {
+            let num = inputs::parse_unsigned(line)
+                .and_then(|num| usize::try_from(num).ok())
+                .with_context(|| format!("Invalid expense '{}'", String::from_utf8_lossy(line)))?;
+            ensure!(num < TARGET, "Expense {} is over {}", num, TARGET);
+            ensure!(!present[num], "Expense {} is listed twice", num);
+            present[num] = true;
         }
 
-        let mut num_list = [0; 2020];
+        let sorted = (0..TARGET).filter(|&n| present[n]).collect();
+        Ok(Expenses { present, sorted })
+    }
 
-        let mut i = 0;
-        for (n, b) in nums.iter().enumerate() {
-            if *b {
-                num_list[i] = n as usize;
-                i += 1;
-            }
-        }
+    fn part1(&self, expenses: &Expenses) -> anyhow::Result<usize> {
+        expenses
+            .sorted
+            .iter()
+            // An expense of 0 would need a partner of exactly 2020.
+            .find(|&&a| expenses.present.get(TARGET - a) == Some(&true))
+            .map(|&a| a * (TARGET - a))
+            .with_context(|| format!("No two expenses add up to {}", TARGET))
+    }
 
-        debug_assert!(i == count);
+    // The sorted list allows us to break early from the inner loop as soon as `a + 2 * b > 2020`.
+    fn part2(&self, expenses: &Expenses) -> anyhow::Result<usize> {
+        let sorted = &expenses.sorted;
 
-        'outer: for (i, &a) in num_list.iter().enumerate() {
+        for (i, &a) in sorted.iter().enumerate() {
             let target = TARGET - a;
-            if nums[target as usize] {
-                solution.0 = a * target;
-                if solution.1 != 0 {
-                    break 'outer;
-                }
-            }
-
-            for &b in num_list[i + 1..].iter() {
+            for &b in &sorted[i + 1..] {
                 if 2 * b > target {
                     break;
                 }
                 let target2 = target - b;
-                if nums[target2 as usize] {
-                    solution.1 = a * b * target2;
-                    if solution.0 != 0 {
-                        break 'outer;
-                    }
+                if expenses.present[target2] {
+                    return Ok(a * b * target2);
                 }
             }
         }
 
-        solution.into()
+        bail!("No three expenses add up to {}", TARGET)
     }
-}
 
-#[cfg(test)]
-mod test {
-    #[allow(unused_imports)]
-    use super::*;
-    use crate::solution::Solution;
-    use crate::Solver;
-
-    #[test]
-    fn example_part1() {
-        let input = "1721\n979\n366\n299\n675\n1456\n";
-        let solver = super::Solver::new();
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
 
-        let expected = 514_579.to_string();
-        let actual = solver.solve(&input);
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
 
-        assert_eq!(actual.part1(), expected)
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        200
     }
 
-    #[test]
-    fn example_part2() {
-        let input = "1721\n979\n366\n299\n675\n1456\n";
-        let solver = super::Solver::new();
+    /// `size` distinct expenses with exactly one pair and one triple summing to 2020.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let mut expenses = loop {
+            let a = rng.range(1..TARGET / 2);
+            let b = rng.range(1..TARGET / 3);
+            let c = rng.range(b + 1..(TARGET - b).div_ceil(2));
+            let expenses = vec![a, TARGET - a, b, c, TARGET - b - c];
+            if count_sums(&expenses) == (1, 1) {
+                break expenses;
+            }
+        };
 
-        let expected = 241_861_950.to_string();
-        let actual = solver.solve(&input);
+        let mut present: HashSet<usize> = expenses.iter().copied().collect();
+        for _ in 0..100 * size {
+            if expenses.len() >= size {
+                break;
+            }
+            let x = rng.range(1..TARGET);
+            let makes_sum = present.contains(&x)
+                || 2 * x == TARGET
+                || present.iter().any(|&y| {
+                    let rest = TARGET.checked_sub(x + y);
+                    x + y == TARGET
+                        || TARGET.checked_sub(2 * x) == Some(y)
+                        || TARGET.checked_sub(x + 2 * y) == Some(0)
+                        || rest.is_some_and(|z| present.contains(&z))
+                });
+            if !makes_sum {
+                expenses.push(x);
+                present.insert(x);
+            }
+        }
 
-        assert_eq!(actual.part2(), expected)
+        rng.shuffle(&mut expenses);
+        expenses.iter().map(|e| format!("{}\n", e)).collect()
     }
+}
 
-    fn helper() -> Solution {
-        let solver = super::Solver::new();
+impl Reference for Solver {
+    /// Try every pair and every triple.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let nums: Vec<usize> = input.lines().map(|line| line.parse().unwrap()).collect();
+        let mut solution = (0, 0);
 
-        let input_path = crate::input_path(1, None);
-        let input_string = match std::fs::read_to_string(input_path) {
-            Ok(inp) => crate::add_newline(inp),
-            Err(error) => panic!("Error while reading input file: {}", error),
-        };
-        let actual = solver.solve(&input_string);
+        for (i, a) in nums.iter().enumerate() {
+            for (j, b) in nums.iter().enumerate().skip(i + 1) {
+                if a + b == TARGET {
+                    solution.0 = a * b;
+                }
+                for c in &nums[j + 1..] {
+                    if a + b + c == TARGET {
+                        solution.1 = a * b * c;
+                    }
+                }
+            }
+        }
+
+        solution.into()
+    }
+}
 
-        actual
+/// Number of pairs and triples summing to the target, counting ones that reuse an expense.
+fn count_sums(expenses: &[usize]) -> (usize, usize) {
+    let mut pairs = 0;
+    let mut triples = 0;
+    for (i, &a) in expenses.iter().enumerate() {
+        for (j, &b) in expenses.iter().enumerate().skip(i) {
+            if a + b == TARGET {
+                pairs += 1;
+            }
+            for &c in &expenses[j..] {
+                if a + b + c == TARGET {
+                    triples += 1;
+                }
+            }
+        }
     }
+    (pairs, triples)
+}
+
+#[cfg(test)]
+mod test {
+    #[allow(unused_imports)]
+    use super::*;
+    use crate::solution::Solution;
+    use crate::Solver;
 
     #[test]
     fn verify() {
-        let actual = helper();
+        let solver = super::Solver::new();
+        let input = require_input!(1);
+
+        let actual = solver.solve(&input).unwrap();
         let expected: Solution = (1_018_336, 288_756_720).into();
 
         assert_eq!(actual, expected)
@@ -1,3 +1,10 @@
+use std::convert::TryFrom;
+
+use anyhow::{ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,42 +17,116 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<PasswordRules<'a>>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         2
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let part1 = part1(input);
-        let part2 = part2(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789abcdefghijklmnopqrstuvwxyz-: "),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Vec<PasswordRules<'a>>> {
+        parse_password_rules(input)
+    }
+
+    fn part1(&self, rules: &Vec<PasswordRules<'_>>) -> anyhow::Result<usize> {
+        Ok(part1(rules))
+    }
 
-        (part1, part2).into()
+    fn part2(&self, rules: &Vec<PasswordRules<'_>>) -> anyhow::Result<usize> {
+        Ok(part2(rules))
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-fn part1(input: &str) -> usize {
-    let pr = parse_password_rules(input);
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        1000
+    }
 
-    pr.iter().filter(|entry| entry.is_valid_part1()).count()
+    /// `size` password policies, each with a password long enough for both interpretations.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let letter = (b'a' + rng.range(0..26) as u8) as char;
+                let min = rng.range(1..10);
+                let max = rng.range(min + 1..min + 10);
+                let len = rng.range(max..max + 8);
+                let password: String = (0..len)
+                    .map(|_| {
+                        if rng.chance(0.4) {
+                            letter
+                        } else {
+                            (b'a' + rng.range(0..26) as u8) as char
+                        }
+                    })
+                    .collect();
+                format!("{}-{} {}: {}\n", min, max, letter, password)
+            })
+            .collect()
+    }
+}
+
+impl Reference for Solver {
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut solution = (0, 0);
+
+        for line in input.lines() {
+            let (policy, password) = line.split_once(": ").unwrap();
+            let (range, letter) = policy.split_once(' ').unwrap();
+            let (low, high) = range.split_once('-').unwrap();
+            let (low, high): (usize, usize) = (low.parse().unwrap(), high.parse().unwrap());
+            let letter = letter.chars().next().unwrap();
+
+            let count = password.chars().filter(|&c| c == letter).count();
+            if low <= count && count <= high {
+                solution.0 += 1;
+            }
+
+            let at = |position: usize| password.chars().nth(position - 1) == Some(letter);
+            if at(low) != at(high) {
+                solution.1 += 1;
+            }
+        }
+
+        solution.into()
+    }
 }
 
-fn part2(input: &str) -> usize {
-    let pr = parse_password_rules(input);
+fn part1(pr: &[PasswordRules]) -> usize {
+    pr.iter().filter(|entry| entry.is_valid_part1()).count()
+}
 
+fn part2(pr: &[PasswordRules]) -> usize {
     pr.iter().filter(|entry| entry.is_valid_part2()).count()
 }
 
-// TODO: use `&str` for `letter` and `password`
-// using `&str` over `char` because the letter is always valid UTF-8, we don't need Unicode support.
+/// A line of the input, with the password borrowed from it.
 #[derive(Debug, PartialOrd, PartialEq)]
-struct PasswordRules {
+pub(crate) struct PasswordRules<'a> {
     min: usize,
     max: usize,
     letter: char,
-    password: String,
+    password: &'a str,
 }
 
-impl PasswordRules {
+impl PasswordRules<'_> {
     /// Line is valid if character appears between `min` and `max` times in the sequence.
     fn is_valid_part1(&self) -> bool {
         (self.min..=self.max).contains(&self.password.chars().filter(|c| *c == self.letter).count())
@@ -54,75 +135,58 @@ impl PasswordRules {
     /// Line is valid if character appears exactly *once* at the given indexes `min` and `max`
     /// (indices start at 1).
     fn is_valid_part2(&self) -> bool {
-        let x = self.password.as_bytes()[self.min - 1] as char;
-        let y = self.password.as_bytes()[self.max - 1] as char;
-        (x == self.letter) ^ (y == self.letter)
+        let x = self.password.chars().nth(self.min - 1);
+        let y = self.password.chars().nth(self.max - 1);
+        (x == Some(self.letter)) ^ (y == Some(self.letter))
     }
 }
 
-impl From<&str> for PasswordRules {
-    fn from(rule: &str) -> Self {
-        let pr = rule.split(['-', ':', ' '].as_ref()).collect::<Vec<&str>>();
-        let min = pr[0].parse::<usize>().unwrap();
-        let max = pr[1].parse::<usize>().unwrap();
-        let letter = pr[2].parse::<char>().unwrap();
+impl<'a> TryFrom<&'a str> for PasswordRules<'a> {
+    type Error = anyhow::Error;
 
-        Self {
+    fn try_from(rule: &'a str) -> anyhow::Result<Self> {
+        let mut pr = rule.split(['-', ':', ' '].as_ref());
+        let mut field = || {
+            pr.next()
+                .with_context(|| format!("Invalid password rule: '{}'", rule))
+        };
+        let (min, max, letter, _, password) = (field()?, field()?, field()?, field()?, field()?);
+        ensure!(pr.next().is_none(), "Invalid password rule: '{}'", rule);
+        let min = min.parse::<usize>().context("Invalid minimum")?;
+        let max = max.parse::<usize>().context("Invalid maximum")?;
+        let letter = letter.parse::<char>().context("Invalid letter")?;
+        // Part 2 reads these as positions, which start at 1.
+        ensure!(min >= 1 && max >= 1, "Invalid password rule: '{}'", rule);
+
+        Ok(Self {
             min,
             max,
             letter,
-            password: String::from(pr[4]),
-        }
+            password,
+        })
     }
 }
 
 /// Parse input into a Vec of PasswordRules
-fn parse_password_rules(input: &str) -> Vec<PasswordRules> {
-    // TODO: make more efficient
-    let mut pr_vec = Vec::new();
-
-    for line in input.lines() {
-        pr_vec.push(PasswordRules::from(line));
-    }
-
-    pr_vec
+fn parse_password_rules(input: &str) -> anyhow::Result<Vec<PasswordRules<'_>>> {
+    input.lines().map(PasswordRules::try_from).collect()
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc";
-
-    #[test]
-    fn example_part1() {
-        let solver = super::Solver::new();
-
-        let expected = 2.to_string();
-        let actual = solver.solve(&INPUT);
-
-        assert_eq!(actual.part1(), expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let solver = super::Solver::new();
-
-        let expected = 1.to_string();
-        let actual = solver.solve(&INPUT);
-
-        assert_eq!(actual.part2(), expected)
-    }
-
     #[test]
     fn parse_password_rules() {
+        let input = example_input(2, "example");
         let actual = {
             let mut a = Vec::with_capacity(3);
-            for line in INPUT.lines() {
-                a.push(PasswordRules::from(line))
+            for line in input.lines() {
+                a.push(PasswordRules::try_from(line).unwrap())
             }
             a
         };
@@ -132,19 +196,19 @@ mod test {
                 min: 1,
                 max: 3,
                 letter: 'a',
-                password: "abcde".to_owned(),
+                password: "abcde",
             },
             PasswordRules {
                 min: 1,
                 max: 3,
                 letter: 'b',
-                password: "cdefg".to_owned(),
+                password: "cdefg",
             },
             PasswordRules {
                 min: 2,
                 max: 9,
                 letter: 'c',
-                password: "ccccccccc".to_owned(),
+                password: "ccccccccc",
             },
         ];
 
@@ -159,7 +223,7 @@ mod test {
             min: 1,
             max: 3,
             letter: 'a',
-            password: "abcde".to_owned(),
+            password: "abcde",
         };
         let actual = pr.is_valid_part2();
 
@@ -169,10 +233,10 @@ mod test {
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day02.txt");
+        let input = require_input!(2);
 
         let expected: Solution = (569, 346).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
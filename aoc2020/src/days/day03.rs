@@ -1,3 +1,8 @@
+use anyhow::ensure;
+
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -13,21 +18,106 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = &'a str;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         3
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let part1 = count_trees(input, &SLOPE_PART_1);
-        let part2 = count_trees(input, &SLOPE_PART_2);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("#."),
+            grid: true,
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<&'a str> {
+        check_map(input)?;
+        Ok(input)
+    }
+
+    fn part1(&self, map: &&str) -> anyhow::Result<usize> {
+        Ok(count_trees(map, &SLOPE_PART_1))
+    }
+
+    fn part2(&self, map: &&str) -> anyhow::Result<usize> {
+        Ok(count_trees(map, &SLOPE_PART_2))
+    }
 
-        (part1, part2).into()
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        323
+    }
+
+    /// A 31 wide map of `size` rows.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size.max(3))
+            .map(|_| {
+                let mut row: String = (0..31)
+                    .map(|_| if rng.chance(0.2) { '#' } else { '.' })
+                    .collect();
+                row.push('\n');
+                row
+            })
+            .collect()
     }
 }
 
+impl Reference for Solver {
+    /// Walk each slope one step at a time, wrapping around the map's width. The starting square
+    /// doesn't count, since the toboggan never hits it.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let map: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+        let trees = |slopes: &[(usize, usize)]| -> usize {
+            let mut product = 1;
+            for &(right, down) in slopes {
+                let (mut x, mut y, mut count) = (right, down, 0);
+                while y < map.len() {
+                    if map[y][x % map[y].len()] == '#' {
+                        count += 1;
+                    }
+                    x += right;
+                    y += down;
+                }
+                product *= count;
+            }
+            product
+        };
+
+        (trees(&SLOPE_PART_1), trees(&SLOPE_PART_2)).into()
+    }
+}
+
+/// Make sure the map is a non-empty rectangle of `.` and `#`.
+fn check_map(input: &str) -> anyhow::Result<()> {
+    let line_width = input.lines().next().map_or(0, str::len);
+    ensure!(line_width > 0, "The map is empty");
+    for line in input.lines() {
+        ensure!(
+            line.len() == line_width && line.bytes().all(|b| b == b'.' || b == b'#'),
+            "Invalid map row: '{}'",
+            line
+        );
+    }
+
+    Ok(())
+}
+
 fn count_trees(input: &str, slopes: &[(usize, usize)]) -> usize {
-    let line_width = input.find('\n').unwrap();
+    let line_width = input.lines().next().map_or(0, str::len);
 
     slopes.iter().fold(1, |acc, &(x_step, y_step)| {
         acc * input
@@ -47,40 +137,13 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-..##.......
-#...#...#..
-.#....#..#.
-..#.#...#.#
-.#...##..#.
-..#.##.....
-.#.#.#....#
-.#........#
-#.##...#...
-#...##....#
-.#..#...#.#";
-
-    #[test]
-    fn example_part1() {
-        let expected = 7;
-        let actual = count_trees(INPUT, &SLOPE_PART_1);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let expected = 336;
-        let actual = count_trees(INPUT, &SLOPE_PART_2);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day03.txt");
+        let input = require_input!(3);
 
-        let expected: Solution = (240, 0).into();
-        let actual = solver.solve(input);
+        let expected: Solution = (240_usize, 2_832_009_600).into();
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,5 +1,9 @@
-use std::collections::HashMap;
+use anyhow::Context;
 
+use crate::generate::{Generate, Rng};
+use crate::hash::FastMap;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,46 +16,197 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<FastMap<&'a str, &'a str>>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         4
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let input = parse(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            alphabet: Some("0123456789abcdefghijklmnopqrstuvwxyz#: "),
+            ..Shape::default()
+        }
+    }
 
-        let part1 = part1(&input);
-        let part2 = part2(&input);
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Vec<FastMap<&'a str, &'a str>>> {
+        parse(input)
+    }
 
-        (part1, part2).into()
+    fn part1(&self, passports: &Vec<FastMap<&str, &str>>) -> anyhow::Result<usize> {
+        Ok(part1(passports))
+    }
+
+    fn part2(&self, passports: &Vec<FastMap<&str, &str>>) -> anyhow::Result<usize> {
+        Ok(part2(passports))
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-fn parse(input: &str) -> Vec<HashMap<&str, &str>> {
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        290
+    }
+
+    /// `size` passports, a mix of valid ones, ones missing fields and ones with invalid values.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let passports: Vec<String> = (0..size)
+            .map(|_| {
+                let present: Vec<&str> = REQUIRED
+                    .iter()
+                    .chain(&["cid"])
+                    .copied()
+                    .filter(|_| rng.chance(0.9))
+                    .collect();
+                let mut fields: Vec<String> = present
+                    .into_iter()
+                    .map(|field| {
+                        let value = if rng.chance(0.9) {
+                            valid_value(rng, field)
+                        } else {
+                            invalid_value(rng, field)
+                        };
+                        format!("{}:{}", field, value)
+                    })
+                    .collect();
+                if fields.is_empty() {
+                    fields.push(format!("cid:{}", rng.range(1..1000)));
+                }
+                rng.shuffle(&mut fields);
+
+                let mut passport = String::new();
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        passport.push(if rng.chance(0.3) { '\n' } else { ' ' });
+                    }
+                    passport.push_str(field);
+                }
+                passport
+            })
+            .collect();
+
+        passports.join("\n\n") + "\n"
+    }
+}
+
+impl Reference for Solver {
+    /// Check each rule as worded in the puzzle.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let in_range = |value: &str, digits: usize, low: u32, high: u32| {
+            value.len() == digits
+                && value.chars().all(|c| c.is_ascii_digit())
+                && (low..=high).contains(&value.parse().unwrap())
+        };
+        let mut solution = (0, 0);
+
+        for passport in input.split("\n\n") {
+            let fields: Vec<(&str, &str)> = passport
+                .split_whitespace()
+                .map(|field| field.split_once(':').unwrap())
+                .collect();
+            let value = |key: &str| {
+                fields
+                    .iter()
+                    .find(|field| field.0 == key)
+                    .map(|field| field.1)
+            };
+
+            if REQUIRED.iter().any(|&key| value(key).is_none()) {
+                continue;
+            }
+            solution.0 += 1;
+
+            let height = value("hgt").unwrap();
+            let hair = value("hcl").unwrap();
+            let valid = in_range(value("byr").unwrap(), 4, 1920, 2002)
+                && in_range(value("iyr").unwrap(), 4, 2010, 2020)
+                && in_range(value("eyr").unwrap(), 4, 2020, 2030)
+                && (height.ends_with("cm") && in_range(&height[..height.len() - 2], 3, 150, 193)
+                    || height.ends_with("in") && in_range(&height[..height.len() - 2], 2, 59, 76))
+                && hair.len() == 7
+                && hair.starts_with('#')
+                && hair[1..].chars().all(|c| "0123456789abcdef".contains(c))
+                && ["amb", "blu", "brn", "gry", "grn", "hzl", "oth"]
+                    .contains(&value("ecl").unwrap())
+                && in_range(value("pid").unwrap(), 9, 0, 999_999_999);
+            if valid {
+                solution.1 += 1;
+            }
+        }
+
+        solution.into()
+    }
+}
+
+fn valid_value(rng: &mut Rng, field: &str) -> String {
+    match field {
+        "byr" => rng.range(1920..2003).to_string(),
+        "iyr" => rng.range(2010..2021).to_string(),
+        "eyr" => rng.range(2020..2031).to_string(),
+        "hgt" if rng.chance(0.5) => format!("{}in", rng.range(59..77)),
+        "hgt" => format!("{}cm", rng.range(150..194)),
+        "hcl" => format!("#{:06x}", rng.range(0..0x100_0000)),
+        "ecl" => rng
+            .choose(&["amb", "blu", "brn", "gry", "grn", "hzl", "oth"])
+            .to_string(),
+        "pid" => format!("{:09}", rng.range(0..1_000_000_000)),
+        _ => rng.range(1..1000).to_string(),
+    }
+}
+
+fn invalid_value(rng: &mut Rng, field: &str) -> String {
+    match field {
+        "byr" => rng.range(1800..1920).to_string(),
+        "iyr" => rng.range(2021..2100).to_string(),
+        "eyr" => rng.range(1950..2020).to_string(),
+        "hgt" => match rng.range(0..3) {
+            0 => format!("{}in", rng.range(77..100)),
+            1 => format!("{}cm", rng.range(100..150)),
+            _ => rng.range(50..200).to_string(),
+        },
+        "hcl" => format!("{:06x}", rng.range(0..0x100_0000)),
+        "ecl" => rng.word(3..4),
+        "pid" => format!("{:010}", rng.range(0..1_000_000_000)),
+        _ => rng.word(1..4),
+    }
+}
+
+fn parse(input: &str) -> anyhow::Result<Vec<FastMap<&str, &str>>> {
     input
         .split("\n\n")
         .map(|passport| {
             passport
                 .split_whitespace()
                 .map(|field| {
-                    let mut field = field.splitn(2, ':');
-                    (field.next().unwrap(), field.next().unwrap())
+                    field
+                        .split_once(':')
+                        .with_context(|| format!("Field '{}' has no value", field))
                 })
-                .collect::<HashMap<_, _>>()
+                .collect::<anyhow::Result<FastMap<_, _>>>()
         })
-        .collect::<Vec<_>>()
+        .collect()
 }
 
 const REQUIRED: [&str; 7] = ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"];
 
-fn part1(input: &[HashMap<&str, &str>]) -> usize {
+fn part1(input: &[FastMap<&str, &str>]) -> usize {
     input
         .iter()
         .filter(|&passport| REQUIRED.iter().all(|&field| passport.contains_key(field)))
         .count()
 }
 
-fn part2(input: &[HashMap<&str, &str>]) -> usize {
+fn part2(input: &[FastMap<&str, &str>]) -> usize {
     input
         .iter()
         .filter(|&passport| {
@@ -70,11 +225,15 @@ fn validate(field: &str, value: &str) -> bool {
         "byr" => validate_number(value, 4, 1920..=2002),
         "iyr" => validate_number(value, 4, 2010..=2020),
         "eyr" => validate_number(value, 4, 2020..=2030),
-        "hgt" => match value.get(value.len() - 2..) {
-            Some("in") => validate_number(&value[..value.len() - 2], 2, 59..=76),
-            Some("cm") => validate_number(&value[..value.len() - 2], 3, 150..=193),
-            _ => false,
-        },
+        "hgt" => {
+            if let Some(inches) = value.strip_suffix("in") {
+                validate_number(inches, 2, 59..=76)
+            } else if let Some(cm) = value.strip_suffix("cm") {
+                validate_number(cm, 3, 150..=193)
+            } else {
+                false
+            }
+        }
         "hcl" => match value.get(0..=0) {
             Some("#") => value[1..].chars().all(|c| c.is_ascii_hexdigit()),
             _ => false,
@@ -107,33 +266,6 @@ mod test {
     use super::*;
     use crate::Solver;
 
-    #[test]
-    fn example_part1() {
-        const INPUT: &str = "\
-ecl:gry pid:860033327 eyr:2020 hcl:#fffffd
-byr:1937 iyr:2017 cid:147 hgt:183cm
-
-iyr:2013 ecl:amb cid:350 eyr:2023 pid:028048884
-hcl:#cfa07d byr:1929
-
-hcl:#ae17e1 iyr:2013
-eyr:2024
-ecl:brn pid:760753108 byr:1931
-hgt:179cm
-
-hcl:#cfa07d eyr:2025 pid:166559648
-iyr:2011 ecl:brn hgt:59in
-
-";
-
-        let input = parse(INPUT);
-
-        let expected = 2;
-        let actual = part1(&input);
-
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn valid() {
         assert!(validate("byr", "2002"));
@@ -158,10 +290,10 @@ iyr:2011 ecl:brn hgt:59in
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day04.txt");
+        let input = require_input!(4);
 
         let expected: Solution = (222, 140).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
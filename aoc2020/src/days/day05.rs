@@ -1,3 +1,9 @@
+use anyhow::{ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::inputs;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,28 +16,145 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<u32>;
+    type Part1 = u32;
+    type Part2 = u64;
+
     fn day(&self) -> u8 {
         5
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let (sum, (min, max)) =
-            input
-                .lines()
-                .fold((0, (u32::MAX, 0)), |(mut sum, (min, max)), line| {
-                    let seat_id = Seat::from(line).seat_id();
-                    sum += seat_id;
-                    (sum, (seat_id.min(min), seat_id.max(max)))
-                });
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("BFLR"),
+            grid: true,
+        }
+    }
+
+    /// The seat IDs of every boarding pass.
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<u32>> {
+        self.parse_bytes(input.as_bytes())
+    }
+
+    fn parse_bytes(&self, input: &[u8]) -> anyhow::Result<Vec<u32>> {
+        ensure!(
+            !input.iter().all(u8::is_ascii_whitespace),
+            "No boarding passes"
+        );
+        inputs::lines(input)
+            .map(|line| {
+                check_pass(line)?;
+                Ok(Seat::from(line).seat_id())
+            })
+            .collect()
+    }
+
+    fn part1(&self, seat_ids: &Vec<u32>) -> anyhow::Result<u32> {
+        seat_ids.iter().copied().max().context("No boarding passes")
+    }
+
+    fn part2(&self, seat_ids: &Vec<u32>) -> anyhow::Result<u64> {
+        let (mut sum, mut min, mut max) = (0_u64, u32::MAX, 0);
+        for &seat_id in seat_ids {
+            sum += u64::from(seat_id);
+            min = min.min(seat_id);
+            max = max.max(seat_id);
+        }
+
+        let (low, high) = (u64::from(min), u64::from(max));
+        let total_sum = (low + high) * (high - low + 1) / 2;
+        total_sum
+            .checked_sub(sum)
+            .filter(|missing| (low + 1..high).contains(missing))
+            .context("The seats are not a single block with one gap")
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
 
-        let total_sum = (max * (max + 1) - min * (min - 1)) / 2;
-        let part2 = total_sum - sum;
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        800
+    }
 
-        (max, part2).into()
+    /// `size` boarding passes for consecutive seats, except for one seat in the middle.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let count = size.clamp(3, 1000);
+        let first = rng.range(8..1024 - count);
+        let missing = first + rng.range(1..count);
+        let mut passes: Vec<String> = (first..=first + count)
+            .filter(|&id| id != missing)
+            .map(|id| {
+                let row: String = (0..7)
+                    .rev()
+                    .map(|bit| if id >> 3 >> bit & 1 == 1 { 'B' } else { 'F' })
+                    .collect();
+                let col: String = (0..3)
+                    .rev()
+                    .map(|bit| if id >> bit & 1 == 1 { 'R' } else { 'L' })
+                    .collect();
+                format!("{}{}\n", row, col)
+            })
+            .collect();
+
+        rng.shuffle(&mut passes);
+        passes.concat()
     }
 }
 
+impl Reference for Solver {
+    /// Narrow down the row and column range letter by letter, then look for the gap in the sorted
+    /// seat IDs.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut ids: Vec<u32> = input
+            .lines()
+            .map(|line| {
+                let (mut rows, mut cols) = (0..128, 0..8);
+                for c in line.chars() {
+                    let (row_mid, col_mid) =
+                        ((rows.start + rows.end) / 2, (cols.start + cols.end) / 2);
+                    match c {
+                        'F' => rows.end = row_mid,
+                        'B' => rows.start = row_mid,
+                        'L' => cols.end = col_mid,
+                        'R' => cols.start = col_mid,
+                        _ => panic!("Invalid character '{}'", c),
+                    }
+                }
+                rows.start * 8 + cols.start
+            })
+            .collect();
+        ids.sort_unstable();
+
+        let max = *ids.last().unwrap();
+        let missing = ids.windows(2).find(|pair| pair[1] != pair[0] + 1).unwrap()[0] + 1;
+
+        (max, missing).into()
+    }
+}
+
+/// Make sure `line` is seven `F`/`B` followed by three `L`/`R`.
+fn check_pass(line: &[u8]) -> anyhow::Result<()> {
+    let valid = line.len() == 10
+        && line[..7].iter().all(|&c| c == b'F' || c == b'B')
+        && line[7..].iter().all(|&c| c == b'L' || c == b'R');
+    ensure!(
+        valid,
+        "Invalid boarding pass: '{}'",
+        String::from_utf8_lossy(line)
+    );
+
+    Ok(())
+}
+
 #[derive(Debug, PartialOrd, PartialEq)]
 struct Seat {
     row: u32,
@@ -81,19 +204,22 @@ impl From<&str> for Seat {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
     #[test]
     fn test_seat_location() {
-        let expected = Seat::from("FBFBBFFRLR");
+        let input = example_input(5, "example");
+        let expected = Seat::from(input.lines().next().unwrap());
         let actual = Seat { row: 44, col: 5 };
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_seat_location_bytes() {
-        let expected = Seat::from("FBFBBFFRLR".as_bytes());
+        let input = example_input(5, "example");
+        let expected = Seat::from(input.lines().next().unwrap().as_bytes());
         let actual = Seat { row: 44, col: 5 };
         assert_eq!(actual, expected)
     }
@@ -101,10 +227,10 @@ mod test {
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day05.txt");
+        let input = require_input!(5);
 
         let actual: Solution = (919, 642).into();
-        let expected = solver.solve(input);
+        let expected = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
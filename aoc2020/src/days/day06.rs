@@ -1,3 +1,11 @@
+use std::collections::HashSet;
+
+use anyhow::ensure;
+
+use crate::bitset::BitSet;
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,41 +18,121 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = &'a str;
+    type Part1 = u32;
+    type Part2 = u32;
+
     fn day(&self) -> u8 {
         6
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let part1: u32 = input
+    fn shape(&self) -> Shape {
+        Shape {
+            alphabet: Some("abcdefghijklmnopqrstuvwxyz"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<&'a str> {
+        ensure!(
+            input.bytes().all(|c| c.is_ascii_lowercase() || c == b'\n'),
+            "Answers must be letters from a to z"
+        );
+        Ok(input)
+    }
+
+    fn part1(&self, input: &&str) -> anyhow::Result<u32> {
+        Ok(input
             .split("\n\n")
             .map(|group| {
                 group
-                    .bytes()
-                    .filter(|c| *c != b'\n')
-                    // the possible answers are a..=z, which can fit in a u32, where each bit
-                    // represents if the answer is present or not.
-                    .fold(0_u32, |acc, choice| acc | 1 << (choice - b'a'))
-                    .count_ones()
+                    .lines()
+                    .map(answers)
+                    .fold(BitSet::new(), |anyone, one| &anyone | &one)
+                    .len() as u32
             })
-            .sum();
+            .sum())
+    }
 
-        let part2 = input
+    fn part2(&self, input: &&str) -> anyhow::Result<u32> {
+        Ok(input
             .split("\n\n")
             .map(|group| {
                 group
                     .lines()
-                    .map(|person| {
-                        person
-                            .bytes()
-                            .fold(0_u32, |acc, choice| acc | 1 << (choice - b'a'))
+                    .map(answers)
+                    .fold(BitSet::full(26), |everyone, one| &everyone & &one)
+                    .len() as u32
+            })
+            .sum())
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+/// The questions `person` answered yes to, numbered from 0 for `a`.
+fn answers(person: &str) -> BitSet {
+    person
+        .bytes()
+        .map(|choice| usize::from(choice - b'a'))
+        .collect()
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        490
+    }
+
+    /// `size` groups of up to five people, each answering at least one question.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let groups: Vec<String> = (0..size)
+            .map(|_| {
+                let shared: Vec<u8> = (b'a'..=b'z').filter(|_| rng.chance(0.2)).collect();
+                (0..rng.range(1..6))
+                    .map(|_| {
+                        let mut person: Vec<u8> = (b'a'..=b'z')
+                            .filter(|c| shared.contains(c) || rng.chance(0.15))
+                            .collect();
+                        if person.is_empty() {
+                            person.push(b'a' + rng.range(0..26) as u8);
+                        }
+                        rng.shuffle(&mut person);
+                        String::from_utf8(person).unwrap() + "\n"
                     })
-                    .fold(std::u32::MAX, |everyone, one| everyone & one)
-                    .count_ones()
+                    .collect()
             })
-            .sum();
+            .collect();
 
-        (part1, part2).into()
+        groups.join("\n")
+    }
+}
+
+impl Reference for Solver {
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut solution = (0, 0);
+
+        for group in input.split("\n\n") {
+            let people: Vec<HashSet<char>> = group
+                .lines()
+                .map(|person| person.chars().collect())
+                .collect();
+            let anyone: HashSet<char> = people.iter().flatten().copied().collect();
+            let everyone = anyone
+                .iter()
+                .filter(|c| people.iter().all(|person| person.contains(c)));
+
+            solution.0 += anyone.len();
+            solution.1 += everyone.count();
+        }
+
+        solution.into()
     }
 }
 
@@ -55,50 +143,13 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-abc
-
-a
-b
-c
-
-ab
-ac
-
-a
-a
-a
-a
-
-b";
-
-    #[test]
-    fn example_part1() {
-        let solver = super::Solver::new();
-
-        let expected = 11.to_string();
-        let actual = solver.solve(INPUT);
-
-        assert_eq!(actual.part1(), expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let solver = super::Solver::new();
-
-        let expected = 6.to_string();
-        let actual = solver.solve(INPUT);
-
-        assert_eq!(actual.part2(), expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day06.txt");
+        let input = require_input!(6);
 
         let expected = (6885, 3550).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
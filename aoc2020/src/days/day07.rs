@@ -1,5 +1,12 @@
-use std::collections::HashMap;
+use std::convert::TryFrom;
 
+use anyhow::{bail, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::graph::{Graph, Interner};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,141 +19,262 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Bags<'a>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         7
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let bags: Bags = {
-            let rule_vec: Vec<Rule> = input.lines().map(Rule::from).collect();
-            Bags::from(rule_vec)
-        };
-
-        let part1 = bags.reverse_bag_count("shiny gold");
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789abcdefghijklmnopqrstuvwxyz ,."),
+            ..Shape::default()
+        }
+    }
 
-        let part2 = bags.count_required_bags("shiny gold");
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Bags<'a>> {
+        let rule_vec = input
+            .lines()
+            .map(Rule::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Bags::try_from(rule_vec)
+    }
 
-        (part1, part2).into()
+    fn part1(&self, bags: &Bags<'_>) -> anyhow::Result<usize> {
+        bags.reverse_bag_count("shiny gold")
     }
-}
 
-struct Bags<'r> {
-    bags: HashMap<String, Rule<'r>>,
-}
+    fn part2(&self, bags: &Bags<'_>) -> anyhow::Result<usize> {
+        bags.count_required_bags("shiny gold")
+    }
 
-impl<'r> Bags<'r> {
-    pub fn new() -> Self {
-        Self {
-            bags: HashMap::new(),
-        }
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
     }
 
-    pub fn add_bag(&mut self, bag: Rule<'r>) -> Option<Rule> {
-        self.bags.insert(bag.get_key(), bag)
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
+}
 
-    /// Find the number of bags that can contain at least one `search_key` bag.
-    ///
-    /// Search begins with `search_key` and works its way backwards through the rules.
-    pub fn reverse_bag_count(&self, search_key: &str) -> usize {
-        let mut count = 0;
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        590
+    }
 
-        for parent_bag in self.bags.values() {
-            match self.recursive_bag_count(&parent_bag, search_key) {
-                0 => {}
-                _ => count += 1,
+    /// Rules for `size` bags nested at most eight levels deep, with shiny gold in the middle.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const ADJECTIVES: [&str; 16] = [
+            "bright", "clear", "dark", "dim", "dotted", "drab", "dull", "faded", "light", "muted",
+            "pale", "plaid", "posh", "shiny", "striped", "vibrant",
+        ];
+        const COLORS: [&str; 16] = [
+            "aqua", "black", "blue", "bronze", "coral", "crimson", "gold", "gray", "green",
+            "indigo", "lime", "olive", "plum", "red", "teal", "white",
+        ];
+        const LEVELS: usize = 8;
+
+        let mut names = vec![String::from("shiny gold")];
+        while names.len() < size.clamp(2, ADJECTIVES.len() * COLORS.len()) {
+            let name = format!("{} {}", rng.choose(&ADJECTIVES), rng.choose(&COLORS));
+            if !names.contains(&name) {
+                names.push(name);
             }
         }
 
-        count
+        // Bags only contain bags from deeper levels, so there are no cycles.
+        let mut levels: Vec<usize> = (0..names.len()).map(|_| rng.range(0..LEVELS)).collect();
+        levels[0] = LEVELS / 2;
+
+        let mut rules: Vec<String> = (0..names.len())
+            .map(|bag| {
+                let deeper: Vec<usize> = (0..names.len())
+                    .filter(|&other| levels[other] > levels[bag])
+                    .collect();
+                let mut contents = Vec::new();
+                if !deeper.is_empty() {
+                    for _ in 0..rng.range(0..5) {
+                        let child = *rng.choose(&deeper);
+                        if !contents.iter().any(|&(c, _)| c == child) {
+                            contents.push((child, rng.range(1..6)));
+                        }
+                    }
+                }
+
+                let contents = if contents.is_empty() {
+                    String::from("no other bags")
+                } else {
+                    let contents: Vec<String> = contents
+                        .iter()
+                        .map(|&(child, count)| {
+                            let plural = if count == 1 { "" } else { "s" };
+                            format!("{} {} bag{}", count, names[child], plural)
+                        })
+                        .collect();
+                    contents.join(", ")
+                };
+                format!("{} bags contain {}.\n", names[bag], contents)
+            })
+            .collect();
+
+        rng.shuffle(&mut rules);
+        rules.concat()
     }
+}
 
-    pub fn recursive_bag_count(&self, bag: &Rule<'r>, search_key: &str) -> usize {
-        let mut count = 0;
+impl Reference for Solver {
+    /// Search the rules from scratch for every bag, without remembering anything in between.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut rules: FastMap<&str, Vec<(usize, String)>> = FastMap::default();
+        for line in input.lines() {
+            let (container, contents) = line.split_once(" bags contain ").unwrap();
+            let contents = contents
+                .trim_end_matches('.')
+                .split(", ")
+                .filter(|&content| content != "no other bags")
+                .map(|content| {
+                    let words: Vec<&str> = content.split(' ').collect();
+                    (
+                        words[0].parse().unwrap(),
+                        format!("{} {}", words[1], words[2]),
+                    )
+                })
+                .collect();
+            rules.insert(container, contents);
+        }
 
-        for (child_key, child_count) in bag.contents.iter() {
-            if search_key == child_key {
-                count += child_count;
-            } else if let Some(c) = self.bags.get(child_key) {
-                count += self.recursive_bag_count(&c, search_key);
-            }
+        fn holds_gold(rules: &FastMap<&str, Vec<(usize, String)>>, bag: &str) -> bool {
+            rules[bag]
+                .iter()
+                .any(|(_, inner)| inner == "shiny gold" || holds_gold(rules, inner))
+        }
+        fn bags_inside(rules: &FastMap<&str, Vec<(usize, String)>>, bag: &str) -> usize {
+            rules[bag]
+                .iter()
+                .map(|(count, inner)| count * (1 + bags_inside(rules, inner)))
+                .sum()
         }
 
-        count
+        let part1 = rules.keys().filter(|bag| holds_gold(&rules, bag)).count();
+        let part2 = bags_inside(&rules, "shiny gold");
+
+        (part1, part2).into()
     }
+}
 
-    pub fn count_required_bags(&self, parent_key: &str) -> usize {
-        let mut count = 0;
+/// The bag rules as a graph, with an edge from each bag to the bags it must directly contain
+/// weighted by how many of them it holds. Bag names are borrowed from the input.
+pub(crate) struct Bags<'b> {
+    names: Interner<&'b str>,
+    graph: Graph,
+}
 
-        let parent_bag = self.bags.get(parent_key).expect("valid key");
+impl<'b> Bags<'b> {
+    pub fn new() -> Self {
+        Self {
+            names: Interner::new(),
+            graph: Graph::new(),
+        }
+    }
 
-        for (child_key, child_count) in parent_bag.contents.iter() {
-            if let Some(child) = self.bags.get(child_key) {
-                count += child_count * (1 + self.count_required_bags(&child.get_key()));
-            } else {
-                eprintln!("Could not find {}", child_key);
-            }
+    pub fn add_bag(&mut self, bag: &Rule<'b>) {
+        let parent = self.names.intern(bag.get_key());
+        for (&child_key, &child_count) in bag.contents.iter() {
+            let child = self.names.intern(child_key);
+            self.graph.add_edge(parent, child, child_count);
         }
+    }
+
+    fn id(&self, key: &str) -> anyhow::Result<usize> {
+        self.names
+            .get(key)
+            .with_context(|| format!("Could not find {}", key))
+    }
+
+    /// Find the number of bags that can contain at least one `search_key` bag.
+    ///
+    /// Search begins with `search_key` and works its way backwards through the rules.
+    pub fn reverse_bag_count(&self, search_key: &str) -> anyhow::Result<usize> {
+        Ok(self.graph.reverse().reachable(self.id(search_key)?).len())
+    }
 
-        count
+    pub fn count_required_bags(&self, parent_key: &str) -> anyhow::Result<usize> {
+        self.graph
+            .path_weight_sum(self.id(parent_key)?)
+            .with_context(|| format!("{} bags can't hold a finite number of bags", parent_key))
     }
 }
 
-impl<'r> From<Vec<Rule<'r>>> for Bags<'r> {
-    fn from(rules: Vec<Rule<'r>>) -> Self {
+impl<'r> TryFrom<Vec<Rule<'r>>> for Bags<'r> {
+    type Error = anyhow::Error;
+
+    fn try_from(rules: Vec<Rule<'r>>) -> anyhow::Result<Self> {
         let mut bags = Bags::new();
+        let mut containers = FastSet::default();
 
         for rule in rules {
-            if let Some(b) = bags.add_bag(rule) {
-                panic!("There's a duplicate parent rule: {:?}", b);
+            if !containers.insert(rule.container) {
+                bail!("There's a duplicate parent rule: {:?}", rule);
             };
+            bags.add_bag(&rule);
         }
 
-        bags
+        Ok(bags)
     }
 }
 
 #[derive(Debug, Eq, PartialEq)]
-struct Rule<'r> {
+pub(crate) struct Rule<'r> {
     container: &'r str,
-    contents: HashMap<String, usize>,
+    contents: FastMap<&'r str, usize>,
 }
 
 impl<'r> Rule<'r> {
-    pub fn get_key(&self) -> String {
-        self.container.into()
+    pub fn get_key(&self) -> &'r str {
+        self.container
     }
 }
 
-impl<'r> From<&'r str> for Rule<'r> {
-    fn from(rule: &'r str) -> Self {
-        let rule: Vec<_> = rule.split("bags contain").map(|s| s.trim()).collect();
-        let container = rule[0];
-        let contents_str = rule[1];
+impl<'r> TryFrom<&'r str> for Rule<'r> {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: &'r str) -> anyhow::Result<Self> {
+        let (container, contents_str) = rule
+            .split_once("bags contain")
+            .with_context(|| format!("Invalid rule: '{}'", rule))?;
+        let (container, contents_str) = (container.trim(), contents_str.trim());
 
-        let contents;
-        if contents_str == "no other bags." {
-            contents = HashMap::new()
+        let contents = if contents_str == "no other bags." {
+            FastMap::default()
         } else {
-            contents = contents_str
+            contents_str
                 .split(',')
                 .map(|s| {
-                    let mut words = s.split_whitespace();
-                    let num: usize = words.next().unwrap().parse().unwrap();
-                    let adjective = words.next().unwrap();
-                    let color = words.next().unwrap();
-                    let bag = format!("{} {}", adjective, color);
-
-                    (bag, num)
+                    // The name is everything between the count and the final "bag" or "bags".
+                    let (num, bag) = match s.trim().split_once(' ') {
+                        Some((num, rest)) => match rest.rsplit_once(' ') {
+                            Some((bag, _)) => (num, bag.trim()),
+                            None => bail!("Invalid bag count: '{}'", s),
+                        },
+                        None => bail!("Invalid bag count: '{}'", s),
+                    };
+                    let num: usize = num
+                        .parse()
+                        .with_context(|| format!("Invalid bag count: '{}'", s))?;
+
+                    Ok((bag, num))
                 })
-                .collect();
-        }
+                .collect::<anyhow::Result<_>>()?
+        };
 
-        Self {
+        Ok(Self {
             container,
             contents,
-        }
+        })
     }
 }
 
@@ -154,33 +282,19 @@ impl<'r> From<&'r str> for Rule<'r> {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-light red bags contain 1 bright white bag, 2 muted yellow bags.
-dark orange bags contain 3 bright white bags, 4 muted yellow bags.
-bright white bags contain 1 shiny gold bag.
-muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.
-shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.
-dark olive bags contain 3 faded blue bags, 4 dotted black bags.
-vibrant plum bags contain 5 faded blue bags, 6 dotted black bags.
-faded blue bags contain no other bags.
-dotted black bags contain no other bags.";
-
     #[test]
     fn test_convert_to_rule() {
-        let input = INPUT.lines().next().unwrap();
-        assert_eq!(
-            input.trim(),
-            "light red bags contain 1 bright white bag, 2 muted yellow bags."
-        );
-        let actual = Rule::from(input);
-
-        let contents: HashMap<String, usize> = {
-            let mut c = HashMap::with_capacity(2);
-            c.insert(String::from("bright white"), 1);
-            c.insert(String::from("muted yellow"), 2);
+        let input = example_input(7, "example");
+        let actual = Rule::try_from(input.lines().next().unwrap()).unwrap();
+
+        let contents: FastMap<&str, usize> = {
+            let mut c = FastMap::with_capacity_and_hasher(2, Default::default());
+            c.insert("bright white", 1);
+            c.insert("muted yellow", 2);
             c
         };
 
@@ -192,39 +306,13 @@ dotted black bags contain no other bags.";
         assert_eq!(actual, expected)
     }
 
-    #[test]
-    fn example_part1() {
-        let bags = {
-            let rule_vec: Vec<Rule> = INPUT.lines().map(Rule::from).collect();
-            Bags::from(rule_vec)
-        };
-
-        let expected = 4;
-        let actual = bags.reverse_bag_count("shiny gold");
-
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let bags = {
-            let rule_vec: Vec<Rule> = INPUT.lines().map(Rule::from).collect();
-            Bags::from(rule_vec)
-        };
-
-        let expected = 32;
-        let actual = bags.count_required_bags("shiny gold");
-
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day07.txt");
+        let input = require_input!(7);
 
         let expected: Solution = (278, 45157).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
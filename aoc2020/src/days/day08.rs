@@ -1,3 +1,11 @@
+use std::convert::TryInto;
+use std::str::FromStr;
+
+use anyhow::{bail, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,42 +18,158 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<Op>;
+    type Part1 = i32;
+    type Part2 = i32;
+
     fn day(&self) -> u8 {
         8
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let instructions: Vec<Op> = input.lines().map(Op::from).collect();
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789acjmnop +-"),
+            ..Shape::default()
+        }
+    }
 
-        let instr_len = instructions.len();
-        let mut vm = Vm::new(instructions);
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<Op>> {
+        input.lines().map(str::parse).collect()
+    }
 
-        let part1 = {
-            vm.execute(-1);
-            vm.acc
-        };
+    fn part1(&self, boot_code: &Vec<Op>) -> anyhow::Result<i32> {
+        let mut vm = Vm::new(boot_code);
+        vm.execute(None)?;
+        Ok(vm.acc)
+    }
+
+    fn part2(&self, boot_code: &Vec<Op>) -> anyhow::Result<i32> {
+        let mut vm = Vm::new(boot_code);
+        for i in 0..boot_code.len() {
+            if vm.execute(Some(i))? {
+                return Ok(vm.acc);
+            }
+        }
+        bail!("No single change makes the boot code terminate")
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
 
-        let part2 = {
-            (0..instr_len)
-                .map(|i| vm.execute(i as i32))
-                .find(|&found| found)
-                .unwrap();
-            vm.acc
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        620
+    }
+
+    /// A program of `size` instructions that loops, but terminates when one particular `jmp` is
+    /// changed into a `nop`.
+    ///
+    /// The code before that `jmp` only moves forwards, and the `jmp` leads back into it, so
+    /// changing anything else still loops. The code after it also only moves forwards, up to
+    /// the end of the program.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let len = size.max(4);
+        let bug = rng.range(1..len - 1);
+
+        let mut program = Vec::with_capacity(len);
+        for ip in 0..len {
+            let op = if ip == bug {
+                Op::Jmp(-(rng.range(1..bug + 1) as i32))
+            } else {
+                // Jumps stay inside their own half of the program.
+                let limit = if ip < bug { bug } else { len };
+                let forward = rng.range(1..(limit - ip).min(8) + 1) as i32;
+                match rng.range(0..3) {
+                    0 => Op::Acc(rng.range(0..100) as i32 - 50),
+                    1 => Op::Jmp(forward),
+                    // A `nop` turned into a `jmp` must not escape either.
+                    _ if ip < bug => Op::Nop(-(rng.range(0..ip + 1) as i32)),
+                    _ => Op::Nop(rng.range(0..100) as i32 - 50),
+                }
+            };
+            program.push(op);
+        }
+
+        program
+            .iter()
+            .map(|op| match op {
+                Op::Acc(n) => format!("acc {:+}\n", n),
+                Op::Jmp(n) => format!("jmp {:+}\n", n),
+                Op::Nop(n) => format!("nop {:+}\n", n),
+            })
+            .collect()
+    }
+}
+
+impl Reference for Solver {
+    /// Run a fresh copy of the program for every instruction that can be flipped.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let program: Vec<(&str, isize)> = input
+            .lines()
+            .map(|line| {
+                let (op, arg) = line.split_once(' ').unwrap();
+                (op, arg.parse().unwrap())
+            })
+            .collect();
+
+        // The accumulator when the program loops or ends, and whether it ended.
+        let run = |program: &[(&str, isize)]| -> (isize, bool) {
+            let (mut ip, mut acc) = (0, 0);
+            let mut seen = Vec::new();
+            while ip != program.len() as isize {
+                if seen.contains(&ip) {
+                    return (acc, false);
+                }
+                seen.push(ip);
+                match program[ip as usize] {
+                    ("acc", n) => {
+                        acc += n;
+                        ip += 1;
+                    }
+                    ("jmp", n) => ip += n,
+                    _ => ip += 1,
+                }
+            }
+            (acc, true)
         };
 
+        let part1 = run(&program).0;
+        let part2 = (0..program.len())
+            .filter_map(|i| {
+                let mut program = program.clone();
+                program[i].0 = match program[i].0 {
+                    "jmp" => "nop",
+                    "nop" => "jmp",
+                    _ => return None,
+                };
+                match run(&program) {
+                    (acc, true) => Some(acc),
+                    _ => None,
+                }
+            })
+            .next()
+            .unwrap();
+
         (part1, part2).into()
     }
 }
 
-struct Vm {
-    boot_code: Vec<Op>,
-    ip: i32,
+struct Vm<'c> {
+    boot_code: &'c [Op],
+    ip: usize,
     acc: i32,
 }
 
-impl Vm {
-    fn new(boot_code: Vec<Op>) -> Self {
+impl<'c> Vm<'c> {
+    fn new(boot_code: &'c [Op]) -> Self {
         Self {
             boot_code,
             ip: 0,
@@ -53,34 +177,67 @@ impl Vm {
         }
     }
 
-    fn execute(&mut self, changed_ip: i32) -> bool {
+    /// Run the boot code, with the instruction at `changed_ip` swapped between `jmp` and `nop`.
+    ///
+    /// Returns whether it terminated by stepping just past the last instruction. Jumping anywhere
+    /// else outside the code, or revisiting an instruction, counts as not terminating.
+    fn execute(&mut self, changed_ip: Option<usize>) -> anyhow::Result<bool> {
         self.ip = 0;
         self.acc = 0;
 
         let mut visited = vec![false; self.boot_code.len()];
 
-        while !visited[self.ip as usize] {
-            visited[self.ip as usize] = true;
-            match (&self.boot_code[self.ip as usize], self.ip == changed_ip) {
+        while self.ip < self.boot_code.len() && !visited[self.ip] {
+            visited[self.ip] = true;
+
+            let op = self.boot_code[self.ip];
+            let changed = Some(self.ip) == changed_ip;
+            aoc_trace!(
+                "ip {:>4}: {:?}{} acc {}",
+                self.ip,
+                op,
+                if changed { " (changed)" } else { "" },
+                self.acc
+            );
+
+            match (op, changed) {
                 (Op::Acc(n), _) => {
-                    self.acc += n;
+                    self.acc = self
+                        .acc
+                        .checked_add(n)
+                        .context("The accumulator overflowed")?;
                     self.ip += 1;
                 }
-                (Op::Jmp(n), false) | (Op::Nop(n), true) => self.ip += n,
+                (Op::Jmp(n), false) | (Op::Nop(n), true) => {
+                    // Anything that lands outside the code ends up past the end, where the loop
+                    // stops.
+                    self.ip = (self.ip as i64 + i64::from(n))
+                        .try_into()
+                        .unwrap_or(usize::MAX);
+                }
                 (Op::Jmp(_), true) | (Op::Nop(_), false) => self.ip += 1,
             }
 
-            if self.ip == self.boot_code.len() as i32 {
-                return true;
+            if self.ip == self.boot_code.len() {
+                aoc_debug!(
+                    "Boot code terminated after changing instruction {:?}, acc {}",
+                    changed_ip,
+                    self.acc
+                );
+                return Ok(true);
             }
         }
 
-        false
+        aoc_trace!(
+            "ip {:>4}: already visited or out of bounds, stopping",
+            self.ip
+        );
+        Ok(false)
     }
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
-enum Op {
+pub(crate) enum Op {
     /// `acc` increases or decreases a single global balue called the *accumulator* by the value
     /// given in the argument.
     Acc(i32),
@@ -92,18 +249,23 @@ enum Op {
     Nop(i32),
 }
 
-impl From<&str> for Op {
-    fn from(instruction: &str) -> Self {
-        let instruction = instruction.split(' ').collect::<Vec<_>>();
-        let op = instruction[0];
-        let arg = instruction[1].parse::<i32>().unwrap();
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(instruction: &str) -> anyhow::Result<Self> {
+        let (op, arg) = instruction
+            .split_once(' ')
+            .with_context(|| format!("Invalid instruction: '{}'", instruction))?;
+        let arg = arg
+            .parse::<i32>()
+            .with_context(|| format!("Invalid argument: '{}'", instruction))?;
 
-        match op {
+        Ok(match op {
             "acc" => Op::Acc(arg),
             "jmp" => Op::Jmp(arg),
             "nop" => Op::Nop(arg),
-            _ => unreachable!("Unrecognized operation: {}", op),
-        }
+            _ => bail!("Unrecognized operation: {}", op),
+        })
     }
 }
 
@@ -114,54 +276,30 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-nop +0
-acc +1
-jmp +4
-acc +3
-jmp -3
-acc -99
-acc +1
-jmp -4
-acc +6";
-
     #[test]
     fn test_parse_op() {
         let expected = Op::Jmp(3);
-        let actual = Op::from("jmp +3");
+        let actual = "jmp +3".parse::<Op>().unwrap();
         assert_eq!(actual, expected);
 
         let expected = Op::Jmp(-3);
-        let actual = Op::from("jmp -3");
+        let actual = "jmp -3".parse::<Op>().unwrap();
         assert_eq!(actual, expected);
     }
 
     #[test]
-    #[should_panic(expected = "Unrecognized operation: zzz")]
     fn test_parse_invalid_op() {
-        Op::from("zzz +0");
-    }
-
-    #[test]
-    fn example_part1() {
-        let input = INPUT.lines().map(Op::from).collect::<Vec<Op>>();
-
-        let expected = 5;
-        let actual = {
-            let mut vm = Vm::new(input);
-            vm.execute(-1);
-            vm.acc
-        };
-        assert_eq!(actual, expected);
+        let error = "zzz +0".parse::<Op>().unwrap_err();
+        assert_eq!(error.to_string(), "Unrecognized operation: zzz");
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day08.txt");
+        let input = require_input!(8);
 
         let expected: Solution = (1949, 2092).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,5 +1,13 @@
 use std::collections::HashSet;
+use std::ops::Range;
 
+use anyhow::Context;
+
+use crate::explain::{Explain, Explanation};
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::params::Params;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,30 +20,204 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+/// The XMAS data, along with how many numbers back each one is checked against.
+pub(crate) struct Xmas {
+    numbers: Vec<usize>,
+    window: usize,
+}
+
+impl crate::Day for Solver {
+    type Parsed<'a> = Xmas;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         9
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let input: Vec<usize> = input
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Xmas> {
+        self.parse_with(input, &Params::new())
+    }
+
+    fn parse_with(&self, input: &str, params: &Params) -> anyhow::Result<Xmas> {
+        let numbers = input
             .lines()
             .map(|i| {
                 i.parse::<usize>()
-                    .unwrap_or_else(|_| panic!("Invalid number: {}", i))
+                    .with_context(|| format!("Invalid number: {}", i))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        Ok(Xmas {
+            numbers,
+            window: params.get_or("window", 25)?,
+        })
+    }
+
+    fn param_keys(&self) -> &[&str] {
+        &["window"]
+    }
+
+    fn part1(&self, xmas: &Xmas) -> anyhow::Result<usize> {
+        find_anomaly(&xmas.numbers, xmas.window)
+            .map(|i| xmas.numbers[i])
+            .context("Every number is the sum of two before it")
+    }
+
+    fn part2(&self, xmas: &Xmas) -> anyhow::Result<usize> {
+        let anomaly = self.part1(xmas)?;
+        let run = find_contiguous_run(&xmas.numbers, anomaly)
+            .with_context(|| format!("No contiguous run adds up to {}", anomaly))?;
+        let run = &xmas.numbers[run];
+        // Both are part of a run adding up to `anomaly`, so this can't overflow.
+        Ok(run.iter().min().unwrap() + run.iter().max().unwrap())
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Some(self)
+    }
+}
+
+impl Explain for Solver {
+    /// The first number that isn't a sum from its window, and the run that adds up to it.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()> {
+        let xmas = crate::Day::parse(self, input)?;
+        let position = find_anomaly(&xmas.numbers, xmas.window)
+            .context("Every number is the sum of two before it")?;
+        let anomaly = xmas.numbers[position];
+        explanation
+            .section("Anomaly")
+            .row("number", anomaly)
+            .row("line", position + 1)
+            .row(
+                "window",
+                format!("lines {}-{}", position + 1 - xmas.window, position),
+            );
+
+        let lines = find_contiguous_run(&xmas.numbers, anomaly)
+            .with_context(|| format!("No contiguous run adds up to {}", anomaly))?;
+        let run = &xmas.numbers[lines.clone()];
+        explanation
+            .section("Contiguous run")
+            .row("lines", format!("{}-{}", lines.start + 1, lines.end))
+            .row("length", run.len())
+            .row("smallest", run.iter().min().unwrap())
+            .row("largest", run.iter().max().unwrap());
+        Ok(())
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        1000
+    }
+
+    /// `size` numbers (at most 1000, to stay in `usize` range) where each one after the
+    /// 25-number preamble is the sum of two distinct numbers in the 25 before it, except for one
+    /// that's instead the sum of a contiguous run.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const WINDOW: usize = 25;
+
+        let len = size.clamp(WINDOW + 10, 1000);
+        let anomaly_at = rng.range(WINDOW + 5..len);
+
+        let mut numbers: Vec<usize> = (1..=2 * WINDOW).collect();
+        rng.shuffle(&mut numbers);
+        numbers.truncate(WINDOW);
+
+        let mut seen: HashSet<usize> = numbers.iter().copied().collect();
+        // Adding two of the smallest numbers keeps the growth slow. Widen the choice whenever
+        // those only give sums that were already used.
+        let mut spread = WINDOW / 2;
+        while numbers.len() < len {
+            let previous = &numbers[numbers.len() - WINDOW..];
+            let next = if numbers.len() == anomaly_at {
+                let start = rng.range(0..numbers.len() - 2);
+                let end = rng.range(start + 2..numbers.len().min(start + 17) + 1);
+                numbers[start..end].iter().sum()
+            } else {
+                let mut smallest = previous.to_vec();
+                smallest.sort_unstable();
+                let a = *rng.choose(&smallest[..spread]);
+                let b = *rng.choose(&smallest[..spread]);
+                if a == b {
+                    continue;
+                }
+                a + b
+            };
+
+            // The anomaly mustn't be a sum of two numbers, nor their difference, which the
+            // solver also accepts. Repeats would make the contiguous run ambiguous.
+            let is_sum = previous.iter().any(|&c| {
+                let needed = next.abs_diff(c);
+                previous.contains(&needed)
+            });
+            if seen.contains(&next) || (numbers.len() == anomaly_at && is_sum) {
+                spread = (spread + 1).min(WINDOW);
+                continue;
+            }
+            spread = WINDOW / 2;
+            seen.insert(next);
+            numbers.push(next);
+        }
+
+        numbers.iter().map(|n| format!("{}\n", n)).collect()
+    }
+}
+
+impl Reference for Solver {
+    /// Check every pair in the window, then every contiguous run of two or more numbers.
+    fn solve_reference(&self, input: &str) -> Solution {
+        // The puzzle's window; only the examples use another.
+        const WINDOW: usize = 25;
+        let numbers: Vec<usize> = input.lines().map(|line| line.parse().unwrap()).collect();
+
+        let part1 = (WINDOW..numbers.len())
+            .map(|i| numbers[i])
+            .zip(numbers.windows(WINDOW))
+            .find(|(number, window)| {
+                !window
+                    .iter()
+                    .enumerate()
+                    .any(|(i, a)| window[i + 1..].iter().any(|b| a != b && a + b == *number))
             })
-            .collect();
+            .unwrap()
+            .0;
 
-        let part1 = find_anomaly(&input, 25);
-        let part2 = find_contiguous_sum(input, part1);
+        let mut part2 = None;
+        'search: for start in 0..numbers.len() {
+            for end in start + 2..=numbers.len() {
+                let run = &numbers[start..end];
+                if run.iter().sum::<usize>() == part1 {
+                    part2 = Some(run.iter().min().unwrap() + run.iter().max().unwrap());
+                    break 'search;
+                }
+            }
+        }
 
-        (part1, part2).into()
+        (part1, part2.unwrap()).into()
     }
 }
 
-fn find_anomaly(numbers: &[usize], window: usize) -> usize {
+/// Find the position of the first number that isn't the sum of two in the `window` before it.
+fn find_anomaly(numbers: &[usize], window: usize) -> Option<usize> {
     let mut checking = HashSet::with_capacity(window);
-    let mut invalid = 0;
 
     for (i, val) in numbers.iter().enumerate() {
         if i < window {
@@ -57,8 +239,7 @@ fn find_anomaly(numbers: &[usize], window: usize) -> usize {
             }
 
             if !found {
-                invalid = *val;
-                break;
+                return Some(i);
             }
 
             // Otherwise rotate checking set.
@@ -67,38 +248,29 @@ fn find_anomaly(numbers: &[usize], window: usize) -> usize {
         }
     }
 
-    invalid
+    None
 }
 
-fn find_contiguous_sum(numbers: Vec<usize>, num: usize) -> usize {
+/// Find where a run of at least two numbers adding up to `num` is.
+fn find_contiguous_run(numbers: &[usize], num: usize) -> Option<Range<usize>> {
+    let num = num as u128;
     let mut start = 0;
-    let mut end = 1;
-    let mut sum = numbers[0] + numbers[1];
-
-    loop {
-        use std::cmp::Ordering;
-        match sum.cmp(&num) {
-            Ordering::Equal => break,
-            Ordering::Less => {
-                end += 1;
-                sum += numbers[end];
-            }
-            Ordering::Greater => {
-                sum -= numbers[start];
-                start += 1;
-            }
-        }
-    }
+    // Wide enough that adding one more number before shrinking the run can't overflow.
+    let mut sum = 0_u128;
 
-    let mut min = usize::MAX;
-    let mut max = 0;
+    for (end, &n) in numbers.iter().enumerate() {
+        sum += n as u128;
+        while sum > num && start < end {
+            sum -= numbers[start] as u128;
+            start += 1;
+        }
 
-    for &n in numbers.iter().take(end + 1).skip(start) {
-        min = min.min(n);
-        max = max.max(n);
+        if sum == num && start < end {
+            return Some(start..end + 1);
+        }
     }
 
-    min + max
+    None
 }
 
 #[cfg(test)]
@@ -108,59 +280,13 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-35
-20
-15
-25
-47
-40
-62
-55
-65
-95
-102
-117
-150
-182
-127
-219
-299
-277
-309
-576";
-
-    #[test]
-    fn example_part1() {
-        let numbers = INPUT
-            .lines()
-            .map(|n| n.parse::<usize>().expect("Invalid number"))
-            .collect::<Vec<usize>>();
-
-        let expected = 127;
-        let actual = find_anomaly(&numbers, 5);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let numbers = INPUT
-            .lines()
-            .map(|n| n.parse::<usize>().expect("Invalid number"))
-            .collect::<Vec<usize>>();
-
-        let expected = 62;
-        let actual = find_contiguous_sum(numbers, 127);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
-        let input = include_str!("../../input/day09.txt");
+        let input = require_input!(9);
         let solver = super::Solver::new();
 
         let expected: Solution = (556543474, 76096372).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
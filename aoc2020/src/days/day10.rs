@@ -1,3 +1,13 @@
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use anyhow::{bail, Context};
+
+use crate::explain::{Explain, Explanation};
+use crate::generate::{Generate, Rng};
+use crate::inputs;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,25 +20,147 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<u32>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         10
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let adapters = {
-            let mut input = input
-                .lines()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect::<Vec<u32>>();
-            get_all_nodes(&mut input);
-            input
-        };
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789"),
+            ..Shape::default()
+        }
+    }
+
+    /// The joltages in order, from the outlet to the device.
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<u32>> {
+        self.parse_bytes(input.as_bytes())
+    }
+
+    fn parse_bytes(&self, input: &[u8]) -> anyhow::Result<Vec<u32>> {
+        let mut adapters = inputs::lines(input)
+            .map(|n| {
+                inputs::parse_unsigned(n)
+                    .and_then(|n| u32::try_from(n).ok())
+                    .filter(|&n| n <= u32::MAX - 3)
+                    .with_context(|| format!("Invalid joltage: {}", String::from_utf8_lossy(n)))
+            })
+            .collect::<anyhow::Result<Vec<u32>>>()?;
+        get_all_nodes(&mut adapters);
+
+        if let Some([x, y]) = adapters
+            .array_windows()
+            .find(|[x, y]| !(1..=3).contains(&(y - x)))
+        {
+            bail!("Can't chain adapters {} and {}", x, y);
+        }
+        Ok(adapters)
+    }
+
+    fn part1(&self, adapters: &Vec<u32>) -> anyhow::Result<usize> {
+        Ok(part1(adapters))
+    }
+
+    fn part2(&self, adapters: &Vec<u32>) -> anyhow::Result<usize> {
+        part2(adapters).context("Too many arrangements to count")
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Some(self)
+    }
+}
 
-        let part1 = part1(&adapters);
-        let part2 = part2(&adapters);
+impl Explain for Solver {
+    /// How often each difference comes up along the chain, which part 1 multiplies.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()> {
+        let adapters = crate::Day::parse(self, input)?;
+        let mut counts = [0; 4];
+        for [x, y] in adapters.array_windows() {
+            counts[(y - x) as usize] += 1;
+        }
 
-        (part1 as usize, part2).into()
+        let section = explanation.section("Jolt differences, outlet to device");
+        for (difference, count) in counts.iter().enumerate().skip(1) {
+            section.row(
+                format!(
+                    "{} jolt{}",
+                    difference,
+                    if difference == 1 { "" } else { "s" }
+                ),
+                count,
+            );
+        }
+        explanation
+            .section("Chain")
+            .row("adapters", adapters.len() - 2)
+            .row("device", adapters.last().unwrap());
+        Ok(())
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        100
+    }
+
+    /// `size` adapters whose joltages differ by 1 or 3, in runs of at most four 1-jolt steps.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let mut adapters = Vec::with_capacity(size);
+        let mut joltage = 0;
+        let mut run = 0;
+        while adapters.len() < size.max(1) {
+            if run < 4 && rng.chance(0.7) {
+                joltage += 1;
+                run += 1;
+            } else {
+                joltage += 3;
+                run = 0;
+            }
+            adapters.push(joltage);
+        }
+
+        rng.shuffle(&mut adapters);
+        adapters.iter().map(|a| format!("{}\n", a)).collect()
+    }
+}
+
+impl Reference for Solver {
+    /// Count the ways to reach each adapter from the ones up to three jolts below it.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut joltages: Vec<usize> = input.lines().map(|line| line.parse().unwrap()).collect();
+        joltages.push(0);
+        joltages.sort_unstable();
+        joltages.push(joltages.last().unwrap() + 3);
+
+        let differences: Vec<usize> = joltages.windows(2).map(|pair| pair[1] - pair[0]).collect();
+        let count = |difference| differences.iter().filter(|&&d| d == difference).count();
+        let part1 = count(1) * count(3);
+
+        let mut ways: HashMap<usize, usize> = HashMap::new();
+        ways.insert(0, 1);
+        for &joltage in &joltages[1..] {
+            let total = (1..=3)
+                .filter(|&step| step <= joltage)
+                .map(|step| ways.get(&(joltage - step)).copied().unwrap_or(0))
+                .sum();
+            ways.insert(joltage, total);
+        }
+        let part2 = ways[joltages.last().unwrap()];
+
+        (part1, part2).into()
     }
 }
 
@@ -46,7 +178,7 @@ fn get_all_nodes(adapters: &mut Vec<u32>) {
 //     }
 // }
 
-fn part1(adapters: &[u32]) -> u32 {
+fn part1(adapters: &[u32]) -> usize {
     // let mut ones = 0;
     // let mut threes = 0;
     // for [x, y] in adapters.array_windows() {
@@ -56,115 +188,107 @@ fn part1(adapters: &[u32]) -> u32 {
     // ones * threes
 
     // alternate way of solving part 1 using iterators.
-    let (diff_1, diff_3) =
-        adapters
-            .array_windows()
-            .map(|[x, y]| y - x)
-            .fold((0, 0), |(diff_1, diff_3), diff| match diff {
-                1 => (diff_1 + 1, diff_3),
-                3 => (diff_1, diff_3 + 1),
-                _ => unreachable!(),
-            });
+    let (diff_1, diff_3) = adapters.array_windows().map(|[x, y]| y - x).fold(
+        (0_usize, 0_usize),
+        |(diff_1, diff_3), diff| match diff {
+            1 => (diff_1 + 1, diff_3),
+            2 => (diff_1, diff_3),
+            3 => (diff_1, diff_3 + 1),
+            _ => unreachable!(),
+        },
+    );
 
     diff_1 * diff_3
 }
 
-fn part2(adapters: &[u32]) -> usize {
+/// Returns `None` if the count doesn't fit in a `usize`.
+fn part2(adapters: &[u32]) -> Option<usize> {
     adapters
         .array_windows()
         .map(|[x, y]| y - x)
-        .fold((1, 0, 0), |(diff_1, diff_2, diff_3), diff| match diff {
-            1 => (diff_1 + diff_2 + diff_3, diff_1, diff_2),
-            2 => (diff_1 + diff_2, 0, diff_1),
-            3 => (diff_1, 0, 0),
-            _ => unreachable!(),
+        .try_fold((1_usize, 0, 0), |(diff_1, diff_2, diff_3), diff| {
+            Some(match diff {
+                1 => (
+                    diff_1.checked_add(diff_2)?.checked_add(diff_3)?,
+                    diff_1,
+                    diff_2,
+                ),
+                2 => (diff_1.checked_add(diff_2)?, 0, diff_1),
+                3 => (diff_1, 0, 0),
+                _ => unreachable!(),
+            })
         })
-        .0
+        .map(|ways| ways.0)
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const SMALL_INPUT: &str = "16\n10\n15\n5\n1\n11\n7\n19\n6\n12\n4";
-    const INPUT: &str = "28\n33\n18\n42\n31\n14\n46\n20\n48\n47\n24\n23\n49\n45\n19\n38\n39\n11\n1\n32\n25\n35\n8\n17\n7\n9\n4\n2\n34\n10\n3";
-
     #[test]
-    fn example_part1_small() {
-        let adapters: Vec<u32> = {
-            let mut input: Vec<u32> = SMALL_INPUT
-                .lines()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect::<Vec<u32>>();
-            get_all_nodes(&mut input);
-            input
-        };
+    fn test_part2_graph() {
+        use crate::graph::Graph;
 
-        let actual = part1(&adapters);
-        let expected = 35;
-        assert_eq!(actual, expected);
-    }
+        // The fold in `part2` should agree with counting paths through the adapter graph.
+        for name in &["small", "example"] {
+            let input = example_input(10, name);
+            let adapters: Vec<u32> = {
+                let mut input: Vec<u32> = input
+                    .lines()
+                    .map(|n| n.parse().expect("Invalid number"))
+                    .collect::<Vec<u32>>();
+                get_all_nodes(&mut input);
+                input
+            };
 
-    #[test]
-    fn example_part1() {
-        let adapters: Vec<u32> = {
-            let mut input: Vec<u32> = INPUT
-                .lines()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect::<Vec<u32>>();
-            get_all_nodes(&mut input);
-            input
-        };
+            let mut graph = Graph::with_nodes(adapters.len());
+            for (i, x) in adapters.iter().enumerate() {
+                for (j, y) in adapters.iter().enumerate().skip(i + 1) {
+                    if y - x <= 3 {
+                        graph.add_edge(i, j, 1);
+                    }
+                }
+            }
 
-        let actual = part1(&adapters);
-        let expected = 220;
-        assert_eq!(actual, expected);
-    }
+            let expected = part2(&adapters);
+            let actual = graph.count_paths(0, adapters.len() - 1);
 
-    #[test]
-    fn example_part2_small() {
-        let adapters: Vec<u32> = {
-            let mut input: Vec<u32> = SMALL_INPUT
-                .lines()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect::<Vec<u32>>();
-            get_all_nodes(&mut input);
-            input
-        };
-
-        let expected = 8;
-        let actual = part2(&adapters);
-
-        assert_eq!(actual, expected)
+            assert_eq!(actual, expected)
+        }
     }
 
     #[test]
-    fn example_part2() {
-        let adapters: Vec<u32> = {
-            let mut input: Vec<u32> = INPUT
-                .lines()
-                .map(|n| n.parse().expect("Invalid number"))
-                .collect::<Vec<u32>>();
-            get_all_nodes(&mut input);
-            input
-        };
-
-        let expected = 19208;
-        let actual = part2(&adapters);
+    fn test_explain() {
+        use crate::explain::{Explain, Explanation};
 
-        assert_eq!(actual, expected)
+        let mut explanation = Explanation::new();
+        super::Solver::new()
+            .explain(&example_input(10, "small"), &mut explanation)
+            .unwrap();
+
+        let expected = "\
+Jolt differences, outlet to device
+  1 jolt   7
+  2 jolts  0
+  3 jolts  5
+Chain
+  adapters  11
+  device    22
+";
+        assert_eq!(explanation.to_string(), expected);
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day10.txt");
+        let input = require_input!(10);
 
         let expected: Solution = (2376_usize, 129586085429248_usize).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
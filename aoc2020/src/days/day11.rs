@@ -1,20 +1,18 @@
+use std::convert::TryFrom;
 use std::fmt;
+use std::io;
 
+use anyhow::{bail, ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::geometry::{Compass, Point2};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
+use crate::visualize::{FrameWriter, Pixmap, Visualize};
 
 pub(crate) struct Solver(());
 
-const ADJACENTS: [(isize, isize); 8] = [
-    (-1, -1),
-    (-1, 0),
-    (-1, 1),
-    (0, -1),
-    (0, 1),
-    (1, -1),
-    (1, 0),
-    (1, 1),
-];
-
 impl Solver {
     pub fn new() -> Self {
         let solver = Self(());
@@ -23,49 +21,219 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<Vec<State>>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         11
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let map: Vec<Vec<State>> = input
-            .lines()
-            .map(|l| l.chars().map(State::from).collect())
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("#.L"),
+            grid: true,
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<Vec<State>>> {
+        parse_input(input)
+    }
+
+    fn part1(&self, map: &Vec<Vec<State>>) -> anyhow::Result<usize> {
+        run_simulation(map, should_swap_p1).context("Part 1 never settles")
+    }
+
+    fn part2(&self, map: &Vec<Vec<State>>) -> anyhow::Result<usize> {
+        run_simulation(map, should_swap_p2).context("Part 2 never settles")
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Some(self)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+impl Visualize for Solver {
+    /// One frame per generation of each part's seating rules.
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()> {
+        let map = parse_input(input)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+        let mut layouts = Vec::new();
+        simulate(&map, should_swap_p1, |map| layouts.push(seat_frame(map)));
+        frames.write_all("part1", layouts)?;
+
+        let mut layouts = Vec::new();
+        simulate(&map, should_swap_p2, |map| layouts.push(seat_frame(map)));
+        frames.write_all("part2", layouts)
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        90
+    }
+
+    /// A `size` by `size` seat layout, all seats empty, that settles under both rules.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let size = size.max(1);
+        let mut map: Vec<Vec<State>> = (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| {
+                        if rng.chance(0.85) {
+                            State::Empty
+                        } else {
+                            State::Floor
+                        }
+                    })
+                    .collect()
+            })
             .collect();
 
-        let part1 = run_simulation(&map, should_swap_p1);
-        let part2 = run_simulation(&map, should_swap_p2);
+        // Random layouts can end up with seats flipping forever. Turn those into floor until
+        // nothing flips.
+        loop {
+            let mut unsettled = unsettled_seats(&map, should_swap_p1);
+            unsettled.extend(unsettled_seats(&map, should_swap_p2));
+            if unsettled.is_empty() {
+                break;
+            }
+            for (i, j) in unsettled {
+                map[i][j] = State::Floor;
+            }
+        }
 
-        (part1, part2).into()
+        map.iter()
+            .map(|row| row.iter().map(State::to_string).collect::<String>() + "\n")
+            .collect()
     }
 }
 
+impl Reference for Solver {
+    /// Rebuild the whole layout every round, scanning outwards from each seat one square at a time.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let layout: Vec<Vec<char>> = input.lines().map(|line| line.chars().collect()).collect();
+
+        // Occupied seats seen from (y, x), looking no further than `reach` squares.
+        let occupied = |layout: &[Vec<char>], y: usize, x: usize, reach: isize| -> usize {
+            let mut count = 0;
+            for dy in -1_isize..=1 {
+                for dx in -1_isize..=1 {
+                    if (dy, dx) == (0, 0) {
+                        continue;
+                    }
+                    for step in 1..=reach {
+                        let (ny, nx) = (y as isize + dy * step, x as isize + dx * step);
+                        if ny < 0 || nx < 0 || ny as usize >= layout.len() {
+                            break;
+                        }
+                        match layout[ny as usize].get(nx as usize) {
+                            Some('#') => {
+                                count += 1;
+                                break;
+                            }
+                            Some('L') | None => break,
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            count
+        };
+        let settle = |reach: isize, tolerance: usize| -> usize {
+            let mut layout = layout.clone();
+            loop {
+                let mut next = layout.clone();
+                for (y, row) in layout.iter().enumerate() {
+                    for (x, &seat) in row.iter().enumerate() {
+                        let seen = occupied(&layout, y, x, reach);
+                        if seat == 'L' && seen == 0 {
+                            next[y][x] = '#';
+                        } else if seat == '#' && seen >= tolerance {
+                            next[y][x] = 'L';
+                        }
+                    }
+                }
+                if next == layout {
+                    return layout.iter().flatten().filter(|&&seat| seat == '#').count();
+                }
+                layout = next;
+            }
+        };
+
+        (settle(1, 4), settle(isize::MAX, 5)).into()
+    }
+}
+
+fn parse_input(input: &str) -> anyhow::Result<Vec<Vec<State>>> {
+    let map = input
+        .lines()
+        .map(|l| l.chars().map(State::try_from).collect())
+        .collect::<anyhow::Result<Vec<Vec<_>>>>()?;
+
+    let width = map.first().map_or(0, Vec::len);
+    ensure!(
+        map.iter().all(|row| row.len() == width),
+        "The rows have different lengths"
+    );
+    Ok(map)
+}
+
+fn seat_frame(map: &[Vec<State>]) -> Pixmap {
+    let width = map.first().map_or(0, Vec::len);
+    let mut frame = Pixmap::new(width, map.len());
+    for (y, row) in map.iter().enumerate() {
+        for (x, state) in row.iter().enumerate() {
+            let colour = match state {
+                State::Floor => [255, 255, 255],
+                State::Empty => [160, 160, 160],
+                State::Occupied => [200, 40, 40],
+            };
+            frame.set(x, y, colour);
+        }
+    }
+    frame
+}
+
 fn should_swap_p1(map: &[Vec<State>], i: usize, j: usize) -> bool {
-    let mut neighbors = ADJACENTS
+    let seat = position(i, j);
+    let mut neighbors = Compass::ALL
         .iter()
-        .map(|&(dy, dx)| (i as isize + dy, j as isize + dx))
-        .filter_map(|(y, x)| map.get(y as usize).and_then(|v| v.get(x as usize)));
+        .filter_map(|direction| tile(map, seat + direction.offset()));
     match map[i][j] {
-        State::Empty => neighbors.all(|&c| c != State::Occupied),
-        State::Occupied => neighbors.filter(|&&c| c == State::Occupied).count() >= 4,
+        State::Empty => neighbors.all(|c| c != State::Occupied),
+        State::Occupied => neighbors.filter(|&c| c == State::Occupied).count() >= 4,
         _ => unreachable!(),
     }
 }
 
-fn find_neighbor(
-    map: &[Vec<State>],
-    (dy, dx): (isize, isize),
-    (i, j): (usize, usize),
-) -> Option<State> {
-    let (mut i, mut j) = (i as isize, j as isize);
+/// The seat at row `i` and column `j`, as a point.
+fn position(i: usize, j: usize) -> Point2 {
+    Point2::new(j as i64, i as i64)
+}
+
+/// The tile at `point`, or `None` off the edge of the map.
+fn tile(map: &[Vec<State>], point: Point2) -> Option<State> {
+    let row = map.get(usize::try_from(point.y()).ok()?)?;
+    row.get(usize::try_from(point.x()).ok()?).copied()
+}
+
+fn find_neighbor(map: &[Vec<State>], direction: Compass, seat: Point2) -> Option<State> {
+    let mut point = seat;
     loop {
-        i += dy;
-        j += dx;
-        let tile = map
-            .get(i as usize)
-            .and_then(|row| row.get(j as usize))
-            .copied();
+        point += direction.offset();
+        let tile = tile(map, point);
         if tile != Some(State::Floor) {
             return tile;
         }
@@ -73,9 +241,10 @@ fn find_neighbor(
 }
 
 fn should_swap_p2(map: &[Vec<State>], i: usize, j: usize) -> bool {
-    let mut neighbors = ADJACENTS
+    let seat = position(i, j);
+    let mut neighbors = Compass::ALL
         .iter()
-        .filter_map(|&dir| find_neighbor(&map, dir, (i, j)));
+        .filter_map(|&direction| find_neighbor(map, direction, seat));
 
     match map[i][j] {
         State::Empty => neighbors.all(|s| s != State::Occupied),
@@ -84,57 +253,114 @@ fn should_swap_p2(map: &[Vec<State>], i: usize, j: usize) -> bool {
     }
 }
 
+/// Occupied seats once the layout settles, or `None` if it doesn't.
 fn run_simulation<F: Fn(&[Vec<State>], usize, usize) -> bool>(
     map: &[Vec<State>],
     should_swap: F,
-) -> usize {
+) -> Option<usize> {
+    let settled = simulate(map, should_swap, |_| {})?;
+    Some(
+        settled
+            .iter()
+            .flatten()
+            .filter(|&&s| s == State::Occupied)
+            .count(),
+    )
+}
+
+/// Apply the seating rules until nothing changes, calling `on_generation` with every layout
+/// (starting with the initial one) along the way.
+///
+/// Returns `None` if the layout is still changing after [`generation_limit`] generations.
+fn simulate<F, G>(
+    map: &[Vec<State>],
+    should_swap: F,
+    mut on_generation: G,
+) -> Option<Vec<Vec<State>>>
+where
+    F: Fn(&[Vec<State>], usize, usize) -> bool,
+    G: FnMut(&[Vec<State>]),
+{
     let mut map = map.to_vec();
     let mut to_swap = Vec::new();
 
-    loop {
-        to_swap.clear();
+    for _ in 0..generation_limit(&map) {
+        on_generation(&map);
+        swap_seats(&mut map, &should_swap, &mut to_swap);
+        if to_swap.is_empty() {
+            return Some(map);
+        }
+    }
 
-        for (i, row) in map.iter().enumerate() {
-            for j in 0..row.len() {
-                if map[i][j] != State::Floor && should_swap(&map, i, j) {
-                    to_swap.push((i, j));
-                }
+    None
+}
+
+/// Generations after which a layout that's still changing is assumed to flip forever. Settling
+/// layouts take about one generation per row or column.
+fn generation_limit(map: &[Vec<State>]) -> usize {
+    let width = map.first().map_or(0, Vec::len);
+    200 + 4 * map.len().max(width)
+}
+
+/// Run one generation, leaving the seats that changed in `to_swap`.
+fn swap_seats<F>(map: &mut [Vec<State>], should_swap: &F, to_swap: &mut Vec<(usize, usize)>)
+where
+    F: Fn(&[Vec<State>], usize, usize) -> bool,
+{
+    to_swap.clear();
+
+    for (i, row) in map.iter().enumerate() {
+        for j in 0..row.len() {
+            if map[i][j] != State::Floor && should_swap(map, i, j) {
+                to_swap.push((i, j));
             }
         }
+    }
 
-        for &(i, j) in &to_swap {
-            map[i][j] = match map[i][j] {
-                State::Empty => State::Occupied,
-                State::Occupied => State::Empty,
-                State::Floor => unreachable!(),
-            };
-        }
+    for &(i, j) in to_swap.iter() {
+        map[i][j] = match map[i][j] {
+            State::Empty => State::Occupied,
+            State::Occupied => State::Empty,
+            State::Floor => unreachable!(),
+        };
+    }
+}
+
+/// Seats still changing long after a layout would normally have settled.
+fn unsettled_seats<F>(map: &[Vec<State>], should_swap: F) -> Vec<(usize, usize)>
+where
+    F: Fn(&[Vec<State>], usize, usize) -> bool,
+{
+    let mut map = map.to_vec();
+    let mut to_swap = Vec::new();
+
+    for _ in 0..generation_limit(&map) {
+        swap_seats(&mut map, &should_swap, &mut to_swap);
         if to_swap.is_empty() {
             break;
         }
     }
 
-    map.iter()
-        .flatten()
-        .filter(|&&s| s == State::Occupied)
-        .count()
+    to_swap
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum State {
+pub(crate) enum State {
     Floor,
     Empty,
     Occupied,
 }
 
-impl From<char> for State {
-    fn from(c: char) -> Self {
-        match c {
+impl TryFrom<char> for State {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> anyhow::Result<Self> {
+        Ok(match c {
             '.' => Self::Floor,
             'L' => Self::Empty,
             '#' => Self::Occupied,
-            _ => unreachable!(),
-        }
+            _ => bail!("Invalid tile: {:?}", c),
+        })
     }
 }
 
@@ -155,53 +381,13 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-L.LL.LL.LL
-LLLLLLL.LL
-L.L.L..L..
-LLLL.LL.LL
-L.LL.LL.LL
-L.LLLLL.LL
-..L.L.....
-LLLLLLLLLL
-L.LLLLLL.L
-L.LLLLL.LL";
-
-    #[test]
-    fn example_part1() {
-        let map: Vec<Vec<State>> = INPUT
-            .lines()
-            .map(|l| l.chars().map(State::from).collect())
-            .collect();
-
-        let expected = 37;
-
-        let actual = run_simulation(&map, should_swap_p1);
-
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let map: Vec<Vec<State>> = INPUT
-            .lines()
-            .map(|l| l.chars().map(State::from).collect())
-            .collect();
-
-        let expected = 26;
-
-        let actual = run_simulation(&map, should_swap_p2);
-
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day11.txt");
+        let input = require_input!(11);
 
         let expected: Solution = (2178, 1978).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,4 +1,15 @@
+use std::convert::TryFrom;
+use std::io;
+use std::str::FromStr;
+
+use anyhow::{bail, ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::geometry::{Compass, Point2, Rotation};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
+use crate::visualize::{FrameWriter, Svg, Visualize};
 
 pub(crate) struct Solver(());
 
@@ -10,136 +21,210 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<Action>;
+    type Part1 = i32;
+    type Part2 = i32;
+
     fn day(&self) -> u8 {
         12
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let actions = input.lines().map(Action::from).collect::<Vec<Action>>();
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789NSEWLRF"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<Action>> {
+        parse(input)
+    }
 
-        let part1 = part1(&actions);
-        let part2 = part2(&actions);
+    fn part1(&self, actions: &Vec<Action>) -> anyhow::Result<i32> {
+        part1(actions).context("The ship sails too far in part 1")
+    }
 
-        (part1, part2).into()
+    fn part2(&self, actions: &Vec<Action>) -> anyhow::Result<i32> {
+        part2(actions).context("The ship sails too far in part 2")
     }
-}
 
-fn part1(actions: &[Action]) -> i32 {
-    let mut ship = Ship::new();
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Some(self)
+    }
 
-    for action in actions {
-        ship.move_ship(action);
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
     }
 
-    ship.x.abs() + ship.y.abs()
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
 }
 
-fn part2(actions: &[Action]) -> i32 {
-    let mut ship = Ship::new();
-    let mut waypoint = Waypoint::new();
+impl Visualize for Solver {
+    /// The path the ship takes in each part.
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()> {
+        let actions = parse(input)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
 
-    for action in actions {
-        match *action {
-            Action::F(n) => {
-                ship.x += waypoint.x * n as i32;
-                ship.y += waypoint.y * n as i32;
-            }
-            _ => waypoint.move_waypoint(&action),
-        }
-    }
+        // A ship that sails out of range still gets the path up to that point drawn.
+        let mut path = vec![Point2::ORIGIN];
+        sail_part1(&actions, |ship| path.push(ship));
+        frames.write("part1", path_frame(&path))?;
 
-    ship.x.abs() + ship.y.abs()
-}
+        let mut path = vec![Point2::ORIGIN];
+        sail_part2(&actions, |ship| path.push(ship));
+        frames.write("part2", path_frame(&path))?;
 
-#[derive(Debug)]
-struct Ship {
-    x: i32,
-    y: i32,
-    dir: Direction,
+        Ok(())
+    }
 }
 
-impl Ship {
-    fn new() -> Self {
-        Self {
-            x: 0,
-            y: 0,
-            dir: Direction::E,
-        }
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        780
     }
 
-    fn move_ship(&mut self, action: &Action) {
-        match *action {
-            Action::N(n) => self.y += n as i32,
-            Action::S(n) => self.y -= n as i32,
-            Action::E(n) => self.x += n as i32,
-            Action::W(n) => self.x -= n as i32,
-            Action::F(n) => match self.dir {
-                Direction::N => self.y += n as i32,
-                Direction::S => self.y -= n as i32,
-                Direction::E => self.x += n as i32,
-                Direction::W => self.x -= n as i32,
-            },
-            _ => self.turn(action),
-        }
+    /// `size` navigation instructions, with moves small enough to keep the ship in `i32` range.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let (action, value) = match rng.range(0..7) {
+                    0..=3 => (*rng.choose(&['N', 'S', 'E', 'W']), rng.range(1..6)),
+                    4 | 5 => (*rng.choose(&['L', 'R']), 90 * rng.range(1..4)),
+                    _ => ('F', rng.range(1..101)),
+                };
+                format!("{}{}\n", action, value)
+            })
+            .collect()
     }
+}
 
-    fn turn(&mut self, action: &Action) {
-        self.dir = self.dir.get_direction(action);
+impl Reference for Solver {
+    /// Track positions as plain vectors, turning them 90 degrees at a time.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let actions: Vec<(char, i32)> = input
+            .lines()
+            .map(|line| (line.chars().next().unwrap(), line[1..].parse().unwrap()))
+            .collect();
+        let turn_left = |(x, y): (i32, i32)| (-y, x);
+
+        // `moves_ship` says whether N/S/E/W move the ship or the vector it sails along.
+        let sail = |mut heading: (i32, i32), moves_ship: bool| -> i32 {
+            let mut ship = (0, 0);
+            for &(action, value) in &actions {
+                let target = if moves_ship { &mut ship } else { &mut heading };
+                match action {
+                    'N' => target.1 += value,
+                    'S' => target.1 -= value,
+                    'E' => target.0 += value,
+                    'W' => target.0 -= value,
+                    'L' => {
+                        for _ in 0..value / 90 {
+                            heading = turn_left(heading);
+                        }
+                    }
+                    'R' => {
+                        for _ in 0..(360 - value) / 90 {
+                            heading = turn_left(heading);
+                        }
+                    }
+                    _ => {
+                        ship.0 += heading.0 * value;
+                        ship.1 += heading.1 * value;
+                    }
+                }
+            }
+            ship.0.abs() + ship.1.abs()
+        };
+
+        (sail((1, 0), true), sail((10, 1), false)).into()
     }
 }
 
-#[derive(Debug)]
-struct Waypoint {
-    x: i32,
-    y: i32,
+/// Draw the ship's path with north pointing up, marking where it set off.
+fn path_frame(path: &[Point2]) -> Svg {
+    let points: Vec<_> = path
+        .iter()
+        .map(|ship| (ship.x() as f64, ship.y() as f64))
+        .collect();
+
+    let mut svg = Svg::fitting(points.iter().copied(), 10.0);
+    svg.polyline(&points, "navy");
+    svg.circle((0.0, 0.0), 5.0, "red");
+    svg
 }
 
-impl Waypoint {
-    fn new() -> Self {
-        Self { x: 10, y: 1 }
-    }
+fn parse(input: &str) -> anyhow::Result<Vec<Action>> {
+    input.lines().map(str::parse).collect()
+}
+
+fn part1(actions: &[Action]) -> Option<i32> {
+    distance(sail_part1(actions, |_| {})?)
+}
+
+fn part2(actions: &[Action]) -> Option<i32> {
+    distance(sail_part2(actions, |_| {})?)
+}
 
-    fn move_waypoint(&mut self, action: &Action) {
-        match *action {
-            Action::N(n) => self.y += n as i32,
-            Action::S(n) => self.y -= n as i32,
-            Action::E(n) => self.x += n as i32,
-            Action::W(n) => self.x -= n as i32,
-            Action::L(_) | Action::R(_) => self.rotate(&action),
-            Action::F(_) => unreachable!(),
+/// How far the ship ends up from where it set off, or `None` if that doesn't fit in an `i32`.
+fn distance(ship: Point2) -> Option<i32> {
+    i32::try_from(ship.manhattan()?).ok()
+}
+
+/// Move the ship itself, calling `on_move` after every action.
+///
+/// Returns `None` if the ship sails out of `i64` range.
+fn sail_part1(actions: &[Action], mut on_move: impl FnMut(Point2)) -> Option<Point2> {
+    let mut ship = Point2::ORIGIN;
+    let mut heading = Compass::E;
+
+    for &action in actions {
+        match action {
+            Action::N(n) => ship = step(ship, Compass::N, n)?,
+            Action::S(n) => ship = step(ship, Compass::S, n)?,
+            Action::E(n) => ship = step(ship, Compass::E, n)?,
+            Action::W(n) => ship = step(ship, Compass::W, n)?,
+            Action::L(_) | Action::R(_) => heading = heading.rotate(action.rotation()),
+            Action::F(n) => ship = step(ship, heading, n)?,
         }
+        on_move(ship);
     }
 
-    fn rotate(&mut self, action: &Action) {
-        let rotation = match *action {
-            Action::L(deg) => 360 - deg,
-            Action::R(deg) => deg,
-            _ => 0,
-        };
+    Some(ship)
+}
 
-        match rotation {
-            90 => {
-                let tmp = self.x;
-                self.x = self.y;
-                self.y = -tmp;
-            }
-            180 => {
-                self.x = -self.x;
-                self.y = -self.y;
-            }
-            270 => {
-                let tmp = self.x;
-                self.x = -self.y;
-                self.y = tmp;
-            }
-            _ => {}
+/// Move the ship towards the waypoint, calling `on_move` after every action.
+///
+/// Returns `None` if the ship or waypoint goes out of `i64` range.
+fn sail_part2(actions: &[Action], mut on_move: impl FnMut(Point2)) -> Option<Point2> {
+    let mut ship = Point2::ORIGIN;
+    let mut waypoint = Point2::new(10, -1);
+
+    for &action in actions {
+        match action {
+            Action::N(n) => waypoint = step(waypoint, Compass::N, n)?,
+            Action::S(n) => waypoint = step(waypoint, Compass::S, n)?,
+            Action::E(n) => waypoint = step(waypoint, Compass::E, n)?,
+            Action::W(n) => waypoint = step(waypoint, Compass::W, n)?,
+            Action::L(_) | Action::R(_) => waypoint = waypoint.checked_rotate(action.rotation())?,
+            Action::F(n) => ship = ship.checked_add(waypoint.checked_mul(i64::from(n))?)?,
         }
+        on_move(ship);
     }
+
+    Some(ship)
+}
+
+/// `point` moved `n` steps towards `direction`.
+fn step(point: Point2, direction: Compass, n: u32) -> Option<Point2> {
+    point.checked_add(direction.offset().checked_mul(i64::from(n))?)
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
-enum Action {
+pub(crate) enum Action {
     /// North
     N(u32),
     /// South
@@ -156,12 +241,37 @@ enum Action {
     F(u32),
 }
 
-impl From<&str> for Action {
-    fn from(a: &str) -> Self {
-        let mut iter = a.chars();
-        let action = iter.next().unwrap();
-        let val = a[1..].trim().parse::<u32>().unwrap();
-        match action {
+impl Action {
+    /// Which way a turn turns, or no way for any other action.
+    fn rotation(self) -> Rotation {
+        let rotation = match self {
+            Self::L(degrees) => Rotation::counter_clockwise(i64::from(degrees)),
+            Self::R(degrees) => Rotation::clockwise(i64::from(degrees)),
+            _ => None,
+        };
+        // Parsing only lets through turns by right angles.
+        rotation.unwrap_or(Rotation::NONE)
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(a: &str) -> anyhow::Result<Self> {
+        let action = a.chars().next().context("Empty action")?;
+        let val = a[action.len_utf8()..]
+            .trim()
+            .parse::<u32>()
+            .with_context(|| format!("Invalid action: '{}'", a))?;
+        if let 'L' | 'R' = action {
+            ensure!(
+                val <= 360 && val.is_multiple_of(90),
+                "Invalid turn: '{}'",
+                a
+            );
+        }
+
+        Ok(match action {
             'N' => Self::N(val),
             'S' => Self::S(val),
             'E' => Self::E(val),
@@ -169,57 +279,8 @@ impl From<&str> for Action {
             'L' => Self::L(val),
             'R' => Self::R(val),
             'F' => Self::F(val),
-            _ => unreachable!(),
-        }
-    }
-}
-
-#[derive(Debug)]
-enum Direction {
-    /// North
-    N,
-    /// South
-    S,
-    /// East
-    E,
-    /// West
-    W,
-}
-
-impl Direction {
-    fn get_direction(&self, action: &Action) -> Self {
-        let rotation = match *action {
-            Action::L(deg) => 360 - deg,
-            Action::R(deg) => deg,
-            _ => 0,
-        };
-
-        match self {
-            Self::N => match rotation {
-                90 => Self::E,
-                180 => Self::S,
-                270 => Self::W,
-                _ => Self::N,
-            },
-            Self::S => match rotation {
-                90 => Self::W,
-                180 => Self::N,
-                270 => Self::E,
-                _ => Self::S,
-            },
-            Self::E => match rotation {
-                90 => Self::S,
-                180 => Self::W,
-                270 => Self::N,
-                _ => Self::E,
-            },
-            Self::W => match rotation {
-                90 => Self::N,
-                180 => Self::E,
-                270 => Self::S,
-                _ => Self::W,
-            },
-        }
+            _ => bail!("Invalid action: '{}'", a),
+        })
     }
 }
 
@@ -227,19 +288,13 @@ impl Direction {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-F10
-N3
-F7
-R90
-F11";
-
     #[test]
     fn test_parse_action() {
-        let actual = INPUT.lines().map(Action::from).collect::<Vec<Action>>();
+        let actual = parse(&example_input(12, "example")).unwrap();
         let expected = vec![
             Action::F(10),
             Action::N(3),
@@ -250,31 +305,13 @@ F11";
         assert_eq!(actual, expected)
     }
 
-    #[test]
-    fn example_part1() {
-        let actions = INPUT.lines().map(Action::from).collect::<Vec<Action>>();
-        let expected = 25;
-        let actual = part1(&actions);
-
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let actions = INPUT.lines().map(Action::from).collect::<Vec<Action>>();
-        let expected = 286;
-        let actual = part2(&actions);
-
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day12.txt");
+        let input = require_input!(12);
 
         let expected: Solution = (757, 51249).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,4 +1,10 @@
 #![allow(clippy::many_single_char_names)]
+use anyhow::{bail, ensure, Context};
+
+use crate::explain::{Explain, Explanation};
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -11,37 +17,189 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = (u32, Vec<(isize, isize)>);
+    type Part1 = isize;
+    type Part2 = isize;
+
     fn day(&self) -> u8 {
         13
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let (earliest, busses) = parse_input(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789,x"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<(u32, Vec<(isize, isize)>)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, (earliest, busses): &(u32, Vec<(isize, isize)>)) -> anyhow::Result<isize> {
+        part1(*earliest, busses).context("The wait is too long")
+    }
+
+    fn part2(&self, (_, busses): &(u32, Vec<(isize, isize)>)) -> anyhow::Result<isize> {
+        part2(busses).context("The bus IDs don't line up")
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Some(self)
+    }
+}
 
-        let part1 = part1(earliest, &busses);
-        let part2 = part2(&busses);
+impl Explain for Solver {
+    /// The congruence each bus adds for part 2, and the timestamp that satisfies them all.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()> {
+        let (_, busses) = parse_input(input)?;
+        let (residues, modulii) = congruences(&busses);
 
-        (part1, part2).into()
+        let section = explanation.section("Congruences fed to the CRT (t = residue mod bus)");
+        for ((&(offset, _), residue), modulus) in busses.iter().zip(&residues).zip(&modulii) {
+            section.row(
+                format!("bus {} at +{}", modulus, offset),
+                format!("t = {} mod {}", residue, modulus),
+            );
+        }
+        let product = modulii
+            .iter()
+            .try_fold(1_isize, |product, &m| product.checked_mul(m));
+        explanation
+            .section("Solution")
+            .row(
+                "product",
+                product.map_or("overflows".to_owned(), |p| p.to_string()),
+            )
+            .row(
+                "t",
+                crt(&residues, &modulii).context("The bus IDs don't line up")?,
+            );
+        Ok(())
     }
 }
 
-fn part1(earliest: u32, busses: &[(isize, isize)]) -> isize {
-    (0..)
-        .filter_map(|i| {
-            busses
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        9
+    }
+
+    /// A timestamp and `size` buses with distinct prime IDs, spread out between `x`s.
+    ///
+    /// Every bus ID is larger than its offset, and their product is kept small enough for the
+    /// Chinese remainder theorem to stay in `isize` range.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let primes: Vec<usize> = (7..1000)
+            .filter(|&n| (2..n).take_while(|d| d * d <= n).all(|d| n % d != 0))
+            .collect();
+
+        let mut buses: Vec<usize> = Vec::new();
+        let mut product: u128 = 1;
+        while buses.len() < size.max(1) {
+            let candidates: Vec<usize> = primes
                 .iter()
-                .find(|&&(_, b)| (i + earliest as isize) % b == 0)
-                .map(|(_, b)| i * b)
-        })
-        .next()
-        .expect("No solution")
+                .copied()
+                .filter(|&p| !buses.contains(&p) && product * p as u128 * 1000 < 1 << 58)
+                .collect();
+            if candidates.is_empty() {
+                break;
+            }
+            let bus = *rng.choose(&candidates);
+            product *= bus as u128;
+            buses.push(bus);
+        }
+        rng.shuffle(&mut buses);
+
+        // Put the buses in order, padded with `x`s, as long as each stays past its offset.
+        let mut schedule: Vec<String> = Vec::new();
+        for bus in buses {
+            while schedule.len() + 1 < bus && rng.chance(0.6) {
+                schedule.push(String::from("x"));
+            }
+            if schedule.len() >= bus {
+                continue;
+            }
+            schedule.push(bus.to_string());
+        }
+
+        format!(
+            "{}\n{}\n",
+            rng.range(100_000..1_000_000),
+            schedule.join(",")
+        )
+    }
+}
+
+impl Reference for Solver {
+    /// Wait minute by minute for part 1. For part 2, step through the timestamps that suit the
+    /// buses placed so far, adding one bus at a time.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let mut lines = input.lines();
+        let earliest: isize = lines.next().unwrap().parse().unwrap();
+        let buses: Vec<(isize, isize)> = lines
+            .next()
+            .unwrap()
+            .split(',')
+            .enumerate()
+            .filter(|&(_, id)| id != "x")
+            .map(|(offset, id)| (offset as isize, id.parse().unwrap()))
+            .collect();
+
+        let mut part1 = None;
+        for time in earliest.. {
+            if let Some(&(_, id)) = buses.iter().find(|&&(_, id)| time % id == 0) {
+                part1 = Some((time - earliest) * id);
+                break;
+            }
+        }
+
+        let (mut time, mut step) = (0, 1);
+        for &(offset, id) in &buses {
+            while (time + offset) % id != 0 {
+                time += step;
+            }
+            step *= id;
+        }
+
+        (part1.unwrap(), time).into()
+    }
+}
+
+/// Returns `None` if the answer doesn't fit in an `isize`.
+fn part1(earliest: u32, busses: &[(isize, isize)]) -> Option<isize> {
+    // The first bus to leave is the one with the shortest wait, going by list order on ties.
+    let (wait, bus) = busses
+        .iter()
+        .map(|&(_, b)| ((b - earliest as isize % b) % b, b))
+        .min_by_key(|&(wait, _)| wait)?;
+    wait.checked_mul(bus)
+}
+
+/// Returns `None` if the bus IDs aren't pairwise coprime, or their product doesn't fit in an
+/// `isize`.
+fn part2(busses: &[(isize, isize)]) -> Option<isize> {
+    let (residues, modulii) = congruences(busses);
+    crt(&residues, &modulii)
 }
 
-fn part2(busses: &[(isize, isize)]) -> isize {
+/// The residues and modulii for the CRT: bus `b` at offset `i` must leave at `-i` mod `b`.
+fn congruences(busses: &[(isize, isize)]) -> (Vec<isize>, Vec<isize>) {
     let modulii = busses.iter().map(|&(_, b)| b).collect::<Vec<_>>();
-    let residues = busses.iter().map(|&(i, b)| b - i).collect::<Vec<_>>();
-    crt(&residues, &modulii).unwrap()
+    let residues = busses
+        .iter()
+        .map(|&(i, b)| (-i).rem_euclid(b))
+        .collect::<Vec<_>>();
+    (residues, modulii)
 }
 
 /// Extended Euclidean Algorithm
@@ -79,73 +237,67 @@ fn modulus_inverse(m_i: isize, modulus: isize) -> Option<isize> {
 
 /// Chinese Remainder Theorem
 fn crt(residues: &[isize], modulii: &[isize]) -> Option<isize> {
-    let prod: isize = modulii.iter().product();
-    let mut sum = 0;
+    let prod = modulii
+        .iter()
+        .try_fold(1_isize, |prod, &modulus| prod.checked_mul(modulus))?;
+    // Each term is below `prod * modulus`, which always fits in an `i128`.
+    let mut sum = 0_i128;
 
     for (&modulus, &residue) in modulii.iter().zip(residues) {
         let m_i = prod / modulus;
         let mod_inverse = modulus_inverse(m_i, modulus)?;
-        sum += residue * m_i * mod_inverse;
+        let term = residue as i128 * m_i as i128 % prod as i128 * mod_inverse as i128;
+        sum = (sum + term) % prod as i128;
     }
 
-    Some(sum % prod)
+    Some(sum as isize)
 }
 
-fn parse_input(input: &str) -> (u32, Vec<(isize, isize)>) {
+fn parse_input(input: &str) -> anyhow::Result<(u32, Vec<(isize, isize)>)> {
     let mut iter = input.lines();
-    let earliest = iter.next().unwrap().parse::<u32>().unwrap();
-    let busses: Vec<(isize, isize)> = iter
+    let earliest = iter.next().context("Missing timestamp")?;
+    let earliest = earliest
+        .parse::<u32>()
+        .with_context(|| format!("Invalid timestamp: {}", earliest))?;
+    let busses = iter
         .next()
-        .unwrap()
+        .context("Missing bus IDs")?
         .split(',')
         .enumerate()
-        .filter_map(|(i, l)| l.parse().ok().map(|b| (i as isize, b)))
-        .collect();
+        .filter(|&(_, l)| l != "x")
+        .map(|(i, l)| match l.parse() {
+            Ok(b) if b > 0 => Ok((i as isize, b)),
+            _ => bail!("Invalid bus ID: {}", l),
+        })
+        .collect::<anyhow::Result<Vec<(isize, isize)>>>()?;
+    ensure!(!busses.is_empty(), "No buses are in service");
 
-    (earliest, busses)
+    Ok((earliest, busses))
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-939
-7,13,x,x,59,x,31,19";
-
     #[test]
     fn test_parse_input() {
         let expected = (939, vec![(0, 7), (1, 13), (4, 59), (6, 31), (7, 19)]);
-        let actual = parse_input(INPUT);
+        let input = example_input(13, "example");
+        let actual = parse_input(&input).unwrap();
         assert_eq!(actual, expected);
     }
 
-    #[test]
-    fn example_part1() {
-        let (earliest, busses) = parse_input(INPUT);
-        let expected = 295;
-        let actual = part1(earliest, &busses);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let (_, busses) = parse_input(INPUT);
-        let expected = 1_068_781;
-        let actual = part2(&busses);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day13.txt");
+        let input = require_input!(13);
 
         let expected: Solution = (261_isize, 807_435_693_182_510).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
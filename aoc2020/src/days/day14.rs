@@ -1,5 +1,12 @@
-use std::collections::HashMap;
+use std::convert::TryFrom;
 
+use anyhow::{bail, ensure, Context};
+
+use crate::bitset::BitSet;
+use crate::generate::{Generate, Rng};
+use crate::hash::FastMap;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,61 +19,181 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<Op<'a>>;
+    type Part1 = u64;
+    type Part2 = u64;
+
     fn day(&self) -> u8 {
         14
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let ops = input.lines().map(Op::from).collect::<Vec<Op>>();
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789maskem[]=X "),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Vec<Op<'a>>> {
+        parse(input)
+    }
+
+    fn part1(&self, ops: &Vec<Op<'_>>) -> anyhow::Result<u64> {
+        Ok(part1(ops))
+    }
+
+    fn part2(&self, ops: &Vec<Op<'_>>) -> anyhow::Result<u64> {
+        part2(ops)
+    }
 
-        let part1 = part1(&ops);
-        let part2 = part2(&ops);
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
 
-        (part1, part2).into()
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum Op {
-    Mask(Vec<u8>),
-    Mem(usize, u64),
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        100
+    }
+
+    /// `size` masks, each followed by a few writes. Masks have at most nine floating bits, like
+    /// the real inputs, so part 2 stays tractable.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let mut program = String::new();
+        for _ in 0..size {
+            let mut mask: Vec<u8> = (0..36)
+                .map(|_| if rng.chance(0.5) { b'0' } else { b'1' })
+                .collect();
+            for _ in 0..rng.range(0..10) {
+                mask[rng.range(0..36)] = b'X';
+            }
+            program.push_str("mask = ");
+            program.push_str(std::str::from_utf8(&mask).unwrap());
+            program.push('\n');
+
+            for _ in 0..rng.range(1..6) {
+                let address = rng.range(0..1 << 16);
+                let value = rng.range(0..1 << 36);
+                program.push_str(&format!("mem[{}] = {}\n", address, value));
+            }
+        }
+        program
+    }
 }
 
-impl From<&str> for Op {
-    fn from(line: &str) -> Self {
-        match &line[..3] {
-            "mem" => {
-                let mem = line[4..].split("] = ").collect::<Vec<_>>();
-                let reg = mem[0].parse().expect("Invalid input");
-                let val = mem[1].parse().expect("Invalid input");
-                Self::Mem(reg, val)
+impl Reference for Solver {
+    /// Apply masks to values and addresses as strings of 36 binary digits.
+    fn solve_reference(&self, input: &str) -> Solution {
+        // Every address `address` can stand for, with its floating bits filled in.
+        fn expand(address: &str) -> Vec<String> {
+            match address.find('X') {
+                None => vec![address.to_owned()],
+                Some(_) => ["0", "1"]
+                    .iter()
+                    .flat_map(|bit| expand(&address.replacen('X', bit, 1)))
+                    .collect(),
             }
-            "mas" => {
-                assert!(line[7..].len() == 36);
-                let mask = line[7..].as_bytes().to_vec();
-                Self::Mask(mask)
+        }
+
+        let mut mask = "";
+        let mut memory1: FastMap<String, u64> = FastMap::default();
+        let mut memory2: FastMap<String, u64> = FastMap::default();
+        for line in input.lines() {
+            let (target, value) = line.split_once(" = ").unwrap();
+            if target == "mask" {
+                mask = value;
+                continue;
+            }
+
+            let address: u64 = target
+                .trim_start_matches("mem[")
+                .trim_end_matches(']')
+                .parse()
+                .unwrap();
+            let address = format!("{:036b}", address);
+            let value: u64 = value.parse().unwrap();
+            let value_bits = format!("{:036b}", value);
+
+            let masked: String = mask
+                .chars()
+                .zip(value_bits.chars())
+                .map(|(m, v)| if m == 'X' { v } else { m })
+                .collect();
+            memory1.insert(address.clone(), u64::from_str_radix(&masked, 2).unwrap());
+
+            let floating: String = mask
+                .chars()
+                .zip(address.chars())
+                .map(|(m, a)| if m == '0' { a } else { m })
+                .collect();
+            for address in expand(&floating) {
+                memory2.insert(address, value);
             }
-            _ => unreachable!("Unable to match line: {}", line),
         }
+
+        (memory1.values().sum::<u64>(), memory2.values().sum::<u64>()).into()
     }
 }
 
-fn part1(ops: &[Op]) -> u64 {
-    let mut memory: HashMap<usize, u64> = Default::default();
-    let mut mask_0 = 0;
-    let mut mask_1 = 0;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Op<'a> {
+    /// The mask's 36 characters, borrowed from the input.
+    Mask(&'a str),
+    Mem(usize, u64),
+}
+
+/// Addresses and values are 36 bits wide, which also keeps the sums from overflowing.
+const WORD: u64 = 1 << 36;
+
+/// Most floating bits a mask may have, since a write touches `2^n` addresses. Real inputs have at
+/// most 9.
+const MAX_FLOATING: usize = 12;
+
+impl<'a> TryFrom<&'a str> for Op<'a> {
+    type Error = anyhow::Error;
+
+    fn try_from(line: &'a str) -> anyhow::Result<Self> {
+        if let Some(mask) = line.strip_prefix("mask = ") {
+            ensure!(
+                mask.len() == 36 && mask.bytes().all(|b| matches!(b, b'0' | b'1' | b'X')),
+                "Invalid mask: {}",
+                mask
+            );
+            Ok(Self::Mask(mask))
+        } else if let Some(mem) = line.strip_prefix("mem[") {
+            let (reg, val) = mem
+                .split_once("] = ")
+                .with_context(|| format!("Unable to match line: {}", line))?;
+            let word = |n: &str| n.parse::<u64>().ok().filter(|&n| n < WORD);
+            let reg = word(reg).with_context(|| format!("Invalid address: {}", reg))?;
+            let val = word(val).with_context(|| format!("Invalid value: {}", val))?;
+            Ok(Self::Mem(reg as usize, val))
+        } else {
+            bail!("Unable to match line: {}", line)
+        }
+    }
+}
+
+fn parse(input: &str) -> anyhow::Result<Vec<Op<'_>>> {
+    input.lines().map(Op::try_from).collect()
+}
+
+fn part1(ops: &[Op<'_>]) -> u64 {
+    let mut memory: FastMap<usize, u64> = Default::default();
+    let mut mask = Mask::default();
 
     for op in ops.iter() {
         match op {
-            Op::Mask(m) => {
-                let (m0, m1, _) = parse_mask(m);
-                mask_0 = m0;
-                mask_1 = m1;
-            }
+            Op::Mask(m) => mask = Mask::parse(m),
             Op::Mem(reg, val) => {
-                let val = (val | mask_1) & mask_0;
-                *memory.entry(*reg).or_default() = val;
+                let val = &(&BitSet::from(*val) | &mask.ones) - &mask.zeros;
+                *memory.entry(*reg).or_default() = val.to_u64().unwrap();
             }
         }
     }
@@ -74,90 +201,91 @@ fn part1(ops: &[Op]) -> u64 {
     memory.values().sum()
 }
 
-fn part2(ops: &[Op]) -> u64 {
-    let mut memory: HashMap<usize, u64> = Default::default();
-    let mut mask_1 = 0;
-    let mut floating_bits: Vec<u64> = Default::default();
+fn part2(ops: &[Op<'_>]) -> anyhow::Result<u64> {
+    let mut memory: FastMap<u64, u64> = Default::default();
+    let mut ones = 0;
+    let mut floating_bits: Vec<usize> = Default::default();
 
     for op in ops.iter() {
         match op {
             Op::Mask(m) => {
-                let (_, m1, floating_mask) = parse_mask(m);
-                mask_1 = m1;
+                let mask = Mask::parse(m);
 
-                floating_bits.clear();
-                for i in 0..36 {
-                    if floating_mask & (1 << i) != 0 {
-                        floating_bits.push(i);
-                    }
-                }
+                ensure!(
+                    mask.floating.len() <= MAX_FLOATING,
+                    "Too many floating bits in mask {}",
+                    m
+                );
+
+                // A mask is 36 bits wide, so it always fits.
+                ones = mask.ones.to_u64().unwrap();
+                floating_bits = mask.floating.iter().collect();
             }
             Op::Mem(reg, val) => {
+                let reg = *reg as u64 | ones;
                 let perms = 2_usize.pow(floating_bits.len() as u32);
                 for perm in 0..perms {
-                    let mut reg = *reg | mask_1 as usize;
+                    let mut address = reg;
 
-                    for (i, fb) in floating_bits.iter().enumerate() {
+                    for (i, &fb) in floating_bits.iter().enumerate() {
                         if perm & (1 << i) != 0 {
-                            reg |= 1 << fb;
+                            address |= 1 << fb;
                         } else {
-                            reg &= !(1 << fb);
+                            address &= !(1 << fb);
                         }
                     }
-                    *memory.entry(reg).or_default() = *val;
+                    *memory.entry(address).or_default() = *val;
                 }
             }
         }
     }
 
-    memory.values().sum()
+    Ok(memory.values().sum())
 }
 
-fn parse_mask(mask: &[u8]) -> (u64, u64, u64) {
-    assert!(mask.len() == 36);
-
-    let mask_0 = mask
-        .iter()
-        .rev()
-        .enumerate()
-        .filter(|(_, &b)| b == b'0')
-        .fold(u64::MAX, |acc, (i, _)| acc & !(1 << i));
-
-    let mask_1 = mask
-        .iter()
-        .rev()
-        .enumerate()
-        .filter(|(_, &b)| b == b'1')
-        .fold(0, |acc, (i, _)| acc | (1 << i));
-
-    let mask_x = mask
-        .iter()
-        .rev()
-        .enumerate()
-        .filter(|(_, &b)| b == b'X')
-        .fold(0, |acc, (i, _)| acc | (1 << i));
-
-    (mask_0, mask_1, mask_x)
+/// Which bits of a mask are `0`, `1` and `X`, numbered from the least significant.
+#[derive(Debug, Default)]
+struct Mask {
+    zeros: BitSet,
+    ones: BitSet,
+    floating: BitSet,
+}
+
+impl Mask {
+    fn parse(mask: &str) -> Self {
+        assert!(mask.len() == 36);
+
+        let bits = |kind: u8| {
+            mask.bytes()
+                .rev()
+                .enumerate()
+                .filter(|&(_, b)| b == kind)
+                .map(|(i, _)| i)
+                .collect()
+        };
+
+        Self {
+            zeros: bits(b'0'),
+            ones: bits(b'1'),
+            floating: bits(b'X'),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X
-mem[8] = 11
-mem[7] = 101
-mem[8] = 0";
-
     #[test]
     fn test_parse_input() {
-        let actual = INPUT.lines().map(Op::from).collect::<Vec<_>>();
+        let input = example_input(14, "part1");
+        let actual = parse(&input).unwrap();
         let expected = vec![
-            Op::Mask(b"XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X".to_vec()),
+            Op::Mask("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X"),
             Op::Mem(8, 11),
             Op::Mem(7, 101),
             Op::Mem(8, 0),
@@ -165,34 +293,13 @@ mem[8] = 0";
         assert_eq!(actual, expected)
     }
 
-    #[test]
-    fn example_part1() {
-        let ops: Vec<Op> = INPUT.lines().map(Op::from).collect();
-        let expected = 165;
-        let actual = part1(&ops);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        const INPUT: &str = "\
-mask = 000000000000000000000000000000X1001X
-mem[42] = 100
-mask = 00000000000000000000000000000000X0XX
-mem[26] = 1";
-        let ops: Vec<Op> = INPUT.lines().map(Op::from).collect();
-        let expected = 208;
-        let actual = part2(&ops);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day14.txt");
+        let input = require_input!(14);
 
         let expected: Solution = (4_297_467_072_083_u64, 5_030_603_328_768).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
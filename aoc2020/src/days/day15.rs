@@ -1,5 +1,10 @@
-use std::collections::HashMap;
+use anyhow::{bail, ensure, Context};
 
+use crate::generate::{Generate, Rng};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::progress::Progress;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,38 +17,171 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<u32>;
+    type Part1 = u32;
+    type Part2 = u32;
+
     fn day(&self) -> u8 {
         15
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let numbers = parse_input(input);
-        // let numbers = vec![0, 13, 16, 17, 1, 10, 6];
+    fn shape(&self) -> Shape {
+        shape()
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<u32>> {
+        parse_input(input)
+    }
+
+    fn part1(&self, numbers: &Vec<u32>) -> anyhow::Result<u32> {
+        Ok(part1(numbers))
+    }
+
+    fn part2(&self, numbers: &Vec<u32>) -> anyhow::Result<u32> {
+        Ok(part2(numbers))
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
 
-        let part1 = part1(&numbers);
-        let part2 = part2(&numbers);
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
 
-        (part1, part2).into()
+/// Plays both parts with part 2's flat table instead of part 1's map, which is faster for part 1
+/// too once the table is sized to the turns played.
+pub(crate) struct Flat(());
+
+impl Flat {
+    pub fn new() -> Self {
+        let solver = Self(());
+        assert_solver_day!(solver);
+        solver
     }
 }
 
-fn parse_input(input: &str) -> Vec<u32> {
-    input
+impl crate::Day for Flat {
+    type Parsed<'a> = Vec<u32>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn day(&self) -> u8 {
+        15
+    }
+
+    fn name(&self) -> &str {
+        "flat"
+    }
+
+    fn shape(&self) -> Shape {
+        shape()
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Vec<u32>> {
+        parse_input(input)
+    }
+
+    fn part1(&self, numbers: &Vec<u32>) -> anyhow::Result<u32> {
+        Ok(play_flat(numbers, PART1_TARGET))
+    }
+
+    fn part2(&self, numbers: &Vec<u32>) -> anyhow::Result<u32> {
+        Ok(play_flat(numbers, PART2_TARGET))
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        6
+    }
+
+    /// `size` distinct starting numbers.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let mut numbers: Vec<usize> = (0..20).collect();
+        rng.shuffle(&mut numbers);
+        let numbers: Vec<String> = numbers
+            .iter()
+            .take(size.clamp(1, 20))
+            .map(ToString::to_string)
+            .collect();
+        numbers.join(",") + "\n"
+    }
+}
+
+impl Reference for Solver {
+    /// Play the game turn by turn, remembering when each number was last spoken in a map.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let starting: Vec<usize> = input
+            .trim_end()
+            .split(',')
+            .map(|n| n.parse().unwrap())
+            .collect();
+        let play = |turns: usize| -> usize {
+            let mut spoken: FastMap<usize, usize> = FastMap::default();
+            let mut last = starting[0];
+            for turn in 1..turns {
+                let next = match starting.get(turn) {
+                    Some(&n) => n,
+                    None => spoken.get(&last).map_or(0, |&before| turn - before),
+                };
+                spoken.insert(last, turn);
+                last = next;
+            }
+            last
+        };
+
+        (play(2020), play(30_000_000)).into()
+    }
+}
+
+fn shape() -> Shape {
+    Shape {
+        blocks: Some(1),
+        alphabet: Some("0123456789,"),
+        ..Shape::default()
+    }
+}
+
+/// Parse the starting numbers, which must be distinct, fewer than the turns in part 1, and small
+/// enough to index part 2's table.
+fn parse_input(input: &str) -> anyhow::Result<Vec<u32>> {
+    let numbers = input
         .trim_end()
         .split(',')
-        .filter_map(|s| s.parse().ok())
-        .collect()
+        .map(|s| {
+            s.parse()
+                .ok()
+                .filter(|&n| n < PART2_TARGET)
+                .with_context(|| format!("Invalid starting number: {}", s))
+        })
+        .collect::<anyhow::Result<Vec<u32>>>()?;
+
+    ensure!(
+        numbers.len() < PART1_TARGET as usize,
+        "Too many starting numbers"
+    );
+    let mut seen = FastSet::default();
+    if let Some(n) = numbers.iter().find(|&&n| !seen.insert(n)) {
+        bail!("Starting number {} is repeated", n);
+    }
+
+    Ok(numbers)
 }
 
+const PART1_TARGET: u32 = 2020;
+const PART2_TARGET: u32 = 30_000_000;
+
 fn part1(numbers: &[u32]) -> u32 {
-    const TARGET: u32 = 2020;
+    const TARGET: u32 = PART1_TARGET;
 
     let mut seen = numbers
         .iter()
         .enumerate()
         .map(|(i, &n)| (n, i as u32 + 1))
-        .collect::<HashMap<_, _>>();
+        .collect::<FastMap<_, _>>();
 
     (numbers.len() as u32..TARGET).fold(*numbers.last().unwrap(), |last, turn| {
         seen.insert(last, turn)
@@ -53,16 +191,24 @@ fn part1(numbers: &[u32]) -> u32 {
 }
 
 fn part2(numbers: &[u32]) -> u32 {
-    const TARGET: u32 = 30_000_000;
+    play_flat(numbers, PART2_TARGET)
+}
 
-    let mut seen: Vec<Option<u32>> = vec![None; TARGET as usize];
+/// The number spoken on turn `target`, remembering when each number was last spoken in a table
+/// indexed by the number.
+fn play_flat(numbers: &[u32], target: u32) -> u32 {
+    // Every number spoken after the starting ones is an age, so less than `target`.
+    let size = numbers.iter().map(|&n| n + 1).fold(target, u32::max);
+    let mut seen: Vec<Option<u32>> = vec![None; size as usize];
 
     for (i, n) in numbers.iter().enumerate() {
         seen[*n as usize] = Some(i as u32);
     }
 
+    let mut progress = Progress::new("Day 15", "turns", u64::from(target));
     let mut num = 0_u32;
-    for i in numbers.len() as u32..TARGET - 1 {
+    for i in numbers.len() as u32..target - 1 {
+        progress.update(u64::from(i));
         match seen[num as usize] {
             None => {
                 seen[num as usize] = Some(i);
@@ -82,74 +228,29 @@ fn part2(numbers: &[u32]) -> u32 {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: [u32; 3] = [0, 3, 6];
-    const INPUT_EXTRA: [[u32; 3]; 6] = [
-        [1, 3, 2],
-        [2, 1, 3],
-        [1, 2, 3],
-        [2, 3, 1],
-        [3, 2, 1],
-        [3, 1, 2],
-    ];
-
     #[test]
     fn test_parse_input() {
-        const INPUT: &str = "0,3,6";
-
         let expected = vec![0, 3, 6];
-        let actual = parse_input(INPUT);
+        let actual = parse_input(&example_input(15, "example")).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
-    fn example_part1() {
-        let expected = 436;
-        let actual = part1(&INPUT);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_extra() {
-        let expected_vec = [1, 10, 27, 78, 438, 1836];
-        for (actual, &expected) in INPUT_EXTRA.iter().zip(expected_vec.iter()) {
-            let actual = part1(actual);
-            assert_eq!(actual, expected);
-        }
-    }
-
-    #[test]
-    fn example_part2() {
-        let expected = 175594;
-        let actual = part2(&INPUT);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    #[ignore = "test takes to long to run"]
-    fn example_part2_extra() {
-        use std::thread;
-
-        let expected_vec = [2578, 3544142, 261214, 6895259, 18, 362];
-
-        let handle = thread::spawn(move || {
-            for (actual, expected) in INPUT_EXTRA.iter().zip(expected_vec.iter()) {
-                assert_eq!(part2(actual), *expected);
-            }
-        });
-
-        handle.join().unwrap();
+    fn test_flat_large_start() {
+        assert_eq!(play_flat(&[0, 3, 2500], PART1_TARGET), part1(&[0, 3, 2500]));
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day15.txt");
+        let input = require_input!(15);
 
         let expected: Solution = (276, 31_916).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,5 +1,14 @@
+use std::convert::TryFrom;
 use std::ops::RangeInclusive;
 
+use anyhow::{ensure, Context};
+
+use crate::assignment;
+use crate::bitset::BitSet;
+use crate::explain::{Explain, Explanation};
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,22 +21,278 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Notes<'a>;
+    type Part1 = u64;
+    type Part2 = u64;
+
     fn day(&self) -> u8 {
         16
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let notes = Notes::from(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(3),
+            alphabet: Some("0123456789abcdefghijklmnopqrstuvwxyz ,-:"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Notes<'a>> {
+        Notes::try_from(input)
+    }
+
+    fn part1(&self, notes: &Notes<'_>) -> anyhow::Result<u64> {
+        Ok(part1(notes))
+    }
+
+    fn part2(&self, notes: &Notes<'_>) -> anyhow::Result<u64> {
+        part2(notes)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
 
-        let part1 = part1(&notes);
-        let part2 = part2(&notes);
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Some(self)
+    }
+}
 
-        (part1, part2).into()
+impl Explain for Solver {
+    /// Which field each column turned out to hold, with the value on your ticket.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()> {
+        let notes = Notes::try_from(input)?;
+        let order = field_order(&notes)?;
+
+        let section = explanation.section("Field for each column (* counts towards part 2)");
+        for (column, (field, value)) in order.iter().zip(&notes.your).enumerate() {
+            let marker = if field.starts_with("departure") {
+                " *"
+            } else {
+                ""
+            };
+            section.row(
+                format!("column {}", column + 1),
+                format!("{} = {}{}", field, value, marker),
+            );
+        }
+        Ok(())
     }
 }
 
-fn part1(notes: &Notes) -> u16 {
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        240
+    }
+
+    /// Notes on 20 fields with `size` nearby tickets, some of them invalid.
+    ///
+    /// Every field's ranges leave a gap that's only valid for the other fields. Tickets are built
+    /// so the column for the `n`th field holds values from the gaps of the fields before it,
+    /// which makes the field order unique.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const FIELDS: [&str; 20] = [
+            "departure location",
+            "departure station",
+            "departure platform",
+            "departure track",
+            "departure date",
+            "departure time",
+            "arrival location",
+            "arrival station",
+            "arrival platform",
+            "arrival track",
+            "class",
+            "duration",
+            "price",
+            "route",
+            "row",
+            "seat",
+            "train",
+            "type",
+            "wagon",
+            "zone",
+        ];
+        const LOW: usize = 25;
+        const HIGH: usize = 974;
+
+        // Field `n` (in `ranks` order) gets the gap `gaps[n]`.
+        let gap_width = (HIGH - LOW) / FIELDS.len() / 2;
+        let gaps: Vec<(usize, usize)> = (0..FIELDS.len())
+            .map(|n| {
+                let start = LOW + 10 + n * 2 * gap_width + rng.range(0..gap_width / 2);
+                (start, start + rng.range(gap_width / 2..gap_width))
+            })
+            .collect();
+        let mut ranks: Vec<usize> = (0..FIELDS.len()).collect();
+        rng.shuffle(&mut ranks);
+        let mut columns: Vec<usize> = (0..FIELDS.len()).collect();
+        rng.shuffle(&mut columns);
+
+        let mut notes = String::new();
+        for (field, name) in FIELDS.iter().enumerate() {
+            let (start, end) = gaps[ranks[field]];
+            notes.push_str(&format!(
+                "{}: {}-{} or {}-{}\n",
+                name,
+                LOW,
+                start - 1,
+                end + 1,
+                HIGH
+            ));
+        }
+
+        // A value valid for every field.
+        let anywhere = |rng: &mut Rng| loop {
+            let value = rng.range(LOW..HIGH + 1);
+            if gaps
+                .iter()
+                .all(|&(start, end)| value < start || value > end)
+            {
+                return value;
+            }
+        };
+        let ticket = |values: Vec<usize>| {
+            let mut ticket = vec![0; values.len()];
+            for (rank, value) in values.into_iter().enumerate() {
+                ticket[columns[rank]] = value;
+            }
+            let ticket: Vec<String> = ticket.iter().map(ToString::to_string).collect();
+            ticket.join(",") + "\n"
+        };
+
+        let your = (0..FIELDS.len()).map(|_| anywhere(rng)).collect();
+        notes.push_str("\nyour ticket:\n");
+        notes.push_str(&ticket(your));
+        notes.push_str("\nnearby tickets:\n");
+
+        // Part 1 sums the invalid values as a `u16`.
+        let mut invalid_sum = 0;
+        let mut nearby = Vec::new();
+        for n in 0..size.max(FIELDS.len()) {
+            let mut values: Vec<usize> = (0..FIELDS.len())
+                .map(|rank| match n {
+                    // Only fields of a higher rank accept a value in this gap.
+                    n if n < rank => rng.range(gaps[n].0..gaps[n].1 + 1),
+                    _ => anywhere(rng),
+                })
+                .collect();
+            // Only invalidate tickets that aren't needed to pin down the order.
+            if n >= FIELDS.len() && rng.chance(0.25) {
+                let value = if invalid_sum + 1000 < usize::from(u16::MAX) && rng.chance(0.5) {
+                    rng.range(HIGH + 1..1000)
+                } else {
+                    rng.range(0..LOW)
+                };
+                if invalid_sum + value < usize::from(u16::MAX) {
+                    values[rng.range(0..FIELDS.len())] = value;
+                    invalid_sum += value;
+                }
+            }
+            nearby.push(ticket(values));
+        }
+        rng.shuffle(&mut nearby);
+        notes.push_str(&nearby.concat());
+
+        notes
+    }
+}
+
+impl Reference for Solver {
+    /// Search for the field order by trying fields in every column that accepts them, starting
+    /// with the columns that accept the fewest.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let sections: Vec<&str> = input.split("\n\n").collect();
+        let fields: Vec<(&str, Vec<(usize, usize)>)> = sections[0]
+            .lines()
+            .map(|line| {
+                let (name, ranges) = line.split_once(": ").unwrap();
+                let ranges = ranges
+                    .split(" or ")
+                    .map(|range| {
+                        let (low, high) = range.split_once('-').unwrap();
+                        (low.parse().unwrap(), high.parse().unwrap())
+                    })
+                    .collect();
+                (name, ranges)
+            })
+            .collect();
+        let ticket =
+            |line: &str| -> Vec<usize> { line.split(',').map(|n| n.parse().unwrap()).collect() };
+        let your = ticket(sections[1].lines().nth(1).unwrap());
+        let nearby: Vec<Vec<usize>> = sections[2].lines().skip(1).map(ticket).collect();
+
+        let accepts = |field: usize, value: usize| {
+            fields[field]
+                .1
+                .iter()
+                .any(|&(low, high)| low <= value && value <= high)
+        };
+        let valid_anywhere = |value: usize| (0..fields.len()).any(|field| accepts(field, value));
+
+        let part1: usize = nearby
+            .iter()
+            .flatten()
+            .filter(|&&value| !valid_anywhere(value))
+            .sum();
+
+        let valid: Vec<&Vec<usize>> = nearby
+            .iter()
+            .filter(|ticket| ticket.iter().all(|&value| valid_anywhere(value)))
+            .collect();
+        let options: Vec<Vec<usize>> = (0..your.len())
+            .map(|column| {
+                (0..fields.len())
+                    .filter(|&field| valid.iter().all(|ticket| accepts(field, ticket[column])))
+                    .collect()
+            })
+            .collect();
+        let mut columns: Vec<usize> = (0..your.len()).collect();
+        columns.sort_by_key(|&column| options[column].len());
+
+        fn search(
+            columns: &[usize],
+            options: &[Vec<usize>],
+            order: &mut Vec<Option<usize>>,
+        ) -> bool {
+            let (&column, rest) = match columns.split_first() {
+                Some(split) => split,
+                None => return true,
+            };
+            for &field in &options[column] {
+                if !order.contains(&Some(field)) {
+                    order[column] = Some(field);
+                    if search(rest, options, order) {
+                        return true;
+                    }
+                    order[column] = None;
+                }
+            }
+            false
+        }
+        let mut order = vec![None; your.len()];
+        assert!(
+            search(&columns, &options, &mut order),
+            "No field order fits"
+        );
+
+        let part2: u64 = order
+            .iter()
+            .zip(&your)
+            .filter(|(field, _)| fields[field.unwrap()].0.starts_with("departure"))
+            .map(|(_, &value)| value as u64)
+            .product();
+
+        (part1 as u64, part2).into()
+    }
+}
+
+fn part1(notes: &Notes) -> u64 {
     notes
         .nearby
         .iter()
@@ -39,13 +304,22 @@ fn part1(notes: &Notes) -> u16 {
                 .flat_map(|(_, ranges)| ranges)
                 .any(|range| range.contains(n))
         })
+        .map(|&n| u64::from(n))
         .sum()
 }
 
-fn part2(notes: &Notes) -> u64 {
-    use std::collections::HashSet;
+fn part2(notes: &Notes) -> anyhow::Result<u64> {
+    field_order(notes)?
+        .iter()
+        .zip(&notes.your)
+        .filter(|(field, _)| field.starts_with("departure"))
+        .try_fold(1_u64, |product, (_, &n)| product.checked_mul(u64::from(n)))
+        .context("The product of the departure fields overflows")
+}
 
-    let mut rules = notes
+/// Work out which field each ticket column holds.
+fn field_order<'n>(notes: &Notes<'n>) -> anyhow::Result<Vec<&'n str>> {
+    let valid_tickets: Vec<&Vec<u16>> = notes
         .nearby
         .iter()
         .filter(|nearby| {
@@ -57,160 +331,112 @@ fn part2(notes: &Notes) -> u64 {
                     .any(|range| range.contains(n))
             })
         })
-        .fold(
-            vec![(1 << notes.rules.len()) - 1; notes.rules.len()],
-            |mut valid_possibilities, nearby_ticket| {
-                for (possibility, ticket_pos) in valid_possibilities.iter_mut().zip(nearby_ticket) {
-                    for (i, (_, ranges)) in notes.rules.iter().enumerate() {
-                        if !ranges.iter().any(|range| range.contains(ticket_pos)) {
-                            *possibility &= !(1_u32 << i);
-                        }
-                    }
-                }
-                valid_possibilities
-            },
-        );
-
-    for _ in 0..rules.len() {
-        let powers_of_two = rules
-            .iter()
-            .filter(|x| x.is_power_of_two())
-            .fold(0, |a, b| a | b);
-
-        let mut any_changes = false;
-        for num in rules.iter_mut().filter(|x| !x.is_power_of_two()) {
-            any_changes = true;
-            *num &= !powers_of_two;
-        }
-
-        if !any_changes {
-            break;
-        }
-    }
-
-    assert!(rules.iter().all(|x| x.is_power_of_two()));
+        .collect();
 
-    let departure_fields: HashSet<u32> = notes
-        .rules
-        .iter()
-        .enumerate()
-        .filter(|(_, (field, _))| field.starts_with("departure"))
-        .map(|(i, _)| 1 << i)
+    let candidates: Vec<BitSet> = (0..notes.your.len())
+        .map(|column| {
+            notes
+                .rules
+                .iter()
+                .enumerate()
+                .filter(|(_, (_, ranges))| {
+                    valid_tickets
+                        .iter()
+                        .all(|ticket| ranges.iter().any(|range| range.contains(&ticket[column])))
+                })
+                .map(|(i, _)| i)
+                .collect()
+        })
         .collect();
 
-    rules
-        .iter()
-        .enumerate()
-        .filter(|(_, value)| departure_fields.contains(value))
-        .map(|(i, _)| u64::from(notes.your[i]))
-        .product()
+    Ok(assignment::assign_indices(&candidates)
+        .context("Unable to match fields to ticket columns")?
+        .into_iter()
+        .map(|i| notes.rules[i].0)
+        .collect())
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Notes<'n> {
+pub(crate) struct Notes<'n> {
     rules: Vec<(&'n str, [RangeInclusive<u16>; 2])>,
     your: Vec<u16>,
     nearby: Vec<Vec<u16>>,
 }
 
-impl<'n> From<&'n str> for Notes<'n> {
-    fn from(input: &'n str) -> Self {
+impl<'n> TryFrom<&'n str> for Notes<'n> {
+    type Error = anyhow::Error;
+
+    fn try_from(input: &'n str) -> anyhow::Result<Self> {
         let mut input = input.split("\n\n");
+        let mut section = |name| input.next().with_context(|| format!("Missing {}", name));
 
-        let rules = input
-            .next()
-            .unwrap()
+        let rules = section("rules")?
             .lines()
             .map(|line| {
-                let mut line = line.split(':');
-                let field = line.next().unwrap();
-                let ranges = line
-                    .next()
-                    .unwrap()
+                let (field, ranges) = line
+                    .split_once(':')
+                    .with_context(|| format!("Invalid rule: {}", line))?;
+                let ranges = ranges
                     .split(" or ")
                     .map(|range| {
-                        let mut range = range.split('-');
-                        let start: u16 = range.next().unwrap().trim().parse().unwrap();
-                        let end: u16 = range.next().unwrap().trim().parse().unwrap();
+                        let (start, end) = range
+                            .split_once('-')
+                            .with_context(|| format!("Invalid range: {}", range))?;
+                        let start: u16 = start.trim().parse()?;
+                        let end: u16 = end.trim().parse()?;
 
-                        RangeInclusive::new(start, end)
+                        Ok(RangeInclusive::new(start, end))
                     })
-                    .collect::<Vec<RangeInclusive<u16>>>();
+                    .collect::<anyhow::Result<Vec<RangeInclusive<u16>>>>()
+                    .with_context(|| format!("Invalid rule: {}", line))?;
+                ensure!(ranges.len() == 2, "Rule doesn't have two ranges: {}", line);
 
-                (field, [ranges[0].clone(), ranges[1].clone()])
+                Ok((field, [ranges[0].clone(), ranges[1].clone()]))
             })
-            .collect();
+            .collect::<anyhow::Result<_>>()?;
 
-        let your = input
-            .next()
-            .unwrap()
-            .lines()
-            .nth(1)
-            .unwrap()
-            .split(',')
-            .map(|n| {
-                n.parse::<u16>()
-                    .unwrap_or_else(|_| panic!("Invalid digit: {}", n))
-            })
-            .collect::<Vec<u16>>();
+        let your = parse_ticket(
+            section("your ticket")?
+                .lines()
+                .nth(1)
+                .context("Missing your ticket")?,
+        )?;
 
-        let nearby = input
-            .next()
-            .unwrap()
+        let nearby = section("nearby tickets")?
             .lines()
             .skip(1)
-            .map(|line| {
-                line.split(',')
-                    .map(|n| {
-                        n.parse::<u16>()
-                            .unwrap_or_else(|_| panic!("Invalid digit: {}", n))
-                    })
-                    .collect()
-            })
-            .collect::<Vec<Vec<u16>>>();
+            .map(parse_ticket)
+            .collect::<anyhow::Result<Vec<Vec<u16>>>>()?;
+        ensure!(
+            nearby.iter().all(|ticket| ticket.len() == your.len()),
+            "Nearby tickets must have as many fields as yours"
+        );
 
-        Self {
+        Ok(Self {
             rules,
             your,
             nearby,
-        }
+        })
     }
 }
 
+fn parse_ticket(line: &str) -> anyhow::Result<Vec<u16>> {
+    line.split(',')
+        .map(|n| {
+            n.parse::<u16>()
+                .with_context(|| format!("Invalid digit: {}", n))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT_PART_1: &str = "\
-class: 1-3 or 5-7
-row: 6-11 or 33-44
-seat: 13-40 or 45-50
-
-your ticket:
-7,1,14
-
-nearby tickets:
-7,3,47
-40,4,50
-55,2,20
-38,6,12";
-
-    const INPUT_PART_2: &str = "\
-class: 0-1 or 4-19
-row: 0-5 or 8-19
-seat: 0-13 or 16-19
-
-your ticket:
-11,12,13
-
-nearby tickets:
-3,9,18
-15,1,5
-5,14,9";
-
     #[test]
     fn test_parse_from_str() {
         let expected = Notes {
@@ -236,24 +462,26 @@ nearby tickets:
                 vec![38, 6, 12],
             ],
         };
-        let actual = Notes::from(INPUT_PART_1);
+        let input = example_input(16, "part1");
+        let actual = Notes::try_from(input.as_str()).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
-    fn example_part1() {
-        let expected = 71;
-        let actual = part1(&Notes::from(INPUT_PART_1));
+    fn test_field_order() {
+        let input = example_input(16, "part2");
+        let expected = vec!["row", "class", "seat"];
+        let actual = field_order(&Notes::try_from(input.as_str()).unwrap()).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day16.txt");
+        let input = require_input!(16);
 
         let expected: Solution = (26_941_u16, 634_796_407_951_u64).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,8 +1,16 @@
-use std::collections::HashSet;
+use std::io;
 
+use anyhow::bail;
+
+use crate::generate::{Generate, Rng};
+use crate::geometry::Point;
+use crate::hash::FastSet;
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
+use crate::visualize::{Bitmap, FrameWriter, Visualize};
 
-type Grid<const D: usize> = HashSet<[isize; D]>;
+type Grid<const D: usize> = FastSet<Point<D>>;
 
 pub(crate) struct Solver(());
 
@@ -14,44 +22,169 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = (Grid<3>, Grid<4>);
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         17
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let part1 = get_active::<3>(input);
-        let part2 = get_active::<4>(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("#."),
+            grid: true,
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<(Grid<3>, Grid<4>)> {
+        Ok((parse_input(input)?, parse_input(input)?))
+    }
+
+    fn part1(&self, (grid, _): &(Grid<3>, Grid<4>)) -> anyhow::Result<usize> {
+        Ok(simulate(grid.clone(), |_| {}).len())
+    }
+
+    fn part2(&self, (_, grid): &(Grid<3>, Grid<4>)) -> anyhow::Result<usize> {
+        Ok(simulate(grid.clone(), |_| {}).len())
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Some(self)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
 
-        (part1, part2).into()
+impl Visualize for Solver {
+    /// One frame per cycle, with every z slice side by side (and every w slice below one another
+    /// for part 2).
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()> {
+        let invalid =
+            |error: anyhow::Error| io::Error::new(io::ErrorKind::InvalidData, error.to_string());
+
+        let mut slices = Vec::new();
+        simulate(parse_input::<3>(input).map_err(invalid)?, |grid| {
+            slices.push(slices_frame(grid))
+        });
+        frames.write_all("part1", slices)?;
+
+        let mut slices = Vec::new();
+        simulate(parse_input::<4>(input).map_err(invalid)?, |grid| {
+            slices.push(slices_frame(grid))
+        });
+        frames.write_all("part2", slices)
     }
 }
 
-fn get_active<const D: usize>(input: &str) -> usize {
-    let grid = parse_input::<D>(input);
-    let val = simulate(grid);
-    val.len()
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        8
+    }
+
+    /// A `size` by `size` starting slice.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size.max(1))
+            .map(|_| {
+                let mut row: String = (0..size.max(1))
+                    .map(|_| if rng.chance(0.45) { '#' } else { '.' })
+                    .collect();
+                row.push('\n');
+                row
+            })
+            .collect()
+    }
 }
 
-fn simulate<const D: usize>(mut grid: Grid<D>) -> Grid<D> {
-    let offsets = {
-        let mut offsets = offsets(0, [0; D]);
-        // Get rid of origin coordinate in offsets vec
-        let _ = offsets.pop();
-        offsets
-    };
+impl Reference for Solver {
+    /// Simulate a dense grid that is big enough for six cycles of growth, checking every cell.
+    fn solve_reference(&self, input: &str) -> Solution {
+        const CYCLES: usize = 6;
+        let start: Vec<Vec<bool>> = input
+            .lines()
+            .map(|line| line.chars().map(|c| c == '#').collect())
+            .collect();
+
+        // Cells are indexed [w][z][y][x]. Three dimensions just keep `w` at 0.
+        let boot = |dimensions: usize| -> usize {
+            let (height, width) = (start.len(), start[0].len());
+            let depth = 2 * CYCLES + 1;
+            let hyper = if dimensions == 4 { depth } else { 1 };
+            let (ys, xs) = (height + 2 * CYCLES, width + 2 * CYCLES);
+
+            let mut grid = vec![vec![vec![vec![false; xs]; ys]; depth]; hyper];
+            for (y, row) in start.iter().enumerate() {
+                for (x, &active) in row.iter().enumerate() {
+                    grid[hyper / 2][CYCLES][y + CYCLES][x + CYCLES] = active;
+                }
+            }
+
+            let active = |grid: &[Vec<Vec<Vec<bool>>>], w: usize, z: usize, y: usize, x: usize| {
+                grid[w][z][y][x]
+            };
+            for _ in 0..CYCLES {
+                let mut next = grid.clone();
+                for w in 0..hyper {
+                    for z in 0..depth {
+                        for y in 0..ys {
+                            for x in 0..xs {
+                                let mut neighbours = 0;
+                                for nw in w.saturating_sub(1)..(w + 2).min(hyper) {
+                                    for nz in z.saturating_sub(1)..(z + 2).min(depth) {
+                                        for ny in y.saturating_sub(1)..(y + 2).min(ys) {
+                                            for nx in x.saturating_sub(1)..(x + 2).min(xs) {
+                                                if (nw, nz, ny, nx) != (w, z, y, x)
+                                                    && active(&grid, nw, nz, ny, nx)
+                                                {
+                                                    neighbours += 1;
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                next[w][z][y][x] =
+                                    neighbours == 3 || (grid[w][z][y][x] && neighbours == 2);
+                            }
+                        }
+                    }
+                }
+                grid = next;
+            }
+
+            grid.iter()
+                .flatten()
+                .flatten()
+                .flatten()
+                .filter(|&&active| active)
+                .count()
+        };
+
+        (boot(3), boot(4)).into()
+    }
+}
+
+/// Run the six boot cycles, calling `on_cycle` with the initial grid and after every cycle.
+fn simulate<const D: usize>(mut grid: Grid<D>, mut on_cycle: impl FnMut(&Grid<D>)) -> Grid<D> {
+    let offsets = Point::<D>::neighbour_offsets();
+
+    on_cycle(&grid);
 
     for _ in 0..6 {
-        let mut active = HashSet::new();
-        let mut inactive = HashSet::new();
+        let mut active = FastSet::default();
+        let mut inactive = FastSet::default();
 
         for coord in &grid {
             let mut active_neighbors = 0;
             for offset in &offsets {
-                let mut neighbor = *coord;
-                for i in 0..D {
-                    neighbor[i] += offset[i];
-                }
+                let neighbor = *coord + *offset;
                 if grid.contains(&neighbor) {
                     active_neighbors += 1;
                 } else {
@@ -68,10 +201,7 @@ fn simulate<const D: usize>(mut grid: Grid<D>) -> Grid<D> {
             let mut active_neighbors = 0;
 
             for offset in &offsets {
-                let mut neighbor = *coord;
-                for i in 0..D {
-                    neighbor[i] += offset[i];
-                }
+                let neighbor = *coord + *offset;
 
                 if grid.contains(&neighbor) {
                     active_neighbors += 1;
@@ -84,131 +214,98 @@ fn simulate<const D: usize>(mut grid: Grid<D>) -> Grid<D> {
         }
 
         grid = active;
+        on_cycle(&grid);
     }
 
     grid
 }
 
-fn offsets<const D: usize>(idx: usize, mut offset_vec: [isize; D]) -> Vec<[isize; D]> {
-    if idx == D {
-        return vec![offset_vec];
+/// Draw the grid's `x`/`y` slices, laid out left to right by `z` and top to bottom by `w`.
+fn slices_frame<const D: usize>(grid: &Grid<D>) -> Bitmap {
+    let (mut min, mut max) = ([0; D], [0; D]);
+    for coord in grid {
+        for i in 0..D {
+            min[i] = min[i].min(coord[i]);
+            max[i] = max[i].max(coord[i]);
+        }
     }
 
-    let mut acc = Vec::with_capacity(usize::pow(3, (D - idx) as u32));
-    for &i in &[-1, 1, 0] {
-        offset_vec[idx] = i;
-        let extend_offsets = offsets(idx + 1, offset_vec);
-        acc.extend(extend_offsets);
+    let size = |i: usize| {
+        if i < D {
+            (max[i] - min[i] + 1) as usize
+        } else {
+            1
+        }
+    };
+    let offset = |coord: &Point<D>, i: usize| {
+        if i < D {
+            (coord[i] - min[i]) as usize
+        } else {
+            0
+        }
+    };
+
+    // Leave a blank line between neighbouring slices.
+    let (slice_width, slice_height) = (size(1) + 1, size(0) + 1);
+    let mut frame = Bitmap::new(slice_width * size(2) - 1, slice_height * size(3) - 1);
+    for coord in grid {
+        let x = offset(coord, 2) * slice_width + offset(coord, 1);
+        let y = offset(coord, 3) * slice_height + offset(coord, 0);
+        frame.set(x, y, true);
     }
 
-    acc
+    frame
 }
 
-fn parse_input<const D: usize>(input: &str) -> Grid<D> {
-    let mut grid = HashSet::new();
+fn parse_input<const D: usize>(input: &str) -> anyhow::Result<Grid<D>> {
+    let mut grid = FastSet::default();
     for (row, line) in input.lines().enumerate() {
         for (col, c) in line.chars().enumerate() {
-            let mut coord = [0; D];
+            let mut coord = Point::ORIGIN;
             match c {
                 '#' => {
-                    coord[0] = row as isize;
-                    coord[1] = col as isize;
+                    coord[0] = row as i64;
+                    coord[1] = col as i64;
                     grid.insert(coord);
                 }
                 '.' => (),
-                _ => panic!("Unrecognized char: {}", c),
+                _ => bail!("Unrecognized char: {}", c),
             }
         }
     }
 
-    grid
+    Ok(grid)
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-.#.
-..#
-###
-";
-
     #[test]
     fn test_parse_input() {
         let expected = {
             let coords = [[0, 1, 0], [1, 2, 0], [2, 0, 0], [2, 1, 0], [2, 2, 0]];
-            let mut ex = HashSet::with_capacity(5);
+            let mut ex = FastSet::with_capacity_and_hasher(5, Default::default());
             for &coord in &coords {
-                ex.insert(coord);
+                ex.insert(Point(coord));
             }
             ex
         };
-        let actual = parse_input(INPUT);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn test_offsets() {
-        let mut expected = vec![
-            [-1, -1, -1],
-            [-1, -1, 1],
-            [-1, -1, 0],
-            [-1, 1, -1],
-            [-1, 1, 1],
-            [-1, 1, 0],
-            [-1, 0, -1],
-            [-1, 0, 1],
-            [-1, 0, 0],
-            [0, -1, -1],
-            [0, -1, 1],
-            [0, -1, 0],
-            [0, 1, -1],
-            [0, 1, 1],
-            [0, 1, 0],
-            [0, 0, -1],
-            [0, 0, 1],
-            [1, -1, -1],
-            [1, -1, 1],
-            [1, -1, 0],
-            [1, 1, -1],
-            [1, 1, 1],
-            [1, 1, 0],
-            [1, 0, -1],
-            [1, 0, 1],
-            [1, 0, 0],
-        ];
-        expected.sort();
-        let mut actual = offsets::<3>(0, [0; 3]);
-        let _ = actual.pop();
-        actual.sort();
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1() {
-        let expected = 112;
-        let actual = get_active::<3>(INPUT);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let expected = 848;
-        let actual = get_active::<4>(INPUT);
+        let actual = parse_input(&example_input(17, "example")).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day17.txt");
+        let input = require_input!(17);
 
         let expected: Solution = (368, 2_696).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
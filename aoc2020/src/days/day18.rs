@@ -1,5 +1,8 @@
-use std::fmt;
+use anyhow::{bail, ensure, Context};
 
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,70 +15,195 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = &'a str;
+    type Part1 = u64;
+    type Part2 = u64;
+
     fn day(&self) -> u8 {
         18
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let part1 = part1(input);
-        let part2 = part2(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789+*() "),
+            ..Shape::default()
+        }
+    }
+
+    /// Operator precedence differs between the parts and is baked in while lexing, so each part
+    /// lexes the homework itself.
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<&'a str> {
+        Ok(input)
+    }
+
+    fn part1(&self, input: &&str) -> anyhow::Result<u64> {
+        part1(input)
+    }
+
+    fn part2(&self, input: &&str) -> anyhow::Result<u64> {
+        part2(input)
+    }
 
-        (part1, part2).into()
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-fn part1(input: &str) -> u64 {
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        380
+    }
+
+    /// `size` expressions of at most 12 single digit numbers, so neither part overflows a `u64`.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        (0..size)
+            .map(|_| {
+                let operands = rng.range(2..13);
+                expression(rng, operands, 0) + "\n"
+            })
+            .collect()
+    }
+}
+
+impl Reference for Solver {
+    /// Evaluate each line with a recursive descent parser, which has one level per precedence.
+    fn solve_reference(&self, input: &str) -> Solution {
+        // Operators from the loosest binding to the tightest, one slice per precedence level.
+        type Levels<'l> = &'l [&'l [char]];
+
+        // Parse the operators in `levels` from `at`. Brackets start over from `all`.
+        fn binary(tokens: &[char], at: &mut usize, levels: Levels<'_>, all: Levels<'_>) -> u64 {
+            let (operators, tighter) = match levels.split_first() {
+                Some(split) => split,
+                None => return operand(tokens, at, all),
+            };
+            let mut value = binary(tokens, at, tighter, all);
+            while let Some(&operator) = tokens.get(*at).filter(|c| operators.contains(c)) {
+                *at += 1;
+                let right = binary(tokens, at, tighter, all);
+                value = if operator == '+' {
+                    value + right
+                } else {
+                    value * right
+                };
+            }
+            value
+        }
+        fn operand(tokens: &[char], at: &mut usize, all: Levels<'_>) -> u64 {
+            let token = tokens[*at];
+            *at += 1;
+            if token == '(' {
+                let value = binary(tokens, at, all, all);
+                assert_eq!(tokens[*at], ')', "Unclosed bracket");
+                *at += 1;
+                value
+            } else {
+                u64::from(token.to_digit(10).unwrap())
+            }
+        }
+
+        let evaluate = |levels: Levels<'_>| -> u64 {
+            input
+                .lines()
+                .map(|line| {
+                    let tokens: Vec<char> = line.chars().filter(|c| !c.is_whitespace()).collect();
+                    binary(&tokens, &mut 0, levels, levels)
+                })
+                .sum()
+        };
+
+        (evaluate(&[&['+', '*']]), evaluate(&[&['*'], &['+']])).into()
+    }
+}
+
+/// A random expression with exactly `operands` numbers, which must be at least 2.
+fn expression(rng: &mut Rng, operands: usize, depth: usize) -> String {
+    let terms = if depth >= 3 {
+        operands
+    } else {
+        rng.range(2..operands.min(4) + 1)
+    };
+    let mut sizes = vec![1; terms];
+    for _ in terms..operands {
+        sizes[rng.range(0..terms)] += 1;
+    }
+
+    let mut expression = String::new();
+    for (i, &size) in sizes.iter().enumerate() {
+        if i > 0 {
+            expression.push_str(if rng.chance(0.5) { " + " } else { " * " });
+        }
+        if size == 1 {
+            expression.push_str(&rng.range(1..10).to_string());
+        } else {
+            expression.push('(');
+            expression.push_str(&self::expression(rng, size, depth + 1));
+            expression.push(')');
+        }
+    }
+    expression
+}
+
+fn part1(input: &str) -> anyhow::Result<u64> {
     let tokens = input
         .lines()
         .map(|line| lexer(line, |_, _| true))
-        .collect::<Vec<Vec<Token>>>();
+        .collect::<anyhow::Result<Vec<Vec<Token>>>>()?;
     let expressions = tokens
         .iter()
-        .map(|expr| shunting_yard(&expr))
-        .collect::<Vec<Vec<Token>>>();
+        .map(|expr| shunting_yard(expr))
+        .collect::<anyhow::Result<Vec<Vec<Token>>>>()?;
 
-    expressions
-        .iter()
-        .map(|tokens| evaluate(tokens))
-        .sum::<u64>()
+    expressions.iter().try_fold(0_u64, |sum, tokens| {
+        sum.checked_add(evaluate(tokens)?)
+            .context("The sum overflows")
+    })
 }
 
-fn part2(input: &str) -> u64 {
+fn part2(input: &str) -> anyhow::Result<u64> {
     let tokens = input
         .lines()
         .map(|line| lexer(line, |a, b| matches!((a.token, b.token), ('+', '*'))))
-        .collect::<Vec<Vec<Token>>>();
+        .collect::<anyhow::Result<Vec<Vec<Token>>>>()?;
     let expressions = tokens
         .iter()
-        .map(|expr| shunting_yard(&expr))
-        .collect::<Vec<Vec<Token>>>();
+        .map(|expr| shunting_yard(expr))
+        .collect::<anyhow::Result<Vec<Vec<Token>>>>()?;
 
-    expressions
-        .iter()
-        .map(|tokens| evaluate(tokens))
-        .sum::<u64>()
+    expressions.iter().try_fold(0_u64, |sum, tokens| {
+        sum.checked_add(evaluate(tokens)?)
+            .context("The sum overflows")
+    })
 }
 
-fn lexer(input: &str, precedince_fn: fn(Operator, Operator) -> bool) -> Vec<Token> {
+fn lexer(input: &str, precedince_fn: fn(Operator, Operator) -> bool) -> anyhow::Result<Vec<Token>> {
     input
         .chars()
         .filter(|c| !c.is_whitespace())
-        .map(|t| match t {
-            '0'..='9' => Token::Digit(u64::from(t.to_digit(10).unwrap())),
-            '+' => Operator::new_token(t, |x, y| x + y, precedince_fn),
-            '*' => Operator::new_token(t, |x, y| x * y, precedince_fn),
-            '(' => Token::LeftParen,
-            ')' => Token::RightParen,
-            _ => panic!("invalid token: {}", t),
+        .map(|t| {
+            Ok(match t {
+                '0'..='9' => Token::Digit(u64::from(t.to_digit(10).unwrap())),
+                '+' => Operator::new_token(t, u64::checked_add, precedince_fn),
+                '*' => Operator::new_token(t, u64::checked_mul, precedince_fn),
+                '(' => Token::LeftParen,
+                ')' => Token::RightParen,
+                _ => bail!("invalid token: {}", t),
+            })
         })
         .collect()
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy)]
 struct Operator {
     token: char,
-    operation: fn(u64, u64) -> u64,
+    /// Returns `None` on overflow.
+    operation: fn(u64, u64) -> Option<u64>,
     precedince_fn: fn(Operator, Operator) -> bool,
 }
 
@@ -90,7 +218,7 @@ enum Token {
 impl Operator {
     fn new_token(
         token: char,
-        operation: fn(u64, u64) -> u64,
+        operation: fn(u64, u64) -> Option<u64>,
         precedince_fn: fn(Operator, Operator) -> bool,
     ) -> Token {
         Token::Operator(Self {
@@ -100,11 +228,20 @@ impl Operator {
         })
     }
 
-    fn eval(&self, x: u64, y: u64) -> u64 {
+    fn eval(&self, x: u64, y: u64) -> Option<u64> {
         (self.operation)(x, y)
     }
 }
 
+// Function pointers can't be compared meaningfully, so operators are identified by their token.
+impl PartialEq for Operator {
+    fn eq(&self, other: &Self) -> bool {
+        self.token == other.token
+    }
+}
+
+impl Eq for Operator {}
+
 trait Stack {
     fn top(&self) -> Option<Token>;
 }
@@ -114,7 +251,7 @@ impl Stack for Vec<Token> {
         if self.is_empty() {
             return None;
         }
-        self.get(self.len() - 1).copied()
+        self.last().copied()
     }
 }
 
@@ -128,7 +265,7 @@ fn take_until(operators: &mut Vec<Token>, output: &mut Vec<Token>, stop: Token)
     false
 }
 
-fn shunting_yard(tokens: &[Token]) -> Vec<Token> {
+fn shunting_yard(tokens: &[Token]) -> anyhow::Result<Vec<Token>> {
     let mut output: Vec<Token> = Default::default();
     let mut operators: Vec<Token> = Default::default();
 
@@ -153,22 +290,24 @@ fn shunting_yard(tokens: &[Token]) -> Vec<Token> {
                 operators.push(token);
             }
             Token::RightParen => {
-                if !take_until(&mut operators, &mut output, Token::LeftParen) {
-                    panic!("Mismatched ')'");
-                }
+                ensure!(
+                    take_until(&mut operators, &mut output, Token::LeftParen),
+                    "Mismatched ')'"
+                );
             }
         }
     }
 
-    if take_until(&mut operators, &mut output, Token::LeftParen) {
-        panic!("Mismatched '('");
-    }
+    ensure!(
+        !take_until(&mut operators, &mut output, Token::LeftParen),
+        "Mismatched '('"
+    );
 
     assert!(operators.is_empty());
-    output
+    Ok(output)
 }
 
-fn evaluate(postfix_tokens: &[Token]) -> u64 {
+fn evaluate(postfix_tokens: &[Token]) -> anyhow::Result<u64> {
     let mut stack = Vec::new();
 
     for &token in postfix_tokens {
@@ -177,18 +316,22 @@ fn evaluate(postfix_tokens: &[Token]) -> u64 {
             Token::Operator(op) => {
                 if let Some(y) = stack.pop() {
                     if let Some(x) = stack.pop() {
-                        stack.push(op.eval(x, y));
+                        stack.push(op.eval(x, y).context("The expression overflows")?);
                         continue;
                     }
                 }
-                panic!("Missing operand for operator '{}'", op.token);
+                bail!("Missing operand for operator '{}'", op.token);
             }
             _ => unreachable!("Unexpected token {:?} during evaluation", token),
         }
     }
 
-    assert!(stack.len() == 1);
-    stack.pop().unwrap()
+    ensure!(
+        stack.len() == 1,
+        "Expected one value, found {}",
+        stack.len()
+    );
+    Ok(stack[0])
 }
 
 #[cfg(test)]
@@ -198,106 +341,13 @@ mod test {
     use crate::solution::Solution;
     use crate::Solver;
 
-    const EXPRESSION: [&str; 6] = [
-        "1 + 2 * 3 + 4 * 5 + 6",
-        "1 + (2 * 3) + (4 * (5 + 6))",
-        "2 * 3 + (4 * 5)",
-        "5 + (8 * 3 + 9 + 3 * 4 * 3)",
-        "5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))",
-        "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2",
-    ];
-
-    #[test]
-    fn example_part1_1() {
-        let expected = 71;
-        let actual = part1(EXPRESSION[0]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_2() {
-        let expected = 51;
-        let actual = part1(EXPRESSION[1]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_3() {
-        let expected = 26;
-        let actual = part1(EXPRESSION[2]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_4() {
-        let expected = 437;
-        let actual = part1(EXPRESSION[3]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_5() {
-        let expected = 12_240;
-        let actual = part1(EXPRESSION[4]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1_6() {
-        let expected = 13_632;
-        let actual = part1(EXPRESSION[5]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_1() {
-        let expected = 231;
-        let actual = part2(EXPRESSION[0]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_2() {
-        let expected = 51;
-        let actual = part2(EXPRESSION[1]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_3() {
-        let expected = 46;
-        let actual = part2(EXPRESSION[2]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_4() {
-        let expected = 1445;
-        let actual = part2(EXPRESSION[3]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_5() {
-        let expected = 669_060;
-        let actual = part2(EXPRESSION[4]);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2_6() {
-        let expected = 23_340;
-        let actual = part2(EXPRESSION[5]);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day18.txt");
+        let input = require_input!(18);
 
         let expected: Solution = (510_009_915_468_u64, 321_176_691_637_769_u64).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
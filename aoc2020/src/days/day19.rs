@@ -1,6 +1,11 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::convert::TryFrom;
 
+use anyhow::{ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -13,71 +18,621 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = (RuleSet<'a>, Vec<&'a str>);
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn day(&self) -> u8 {
+        19
+    }
+
+    fn shape(&self) -> Shape {
+        shape()
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<(RuleSet<'a>, Vec<&'a str>)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        part1(rule_set, messages)
+    }
+
+    fn part2(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        part2(&mut rule_set.clone(), messages)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+/// Matches messages with an Earley parser, which fills in a table of partly matched rules for each
+/// position instead of backtracking, and copes with any grammar, left-recursive ones included.
+pub(crate) struct Earley(());
+
+impl Earley {
+    pub fn new() -> Self {
+        let solver = Self(());
+        assert_solver_day!(solver);
+        solver
+    }
+}
+
+impl crate::Day for Earley {
+    type Parsed<'a> = (RuleSet<'a>, Vec<&'a str>);
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn day(&self) -> u8 {
+        19
+    }
+
+    fn name(&self) -> &str {
+        "earley"
+    }
+
+    fn shape(&self) -> Shape {
+        shape()
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<(RuleSet<'a>, Vec<&'a str>)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        Grammar::new(rule_set).count_valid(messages)
+    }
+
+    fn part2(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        let mut rule_set = rule_set.clone();
+        add_loops(&mut rule_set)?;
+        Grammar::new(&rule_set).count_valid(messages)
+    }
+}
+
+/// Matches messages by working out every position each rule can end at from each start,
+/// remembering them so that no rule is tried twice at the same place.
+pub(crate) struct Memoized(());
+
+impl Memoized {
+    pub fn new() -> Self {
+        let solver = Self(());
+        assert_solver_day!(solver);
+        solver
+    }
+}
+
+impl crate::Day for Memoized {
+    type Parsed<'a> = (RuleSet<'a>, Vec<&'a str>);
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         19
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let (mut rule_set, messages) = parse_input(input);
+    fn name(&self) -> &str {
+        "memoized"
+    }
+
+    fn shape(&self) -> Shape {
+        shape()
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<(RuleSet<'a>, Vec<&'a str>)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        count_valid(messages, |message| rule_set.validate_memoized(0, message))
+    }
+
+    fn part2(&self, (rule_set, messages): &(RuleSet<'_>, Vec<&str>)) -> anyhow::Result<usize> {
+        let mut rule_set = rule_set.clone();
+        add_loops(&mut rule_set)?;
+        count_valid(messages, |message| rule_set.validate_memoized(0, message))
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        450
+    }
+
+    /// A grammar in the shape of the real puzzle (`0: 8 11`, `8: 42`, `11: 42 31`), where rules
+    /// 42 and 31 split every string of one length between them, followed by `size` messages,
+    /// some matching either part and some not.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const LEN: usize = 5;
+
+        // Hand out rule numbers, keeping the ones with a fixed meaning.
+        let mut ids: Vec<usize> = (1..40 + 4 * LEN)
+            .filter(|id| ![8, 11, 31, 42].contains(id))
+            .collect();
+        rng.shuffle(&mut ids);
+        let mut ids = ids.into_iter();
+        let mut rules = vec![
+            (0, String::from("8 11")),
+            (8, String::from("42")),
+            (11, String::from("42 31")),
+        ];
+
+        // `pairs[n]` holds two rules splitting all strings of length `n + 1` between them,
+        // along with those strings.
+        let (a, b) = (ids.next().unwrap(), ids.next().unwrap());
+        rules.push((a, String::from("\"a\"")));
+        rules.push((b, String::from("\"b\"")));
+        let mut pair = [(a, vec![String::from("a")]), (b, vec![String::from("b")])];
+        for n in 1..LEN {
+            let ids = if n + 1 == LEN {
+                [42, 31]
+            } else {
+                [ids.next().unwrap(), ids.next().unwrap()]
+            };
+            // Each new rule is a letter followed by one of the old rules, using the other old
+            // rule after the other letter, so between them they cover every string once.
+            let swap = [rng.chance(0.5), rng.chance(0.5)];
+            let mut next = [(ids[0], Vec::new()), (ids[1], Vec::new())];
+            for (side, &id) in ids.iter().enumerate() {
+                let mut branches = Vec::new();
+                for (letter, (letter_id, letter_str)) in [(a, "a"), (b, "b")].iter().enumerate() {
+                    let (rule, strings) = &pair[side ^ swap[letter] as usize];
+                    branches.push(format!("{} {}", letter_id, rule));
+                    next[side]
+                        .1
+                        .extend(strings.iter().map(|s| format!("{}{}", letter_str, s)));
+                }
+                rules.push((id, branches.join(" | ")));
+            }
+            pair = next;
+        }
+        let (forty_two, thirty_one) = (&pair[0].1, &pair[1].1);
+
+        rng.shuffle(&mut rules);
+        let mut input: String = rules
+            .iter()
+            .map(|(id, rule)| format!("{}: {}\n", id, rule))
+            .collect();
+        input.push('\n');
+
+        for _ in 0..size {
+            let (fronts, backs) = match rng.range(0..4) {
+                // Matches both parts.
+                0 => (2, 1),
+                // Only matches part 2.
+                1 => {
+                    let backs = rng.range(1..4);
+                    (backs + rng.range(1..4), backs)
+                }
+                // Matches neither.
+                2 => {
+                    let fronts = rng.range(1..4);
+                    (fronts, fronts + rng.range(0..3))
+                }
+                _ => (0, 0),
+            };
+            let message = if fronts == 0 {
+                (0..rng.range(1..4 * LEN))
+                    .map(|_| if rng.chance(0.5) { 'a' } else { 'b' })
+                    .collect()
+            } else {
+                let mut message = String::new();
+                for _ in 0..fronts {
+                    message.push_str(rng.choose(forty_two).as_str());
+                }
+                for _ in 0..backs {
+                    message.push_str(rng.choose(thirty_one).as_str());
+                }
+                message
+            };
+            input.push_str(&message);
+            input.push('\n');
+        }
+
+        input
+    }
+}
+
+impl Reference for Solver {
+    /// Check whether each rule matches each slice of a message, trying every way of splitting a
+    /// slice between the parts of a sequence.
+    fn solve_reference(&self, input: &str) -> Solution {
+        enum Pattern {
+            Letter(u8),
+            Choice(Vec<Vec<usize>>),
+        }
+        struct Grammar<'g> {
+            rules: &'g FastMap<usize, Pattern>,
+            message: &'g [u8],
+            known: FastMap<(usize, usize, usize), bool>,
+        }
+        impl Grammar<'_> {
+            fn rule(&mut self, rule: usize, start: usize, end: usize) -> bool {
+                if let Some(&matched) = self.known.get(&(rule, start, end)) {
+                    return matched;
+                }
+                let matched = match &self.rules[&rule] {
+                    Pattern::Letter(letter) => end == start + 1 && self.message[start] == *letter,
+                    Pattern::Choice(options) => options
+                        .iter()
+                        .any(|sequence| self.sequence(sequence, start, end)),
+                };
+                self.known.insert((rule, start, end), matched);
+                matched
+            }
+            fn sequence(&mut self, sequence: &[usize], start: usize, end: usize) -> bool {
+                match sequence {
+                    [] => start == end,
+                    [rule] => self.rule(*rule, start, end),
+                    [rule, rest @ ..] => (start + 1..end).any(|middle| {
+                        self.rule(*rule, start, middle) && self.sequence(rest, middle, end)
+                    }),
+                }
+            }
+        }
+
+        let (rules, messages) = input.split_once("\n\n").unwrap();
+        let pattern = |text: &str| match text.strip_prefix('"') {
+            Some(letter) => Pattern::Letter(letter.as_bytes()[0]),
+            None => Pattern::Choice(
+                text.split(" | ")
+                    .map(|sequence| sequence.split(' ').map(|n| n.parse().unwrap()).collect())
+                    .collect(),
+            ),
+        };
+        let mut rules: FastMap<usize, Pattern> = rules
+            .lines()
+            .map(|line| {
+                let (n, text) = line.split_once(": ").unwrap();
+                (n.parse().unwrap(), pattern(text))
+            })
+            .collect();
+        let count = |rules: &FastMap<usize, Pattern>| {
+            messages
+                .lines()
+                .filter(|message| {
+                    let mut grammar = Grammar {
+                        rules,
+                        message: message.as_bytes(),
+                        known: FastMap::default(),
+                    };
+                    grammar.rule(0, 0, message.len())
+                })
+                .count()
+        };
 
-        let part1 = part1(&rule_set, &messages);
-        let part2 = part2(&mut rule_set, &messages);
+        let part1 = count(&rules);
+        rules.insert(8, pattern("42 | 42 8"));
+        rules.insert(11, pattern("42 31 | 42 11 31"));
+        let part2 = count(&rules);
 
         (part1, part2).into()
     }
 }
 
-fn part1(rule_set: &RuleSet, messages: &[&str]) -> usize {
-    messages
-        .iter()
-        .filter(|message| rule_set.validate(0, message))
-        .count()
+fn part1(rule_set: &RuleSet, messages: &[&str]) -> anyhow::Result<usize> {
+    count_valid(messages, |message| rule_set.validate(0, message))
 }
 
-fn part2(rule_set: &mut RuleSet, messages: &[&str]) -> usize {
-    rule_set.rules.insert(8, Rule::from("42 | 42 8"));
-    rule_set.rules.insert(11, Rule::from("42 31 | 42 11 31"));
+fn part2(rule_set: &mut RuleSet, messages: &[&str]) -> anyhow::Result<usize> {
+    add_loops(rule_set)?;
+    count_valid(messages, |message| rule_set.validate(0, message))
+}
 
-    messages
-        .iter()
-        .filter(|message| rule_set.validate(0, message))
-        .count()
+/// Replace rules 8 and 11 with part 2's looping versions.
+fn add_loops(rule_set: &mut RuleSet) -> anyhow::Result<()> {
+    rule_set.rules.insert(8, Rule::try_from("42 | 42 8")?);
+    rule_set
+        .rules
+        .insert(11, Rule::try_from("42 31 | 42 11 31")?);
+    Ok(())
 }
 
-fn parse_input(input: &str) -> (RuleSet, Vec<&str>) {
+/// How many of `messages` are `valid`.
+fn count_valid(
+    messages: &[&str],
+    mut valid: impl FnMut(&str) -> anyhow::Result<bool>,
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for message in messages {
+        if valid(message)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+fn shape() -> Shape {
+    Shape {
+        blocks: Some(2),
+        alphabet: Some("0123456789ab:|\" "),
+        ..Shape::default()
+    }
+}
+
+fn parse_input(input: &str) -> anyhow::Result<(RuleSet<'_>, Vec<&str>)> {
     let mut iter = input.split("\n\n");
     let rule_set = iter
         .next()
-        .unwrap()
+        .context("Missing rules")?
         .lines()
         .map(|line| {
-            let mut iter = line.split(": ");
-            let idx = iter.next().unwrap().parse().unwrap();
-            let rule = Rule::from(iter.next().unwrap());
-            (idx, rule)
+            let (idx, rule) = line
+                .split_once(": ")
+                .with_context(|| format!("Invalid rule: {}", line))?;
+            let idx = idx
+                .parse()
+                .with_context(|| format!("Invalid rule number: {}", idx))?;
+            Ok((idx, Rule::try_from(rule)?))
         })
-        .collect::<RuleSet>();
-    let messages = iter.next().unwrap().lines().collect::<Vec<&str>>();
-    (rule_set, messages)
+        .collect::<anyhow::Result<RuleSet>>()?;
+    let messages = iter
+        .next()
+        .context("Missing messages")?
+        .lines()
+        .collect::<Vec<&str>>();
+    Ok((rule_set, messages))
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct RuleSet<'r> {
-    rules: HashMap<usize, Rule<'r>>,
+/// How deep rules may nest while matching a message. Real inputs stay in the low hundreds, and
+/// left-recursive rules, which would never stop, run into this instead.
+const MAX_DEPTH: usize = 1000;
+
+/// How many rules the backtracker may try on one message. Real inputs need about 12,000, and
+/// rules with exponentially many ways to match run into this instead.
+const MAX_STEPS: usize = 1_000_000;
+
+/// Where each rule can end when it starts at a given position, by `(rule, start)`.
+type Memo = FastMap<(usize, usize), Vec<usize>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct RuleSet<'r> {
+    rules: FastMap<usize, Rule<'r>>,
 }
 
 impl<'a> RuleSet<'a> {
-    fn validate(&self, idx: usize, message: &str) -> bool {
-        let to_validate = self.rules.get(&idx).unwrap();
-        let remaining = to_validate.validate(message, self);
-        remaining.into_iter().any(str::is_empty)
+    /// Whether rule `idx` matches the whole of `message`, trying every way through the rules.
+    fn validate(&self, idx: usize, message: &str) -> anyhow::Result<bool> {
+        let remaining = self.rule(idx)?.validate(message, self, 0, &mut 0)?;
+        Ok(remaining.into_iter().any(str::is_empty))
+    }
+
+    /// Like [`RuleSet::validate`], but remembering where each rule can end from each position.
+    fn validate_memoized(&self, idx: usize, message: &str) -> anyhow::Result<bool> {
+        let ends = self.ends(idx, message, 0, 0, &mut Memo::default())?;
+        Ok(ends.contains(&message.len()))
+    }
+
+    /// Every position in `message` that a match of rule `idx` starting at `start` can end at,
+    /// sorted and without duplicates. Rules never match at the end of the message.
+    fn ends(
+        &self,
+        idx: usize,
+        message: &str,
+        start: usize,
+        depth: usize,
+        memo: &mut Memo,
+    ) -> anyhow::Result<Vec<usize>> {
+        if start == message.len() {
+            return Ok(Vec::new());
+        }
+        if let Some(ends) = memo.get(&(idx, start)) {
+            return Ok(ends.clone());
+        }
+        ensure!(depth < MAX_DEPTH, "Rule {} nests too deeply", idx);
+
+        let mut ends = match self.rule(idx)? {
+            Rule::Terminator(s) => {
+                if message[start..].starts_with(s) {
+                    vec![start + s.len()]
+                } else {
+                    Vec::new()
+                }
+            }
+            Rule::Reference(branches) => {
+                let mut ends = Vec::new();
+                for sequence in branches {
+                    let mut positions = vec![start];
+                    for &next in sequence {
+                        let mut after = Vec::new();
+                        for &position in &positions {
+                            after.extend(self.ends(next, message, position, depth + 1, memo)?);
+                        }
+                        after.sort_unstable();
+                        after.dedup();
+                        positions = after;
+                    }
+                    ends.extend(positions);
+                }
+                ends
+            }
+        };
+
+        ends.sort_unstable();
+        ends.dedup();
+        memo.insert((idx, start), ends.clone());
+        Ok(ends)
+    }
+
+    fn rule(&self, idx: usize) -> anyhow::Result<&Rule<'a>> {
+        self.rules
+            .get(&idx)
+            .with_context(|| format!("Missing rule {}", idx))
+    }
+}
+
+/// Stands for the rule that matches rule 0 and nothing more, so that the whole message is checked.
+const START: usize = usize::MAX;
+
+/// A rule set laid out for [`Grammar::matches`]: every branch of every rule as a numbered
+/// production.
+struct Grammar<'r> {
+    /// Each production as the rule it belongs to and the sequence it expands to.
+    productions: Vec<(usize, Vec<usize>)>,
+    /// The productions of each rule, by rule number.
+    by_rule: FastMap<usize, Vec<usize>>,
+    terminals: FastMap<usize, &'r str>,
+    /// Rules that can match nothing at all.
+    nullable: FastSet<usize>,
+}
+
+/// A production partly matched: how much of it has been, and where that match started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Item {
+    production: usize,
+    dot: usize,
+    origin: usize,
+}
+
+impl<'r> Grammar<'r> {
+    fn new(rule_set: &RuleSet<'r>) -> Self {
+        let mut grammar = Self {
+            productions: vec![(START, vec![0])],
+            by_rule: FastMap::default(),
+            terminals: FastMap::default(),
+            nullable: FastSet::default(),
+        };
+        grammar.by_rule.insert(START, vec![0]);
+        for (&idx, rule) in &rule_set.rules {
+            match rule {
+                Rule::Terminator(s) => {
+                    grammar.terminals.insert(idx, s);
+                }
+                Rule::Reference(branches) => {
+                    for sequence in branches {
+                        let production = grammar.productions.len();
+                        grammar.productions.push((idx, sequence.clone()));
+                        grammar.by_rule.entry(idx).or_default().push(production);
+                    }
+                }
+            }
+        }
+
+        // A rule is nullable if one of its productions is made only of nullable rules.
+        loop {
+            let nullable = &grammar.nullable;
+            let found: Vec<usize> = grammar
+                .productions
+                .iter()
+                .filter(|(idx, sequence)| {
+                    !nullable.contains(idx) && sequence.iter().all(|next| nullable.contains(next))
+                })
+                .map(|&(idx, _)| idx)
+                .collect();
+            if found.is_empty() {
+                break;
+            }
+            grammar.nullable.extend(found);
+        }
+
+        grammar
+    }
+
+    fn count_valid(&self, messages: &[&str]) -> anyhow::Result<usize> {
+        let mut count = 0;
+        for message in messages {
+            if self.matches(message)? {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Whether rule 0 matches the whole of `message`. The chart holds, for each position, every
+    /// item that has matched the message up to there.
+    fn matches(&self, message: &str) -> anyhow::Result<bool> {
+        let mut chart: Vec<Vec<Item>> = vec![Vec::new(); message.len() + 1];
+        let mut seen: Vec<FastSet<Item>> = vec![FastSet::default(); message.len() + 1];
+        let mut add = |chart: &mut Vec<Vec<Item>>, position: usize, item: Item| {
+            if seen[position].insert(item) {
+                chart[position].push(item);
+            }
+        };
+        add(
+            &mut chart,
+            0,
+            Item {
+                production: 0,
+                dot: 0,
+                origin: 0,
+            },
+        );
+
+        for position in 0..=message.len() {
+            let mut i = 0;
+            while i < chart[position].len() {
+                let item = chart[position][i];
+                i += 1;
+                let (idx, sequence) = &self.productions[item.production];
+                let advanced = Item {
+                    dot: item.dot + 1,
+                    ..item
+                };
+
+                // Completed: move on every item that was waiting for this rule.
+                let next = match sequence.get(item.dot) {
+                    Some(&next) => next,
+                    None => {
+                        let waiting: Vec<Item> = chart[item.origin]
+                            .iter()
+                            .filter(|waiting| {
+                                self.productions[waiting.production].1.get(waiting.dot) == Some(idx)
+                            })
+                            .map(|&waiting| Item {
+                                dot: waiting.dot + 1,
+                                ..waiting
+                            })
+                            .collect();
+                        for waiting in waiting {
+                            add(&mut chart, position, waiting);
+                        }
+                        continue;
+                    }
+                };
+
+                if let Some(terminal) = self.terminals.get(&next) {
+                    if message[position..].starts_with(terminal) {
+                        add(&mut chart, position + terminal.len(), advanced);
+                    }
+                    continue;
+                }
+                let productions = self
+                    .by_rule
+                    .get(&next)
+                    .with_context(|| format!("Missing rule {}", next))?;
+                for &production in productions {
+                    let predicted = Item {
+                        production,
+                        dot: 0,
+                        origin: position,
+                    };
+                    add(&mut chart, position, predicted);
+                }
+                // A nullable rule completes where it starts, possibly after the items waiting
+                // for it have been looked at, so move past it straight away.
+                if self.nullable.contains(&next) {
+                    add(&mut chart, position, advanced);
+                }
+            }
+        }
+
+        Ok(chart[message.len()].contains(&Item {
+            production: 0,
+            dot: 1,
+            origin: 0,
+        }))
     }
 }
 
 impl<'r> std::iter::FromIterator<(usize, Rule<'r>)> for RuleSet<'r> {
     fn from_iter<T: IntoIterator<Item = (usize, Rule<'r>)>>(iter: T) -> Self {
-        let mut rules = HashMap::new();
+        let mut rules = FastMap::default();
         for (idx, rule) in iter {
             rules.insert(idx, rule);
         }
@@ -85,60 +640,74 @@ impl<'r> std::iter::FromIterator<(usize, Rule<'r>)> for RuleSet<'r> {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 enum Rule<'r> {
     Terminator(&'r str),
-    Reference(HashSet<Vec<usize>>),
+    Reference(FastSet<Vec<usize>>),
 }
 
 impl<'a> Rule<'a> {
-    fn validate<'r, 's: 'r>(
-        &'r self,
+    /// What's left of `message` after each way this rule can match its start. Rules never match
+    /// an empty message. `steps` counts the rules tried so far.
+    fn validate<'s>(
+        &self,
         message: &'s str,
-        rules: &'r RuleSet,
-    ) -> Box<dyn Iterator<Item = &str> + 'r> {
+        rules: &RuleSet,
+        depth: usize,
+        steps: &mut usize,
+    ) -> anyhow::Result<Vec<&'s str>> {
         if message.is_empty() {
-            return Box::new(None.into_iter());
+            return Ok(Vec::new());
         }
+        *steps += 1;
+        ensure!(*steps <= MAX_STEPS, "Too many ways to match a message");
 
         match self {
-            Self::Terminator(s) => {
-                if message.starts_with(s) {
-                    let (_, unmatched) = message.split_at(s.len());
-                    Box::new(Some(unmatched).into_iter())
-                } else {
-                    Box::new(None.into_iter())
+            Self::Terminator(s) => Ok(message.strip_prefix(s).into_iter().collect()),
+            Self::Reference(branches) => {
+                let mut remaining = Vec::new();
+                for sequence in branches {
+                    let mut remaining_message = vec![message];
+                    for &idx in sequence {
+                        ensure!(depth < MAX_DEPTH, "Rule {} nests too deeply", idx);
+                        let rule = rules.rule(idx)?;
+                        let mut next = Vec::new();
+                        for msg in remaining_message {
+                            next.extend(rule.validate(msg, rules, depth + 1, steps)?);
+                        }
+                        remaining_message = next;
+                    }
+                    remaining.extend(remaining_message);
                 }
+                Ok(remaining)
             }
-            Self::Reference(branches) => Box::new(branches.iter().flat_map(move |sequence| {
-                let mut remaining_message = vec![message];
-                for rule in sequence.iter().map(|idx| rules.rules.get(idx).unwrap()) {
-                    remaining_message = remaining_message
-                        .iter()
-                        .flat_map(|msg| rule.validate(msg, rules))
-                        .collect()
-                }
-                remaining_message.into_iter()
-            })),
         }
     }
 }
 
-impl<'r, 's: 'r> From<&'s str> for Rule<'r> {
-    fn from(rule: &'s str) -> Self {
-        if rule.starts_with('"') {
-            let terminal = rule.strip_prefix('"').unwrap().strip_suffix('"').unwrap();
-            Self::Terminator(terminal)
+impl<'r, 's: 'r> TryFrom<&'s str> for Rule<'r> {
+    type Error = anyhow::Error;
+
+    fn try_from(rule: &'s str) -> anyhow::Result<Self> {
+        if let Some(terminal) = rule.strip_prefix('"') {
+            let terminal = terminal
+                .strip_suffix('"')
+                .filter(|terminal| !terminal.is_empty())
+                .with_context(|| format!("Invalid terminal: {}", rule))?;
+            Ok(Self::Terminator(terminal))
         } else {
             let rule = rule
                 .split('|')
                 .map(|s| {
                     s.split_whitespace()
-                        .filter_map(|s| s.parse().ok())
+                        .map(|s| {
+                            s.parse()
+                                .with_context(|| format!("Invalid rule number: {}", s))
+                        })
                         .collect()
                 })
-                .collect();
-            Self::Reference(rule)
+                .collect::<anyhow::Result<_>>()?;
+            Ok(Self::Reference(rule))
         }
     }
 }
@@ -147,25 +716,10 @@ impl<'r, 's: 'r> From<&'s str> for Rule<'r> {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    #[allow(unused)]
-    const INPUT: &str = r#"0: 4 1 5
-1: 2 3 | 3 2
-2: 4 4 | 5 5
-3: 4 5 | 5 4
-4: "a"
-5: "b"
-
-ababbb
-bababa
-abbbab
-aaabbb
-aaaabbb
-
-"#;
-
     #[test]
     fn test_parse_rules() {
         const INPUT: &str = r#"0: 1 2
@@ -175,10 +729,10 @@ aaaabbb
 
 "#;
 
-        let (actual, _) = parse_input(INPUT);
+        let (actual, _) = parse_input(INPUT).unwrap();
 
         let expected = {
-            let mut expected = HashMap::with_capacity(6);
+            let mut expected = FastMap::with_capacity_and_hasher(6, Default::default());
             expected.insert(0, Rule::Reference(vec![vec![1, 2]].into_iter().collect()));
             expected.insert(1, Rule::Terminator("a"));
             expected.insert(
@@ -192,77 +746,32 @@ aaaabbb
     }
 
     #[test]
-    fn example_part1() {
-        let (rule_set, messages) = parse_input(INPUT);
-        let expected = 2;
-        let actual = part1(&rule_set, &messages);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let (mut rule_set, messages) = parse_input(EXAMPLE_INPUT_P2);
-        let expected = 12;
-        let actual = part2(&mut rule_set, &messages);
-        assert_eq!(actual, expected)
+    fn example_earley() {
+        let input = example_input(19, "part1");
+        let (mut rule_set, _) = parse_input(&input).unwrap();
+
+        // Left recursion and rules that match nothing, which backtracking can't cope with.
+        rule_set
+            .rules
+            .insert(0, Rule::try_from("0 4 | 6 5").unwrap());
+        rule_set.rules.insert(6, Rule::try_from("").unwrap());
+        let grammar = Grammar::new(&rule_set);
+        assert!(grammar.matches("b").unwrap());
+        assert!(grammar.matches("baaa").unwrap());
+        assert!(!grammar.matches("ab").unwrap());
+        assert!(!grammar.matches("").unwrap());
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day19.txt");
+        let input = require_input!(19);
 
         let expected: Solution = (102, 318).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
-        assert_eq!(actual, expected)
+        assert_eq!(actual, expected);
+        assert_eq!(super::Earley::new().solve(&input).unwrap(), expected);
+        assert_eq!(super::Memoized::new().solve(&input).unwrap(), expected);
     }
-
-    const EXAMPLE_INPUT_P2: &str = r#"42: 9 14 | 10 1
-9: 14 27 | 1 26
-10: 23 14 | 28 1
-1: "a"
-11: 42 31
-5: 1 14 | 15 1
-19: 14 1 | 14 14
-12: 24 14 | 19 1
-16: 15 1 | 14 14
-31: 14 17 | 1 13
-6: 14 14 | 1 14
-2: 1 24 | 14 4
-0: 8 11
-13: 14 3 | 1 12
-15: 1 | 14
-17: 14 2 | 1 7
-23: 25 1 | 22 14
-28: 16 1
-4: 1 1
-20: 14 14 | 1 15
-3: 5 14 | 16 1
-27: 1 6 | 14 18
-14: "b"
-21: 14 1 | 1 14
-25: 1 1 | 1 14
-22: 14 14
-8: 42
-26: 14 22 | 1 20
-18: 15 15
-7: 14 5 | 1 21
-24: 14 1
-
-abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa
-bbabbbbaabaabba
-babbbbaabbbbbabbbbbbaabaaabaaa
-aaabbbbbbaaaabaababaabababbabaaabbababababaaa
-bbbbbbbaaaabbbbaaabbabaaa
-bbbababbbbaaaaaaaabbababaaababaabab
-ababaaaaaabaaab
-ababaaaaabbbaba
-baabbaaaabbaaaababbaababb
-abbbbabbbbaaaababbbbbbaaaababb
-aaaaabbaabaaaaababaa
-aaaabbaaaabbaaa
-aaaabbaabbaaaaaaabbbabbbaaabbaabaaa
-babaaabbbaaabaababbaabababaaab
-aabbbbbaabbbaaaaaabbbbbababaaaaabbaaabba"#;
 }
@@ -1,7 +1,17 @@
-use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
+use std::io;
 use std::iter::FromIterator;
 
+use anyhow::{bail, ensure, Context};
+
+use crate::bitset::BitSet;
+use crate::generate::{Generate, Rng};
+use crate::geometry::{Point2, Rotation, Symmetry};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
+use crate::visualize::{Bitmap, FrameWriter, Visualize};
 
 const SEA_MONSTER: &str = "                  #
 #    ##    ##    ###
@@ -17,144 +27,511 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = FastMap<usize, Tile<'a>>;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         20
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let tiles = parse_input(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            alphabet: Some("0123456789Tile:#. "),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<FastMap<usize, Tile<'a>>> {
+        parse_input(input)
+    }
+
+    fn part1(&self, tiles: &FastMap<usize, Tile<'_>>) -> anyhow::Result<usize> {
+        part1(tiles)
+    }
+
+    fn part2(&self, tiles: &FastMap<usize, Tile<'_>>) -> anyhow::Result<usize> {
+        part2(tiles)
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Some(self)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+impl Visualize for Solver {
+    /// The assembled image, with tile borders removed.
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()> {
+        let assemble = || -> anyhow::Result<Image> {
+            let tiles = parse_input(input)?;
+            Ok(Image::from_raw_image(RawImage::try_from(&tiles)?, &tiles))
+        };
+        let image = assemble()
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+
+        frames.write("image", image.frame())?;
+        Ok(())
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        12
+    }
+
+    /// A `size` by `size` image with some sea monsters in it, cut into tiles which are then
+    /// shuffled, rotated and flipped.
+    ///
+    /// Tile borders are chosen so that every edge only matches the one it was cut from, and
+    /// none reads the same both ways.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let side = size.max(3);
+        let image = monster_image(rng, side * 8);
+
+        // `corners[y][x]` is the pixel where four tiles meet, so the edges running through it
+        // agree on it.
+        let corners: Vec<Vec<bool>> = (0..=side)
+            .map(|_| (0..=side).map(|_| rng.chance(0.5)).collect())
+            .collect();
+        let mut used = FastSet::default();
+        let mut edge = |rng: &mut Rng, start: bool, end: bool| loop {
+            let mut bits = vec![start];
+            bits.extend((0..8).map(|_| rng.chance(0.5)));
+            bits.push(end);
+            let forward: Edge = bits.iter().copied().collect();
+            let reverse = forward.reverse();
+            // An edge and its reverse are always used together.
+            if forward != reverse && !used.contains(&forward) {
+                used.insert(forward);
+                used.insert(reverse);
+                return bits;
+            }
+        };
+        let horizontal: Vec<Vec<Vec<bool>>> = (0..=side)
+            .map(|y| {
+                (0..side)
+                    .map(|x| edge(rng, corners[y][x], corners[y][x + 1]))
+                    .collect()
+            })
+            .collect();
+        let vertical: Vec<Vec<Vec<bool>>> = (0..side)
+            .map(|y| {
+                (0..=side)
+                    .map(|x| edge(rng, corners[y][x], corners[y + 1][x]))
+                    .collect()
+            })
+            .collect();
+
+        let mut ids: Vec<usize> = (1000..10_000).collect();
+        rng.shuffle(&mut ids);
+
+        let mut tiles = Vec::with_capacity(side * side);
+        for y in 0..side {
+            for x in 0..side {
+                let mut pixels = vec![vec![false; 10]; 10];
+                for i in 0..10 {
+                    pixels[0][i] = horizontal[y][x][i];
+                    pixels[9][i] = horizontal[y + 1][x][i];
+                    pixels[i][0] = vertical[y][x][i];
+                    pixels[i][9] = vertical[y][x + 1][i];
+                }
+                for (row, pixel_row) in pixels.iter_mut().enumerate().skip(1).take(8) {
+                    for (col, pixel) in pixel_row.iter_mut().enumerate().skip(1).take(8) {
+                        *pixel = image[y * 8 + row - 1][x * 8 + col - 1];
+                    }
+                }
+
+                for _ in 0..rng.range(0..4) {
+                    pixels = (0..10)
+                        .map(|row| (0..10).map(|col| pixels[9 - col][row]).collect())
+                        .collect();
+                }
+                if rng.chance(0.5) {
+                    pixels.iter_mut().for_each(|row| row.reverse());
+                }
+                tiles.push((ids[tiles.len()], pixels));
+            }
+        }
+        rng.shuffle(&mut tiles);
+
+        let tiles: Vec<String> = tiles
+            .iter()
+            .map(|(id, pixels)| {
+                let rows: String = pixels
+                    .iter()
+                    .map(|row| {
+                        let mut row: String =
+                            row.iter().map(|&p| if p { '#' } else { '.' }).collect();
+                        row.push('\n');
+                        row
+                    })
+                    .collect();
+                format!("Tile {}:\n{}", id, rows)
+            })
+            .collect();
+        tiles.join("\n")
+    }
+}
+
+impl Reference for Solver {
+    /// Lay the tiles out row by row, trying every unused tile in every orientation at each spot
+    /// and backing out of dead ends. Then look for sea monsters in every orientation of the image.
+    fn solve_reference(&self, input: &str) -> Solution {
+        type Grid = Vec<Vec<char>>;
+
+        fn orientations(grid: &[Vec<char>]) -> Vec<Grid> {
+            let mut grid = grid.to_vec();
+            let mut all = Vec::new();
+            for _ in 0..4 {
+                all.push(
+                    grid.iter()
+                        .map(|row| row.iter().rev().copied().collect())
+                        .collect(),
+                );
+                let side = grid.len();
+                let rotated = (0..side)
+                    .map(|y| (0..side).map(|x| grid[side - 1 - x][y]).collect())
+                    .collect();
+                all.push(std::mem::replace(&mut grid, rotated));
+            }
+            all
+        }
 
-        let part1 = part1(&tiles);
-        let part2 = part2(&tiles);
+        // Fill the spots after `placed` with `(tile, orientation)` pairs, row by row.
+        fn place(
+            tiles: &[(usize, Vec<Grid>)],
+            side: usize,
+            placed: &mut Vec<(usize, usize)>,
+            used: &mut [bool],
+        ) -> bool {
+            let spot = placed.len();
+            if spot == tiles.len() {
+                return true;
+            }
+            let grid_at = |spot: usize| {
+                let (tile, orientation) = placed[spot];
+                &tiles[tile].1[orientation]
+            };
+            let left = if spot.is_multiple_of(side) {
+                None
+            } else {
+                Some(grid_at(spot - 1))
+            };
+            let above = spot.checked_sub(side).map(grid_at);
+
+            for tile in 0..tiles.len() {
+                if used[tile] {
+                    continue;
+                }
+                for orientation in 0..8 {
+                    let grid = &tiles[tile].1[orientation];
+                    let fits_left = left.is_none_or(|left| {
+                        left.iter()
+                            .zip(grid)
+                            .all(|(left, right)| left.last() == right.first())
+                    });
+                    let fits_above = above.is_none_or(|above| above.last() == grid.first());
+                    if !fits_left || !fits_above {
+                        continue;
+                    }
+
+                    used[tile] = true;
+                    placed.push((tile, orientation));
+                    if place(tiles, side, placed, used) {
+                        return true;
+                    }
+                    placed.pop();
+                    used[tile] = false;
+                }
+            }
+            false
+        }
+
+        let tiles: Vec<(usize, Vec<Grid>)> = input
+            .split("\n\n")
+            .filter(|tile| !tile.trim().is_empty())
+            .map(|tile| {
+                let (header, grid) = tile.split_once('\n').unwrap();
+                let id = header
+                    .trim_start_matches("Tile ")
+                    .trim_end_matches(':')
+                    .parse()
+                    .unwrap();
+                let grid: Grid = grid.lines().map(|line| line.chars().collect()).collect();
+                (id, orientations(&grid))
+            })
+            .collect();
+        let side = (1..).find(|side| side * side >= tiles.len()).unwrap();
+        let mut placed = Vec::new();
+        assert!(
+            place(&tiles, side, &mut placed, &mut vec![false; tiles.len()]),
+            "The tiles don't fit together"
+        );
+
+        let part1: usize = [0, side - 1, side * (side - 1), side * side - 1]
+            .iter()
+            .map(|&spot| tiles[placed[spot].0].0)
+            .product();
+
+        let inner = tiles[0].1[0].len() - 2;
+        let mut image: Grid = vec![Vec::new(); side * inner];
+        for (spot, &(tile, orientation)) in placed.iter().enumerate() {
+            let grid = &tiles[tile].1[orientation];
+            for (y, row) in grid[1..=inner].iter().enumerate() {
+                image[spot / side * inner + y].extend(&row[1..=inner]);
+            }
+        }
+
+        let monster: Vec<(usize, usize)> = SEA_MONSTER
+            .lines()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.chars()
+                    .enumerate()
+                    .filter(|&(_, c)| c == '#')
+                    .map(move |(x, _)| (y, x))
+            })
+            .collect();
+        let (monster_height, monster_width) = (
+            monster.iter().map(|&(y, _)| y).max().unwrap() + 1,
+            monster.iter().map(|&(_, x)| x).max().unwrap() + 1,
+        );
+        let rough = image.iter().flatten().filter(|&&c| c == '#').count();
+        let part2 = orientations(&image)
+            .iter()
+            .find_map(|image| {
+                let mut covered = FastSet::default();
+                for y in 0..=image.len().saturating_sub(monster_height) {
+                    for x in 0..=image[y].len().saturating_sub(monster_width) {
+                        if monster.iter().all(|&(dy, dx)| image[y + dy][x + dx] == '#') {
+                            covered.extend(monster.iter().map(|&(dy, dx)| (y + dy, x + dx)));
+                        }
+                    }
+                }
+                if covered.is_empty() {
+                    None
+                } else {
+                    Some(rough - covered.len())
+                }
+            })
+            .unwrap_or(rough);
 
         (part1, part2).into()
     }
 }
 
-fn parse_input(input: &str) -> HashMap<usize, Tile> {
-    input
-        .split("\n\n")
-        .filter(|s| !s.is_empty())
-        .map(Tile::from_str)
-        .collect()
+/// A sparse random image of `len` by `len` pixels with non-overlapping sea monsters.
+fn monster_image(rng: &mut Rng, len: usize) -> Vec<Vec<bool>> {
+    let monster: Vec<(usize, usize)> = SEA_MONSTER
+        .lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.chars()
+                .enumerate()
+                .filter(|&(_, c)| c == '#')
+                .map(move |(x, _)| (x, y))
+        })
+        .collect();
+    // The monster turned and mirrored every way, starting with the upright one.
+    let shapes: Vec<Vec<(usize, usize)>> = Symmetry::ALL
+        .iter()
+        .map(|symmetry| transform(&monster, *symmetry, 20, 3))
+        .collect();
+
+    loop {
+        let mut image: Vec<Vec<bool>> = (0..len)
+            .map(|_| (0..len).map(|_| rng.chance(0.3)).collect())
+            .collect();
+
+        let mut taken = vec![vec![false; len]; len];
+        let mut placed = 0;
+        for _ in 0..len / 4 {
+            let (x, y) = (rng.range(0..len - 19), rng.range(0..len - 2));
+            let area = &mut taken[y..y + 3];
+            if area.iter().any(|row| row[x..x + 20].contains(&true)) {
+                continue;
+            }
+            for row in area {
+                row[x..x + 20].fill(true);
+            }
+            for &(dx, dy) in &monster {
+                image[y + dy][x + dx] = true;
+            }
+            placed += 1;
+        }
+
+        // Start over if the noise made any monsters of its own, since they could overlap the
+        // placed ones or face another way.
+        let found: usize = shapes
+            .iter()
+            .map(|shape| {
+                (0..len)
+                    .flat_map(|y| (0..len).map(move |x| (x, y)))
+                    .filter(|&(x, y)| {
+                        shape.iter().all(|&(dx, dy)| {
+                            image.get(y + dy).and_then(|row| row.get(x + dx)) == Some(&true)
+                        })
+                    })
+                    .count()
+            })
+            .sum();
+        if found == placed {
+            return image;
+        }
+    }
+}
+
+/// Parse the tiles, which must have distinct IDs and be enough to fill a square.
+fn parse_input(input: &str) -> anyhow::Result<FastMap<usize, Tile<'_>>> {
+    let mut tiles = FastMap::default();
+    for tile in input.split("\n\n").filter(|s| !s.is_empty()) {
+        let (id, tile) = Tile::from_str(tile)?;
+        ensure!(tiles.insert(id, tile).is_none(), "Tile {} is repeated", id);
+    }
+
+    let side_len = (tiles.len() as f64).sqrt() as usize;
+    ensure!(
+        !tiles.is_empty() && side_len * side_len == tiles.len(),
+        "{} tiles can't make a square image",
+        tiles.len()
+    );
+    Ok(tiles)
 }
 
-fn part1(tiles: &HashMap<usize, Tile>) -> usize {
+fn part1(tiles: &FastMap<usize, Tile<'_>>) -> anyhow::Result<usize> {
     let edge_map = EdgeMap::from(tiles);
-    edge_map.get_corners().product()
+    let corners: Vec<usize> = edge_map.get_corners().collect();
+    ensure!(
+        corners.len() == 4,
+        "Expected 4 corner tiles, found {}",
+        corners.len()
+    );
+    corners
+        .into_iter()
+        .try_fold(1_usize, usize::checked_mul)
+        .context("The product of the corner IDs overflows")
 }
 
-fn part2(tiles: &HashMap<usize, Tile>) -> usize {
-    let raw_image = RawImage::from(tiles);
+fn part2(tiles: &FastMap<usize, Tile<'_>>) -> anyhow::Result<usize> {
+    let raw_image = RawImage::try_from(tiles)?;
     let image = Image::from_raw_image(raw_image, tiles);
-    println!("{}", image);
+    aoc_debug!("Assembled image:\n{}", image);
+
+    let count = image.find_sea_monsters(SEA_MONSTER);
+    let waves = image.count_waves();
+    aoc_debug!("Found {} sea monsters among {} waves", count, waves);
 
-    let count = dbg!(image.find_sea_monsters(SEA_MONSTER));
-    dbg!(image.count_waves()) - count * 15
+    // Monsters can only share waves in images that aren't from the puzzle.
+    waves
+        .checked_sub(count * 15)
+        .context("The sea monsters overlap")
 }
 
+/// A tile's rows of `#` and `.`, borrowed from the input.
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Tile(Vec<Vec<bool>>);
+pub(crate) struct Tile<'a>([&'a [u8]; 10]);
 
-impl Tile {
-    fn from_str(s: &str) -> (usize, Self) {
+impl<'a> Tile<'a> {
+    /// Parse a tile, which must be 10 by 10.
+    fn from_str(s: &'a str) -> anyhow::Result<(usize, Self)> {
         let mut iter = s.lines();
-        let id = iter
-            .next()
-            .unwrap()
+        let header = iter.next().context("Missing tile header")?;
+        let id = header
             .strip_prefix("Tile ")
-            .unwrap()
-            .strip_suffix(':')
-            .unwrap()
-            .parse::<usize>()
-            .unwrap();
+            .and_then(|id| id.strip_suffix(':'))
+            .and_then(|id| id.parse::<usize>().ok())
+            .with_context(|| format!("Invalid tile header: {}", header))?;
+
+        let mut image: [&[u8]; 10] = [&[]; 10];
+        for row in image.iter_mut() {
+            *row = iter.next().unwrap_or_default().as_bytes();
+            if let Some(&c) = row.iter().find(|&&c| c != b'#' && c != b'.') {
+                bail!("Unrecognized token: {}", char::from(c));
+            }
+        }
+        ensure!(
+            iter.next().is_none() && image.iter().all(|row| row.len() == 10),
+            "Tile {} isn't 10 by 10",
+            id
+        );
 
-        let image = iter
-            .map(|l| {
-                l.chars()
-                    .map(|c| match c {
-                        '#' => true,
-                        '.' => false,
-                        _ => unreachable!("Unrecognized token: {}", c),
-                    })
-                    .collect()
-            })
-            .collect();
+        Ok((id, Self(image)))
+    }
 
-        (id, Self(image))
+    fn pixel(&self, x: usize, y: usize) -> bool {
+        self.0[y][x] == b'#'
     }
 
     fn edges(&self) -> impl Iterator<Item = Edge> {
         vec![
-            self.0[0].iter().cloned().collect(),
-            (0..10).map(|i| self.0[i][9]).collect(),
-            self.0[9].iter().cloned().collect::<Edge>().reverse(),
-            (0..10).map(|i| self.0[i][0]).collect::<Edge>().reverse(),
+            (0..10).map(|i| self.pixel(i, 0)).collect(),
+            (0..10).map(|i| self.pixel(9, i)).collect(),
+            (0..10)
+                .map(|i| self.pixel(i, 9))
+                .collect::<Edge>()
+                .reverse(),
+            (0..10)
+                .map(|i| self.pixel(0, i))
+                .collect::<Edge>()
+                .reverse(),
         ]
         .into_iter()
     }
 
+    /// The pixel at `x` and `y` once the tile is mirrored if `flipped`, then turned `rotation`
+    /// quarter turns clockwise.
     fn index(&self, flipped: bool, rotation: u8, x: usize, y: usize) -> bool {
-        if flipped {
-            match rotation {
-                0 => self.0[y][9 - x],
-                1 => self.0[9 - x][9 - y],
-                2 => self.0[9 - y][x],
-                3 => self.0[x][y],
-                _ => unreachable!(),
-            }
-        } else {
-            match rotation {
-                0 => self.0[y][x],
-                1 => self.0[9 - x][y],
-                2 => self.0[9 - y][9 - x],
-                3 => self.0[x][9 - y],
-                _ => unreachable!(),
-            }
-        }
+        let symmetry = Symmetry::new(flipped, Rotation::quarter_turns(rotation.into()));
+        let point = Point2::new(x as i64, y as i64);
+        let source = symmetry.inverse().apply_within(point, 10, 10);
+        self.pixel(source.x() as usize, source.y() as usize)
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-struct Edge(u16);
+/// Pixels along a tile's edge.
+const EDGE_LEN: usize = 10;
+
+/// The pixels set along an edge, numbered from where it starts.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Edge(BitSet);
 
 impl Edge {
-    fn reverse(mut self) -> Self {
-        let mut edge = 0;
-        for _ in 0..10 {
-            edge <<= 1;
-            if 1 == (1 & self.0) {
-                edge += 1;
-            }
-            self.0 >>= 1;
-        }
-        Self(edge)
+    /// The same edge, read from the other end.
+    fn reverse(&self) -> Self {
+        Self(self.0.iter().map(|i| EDGE_LEN - 1 - i).collect())
     }
 }
 
 impl FromIterator<bool> for Edge {
     fn from_iter<T: IntoIterator<Item = bool>>(iter: T) -> Self {
-        let mut edge: u16 = 0;
-        for bit in iter {
-            edge <<= 1;
-            if bit {
-                edge += 1;
-            }
-        }
-        Self(edge)
+        Self(
+            iter.into_iter()
+                .enumerate()
+                .filter(|&(_, bit)| bit)
+                .map(|(i, _)| i)
+                .collect(),
+        )
     }
 }
 
 #[derive(Debug, Default)]
-struct EdgeMap(HashMap<Edge, HashSet<(usize, bool, u8)>>);
+struct EdgeMap(FastMap<Edge, FastSet<(usize, bool, u8)>>);
 
 impl EdgeMap {
     fn insert(&mut self, id: usize, edges: impl Iterator<Item = Edge>) {
         for (rotation, edge) in edges.enumerate() {
             let rotation = rotation as u8;
-            self.insert_one(edge, (id, false, rotation));
             self.insert_one(edge.reverse(), (id, true, rotation));
+            self.insert_one(edge, (id, false, rotation));
         }
     }
 
@@ -167,11 +544,11 @@ impl EdgeMap {
     }
 
     fn get_corners(&self) -> impl Iterator<Item = usize> {
-        let mut counts: HashMap<usize, usize> = HashMap::new();
+        let mut counts: FastMap<usize, usize> = FastMap::default();
 
         for (_, ids) in self.0.iter().filter(|(_, ids)| ids.len() == 1) {
             let (id, _, _) = ids.iter().next().unwrap();
-            if let Some(count) = counts.get_mut(&id) {
+            if let Some(count) = counts.get_mut(id) {
                 *count += 1;
             } else {
                 counts.insert(*id, 1);
@@ -185,8 +562,8 @@ impl EdgeMap {
     }
 }
 
-impl From<&HashMap<usize, Tile>> for EdgeMap {
-    fn from(tiles: &HashMap<usize, Tile>) -> Self {
+impl From<&FastMap<usize, Tile<'_>>> for EdgeMap {
+    fn from(tiles: &FastMap<usize, Tile<'_>>) -> Self {
         let mut edge_map = EdgeMap::default();
         for (id, tile) in tiles.iter() {
             edge_map.insert(*id, tile.edges());
@@ -198,18 +575,37 @@ impl From<&HashMap<usize, Tile>> for EdgeMap {
 #[derive(Debug)]
 struct RawImage(Vec<Vec<Option<(usize, bool, u8)>>>);
 
-impl From<&HashMap<usize, Tile>> for RawImage {
-    fn from(tiles: &HashMap<usize, Tile>) -> Self {
+/// The tile with `id` and the edge it shares with a different tile, if there is one.
+fn neighbour<'m>(
+    tiles: &FastMap<usize, Tile<'_>>,
+    edge_map: &'m EdgeMap,
+    id: usize,
+    rotation: usize,
+) -> anyhow::Result<&'m (usize, bool, u8)> {
+    let edge = tiles[&id].edges().nth(rotation).unwrap();
+    edge_map
+        .0
+        .get(&edge)
+        .and_then(|matches| matches.iter().find(|(other, _, _)| *other != id))
+        .with_context(|| format!("Nothing fits next to tile {}", id))
+}
+
+impl TryFrom<&FastMap<usize, Tile<'_>>> for RawImage {
+    type Error = anyhow::Error;
+
+    /// Lay the tiles out, starting from a corner. `tiles` must be a non-empty square number of
+    /// tiles, as [`parse_input`] makes sure of.
+    fn try_from(tiles: &FastMap<usize, Tile<'_>>) -> anyhow::Result<Self> {
         let side_len = (tiles.len() as f64).sqrt() as usize;
 
         let mut raw_image = vec![vec![None; side_len]; side_len];
 
         let edge_map = EdgeMap::from(tiles);
-        let corner_id = edge_map.get_corners().next().unwrap();
+        let corner_id = edge_map.get_corners().next().context("No corner tiles")?;
 
-        let edges = tiles.get(&corner_id).unwrap().edges().collect::<Vec<_>>();
-        let edge0_matches = edge_map.0.get(&edges[0]).unwrap().len();
-        let edge1_matches = edge_map.0.get(&edges[1]).unwrap().len();
+        let edges = tiles[&corner_id].edges().collect::<Vec<_>>();
+        let edge0_matches = edge_map.0.get(&edges[0]).map_or(0, FastSet::len);
+        let edge1_matches = edge_map.0.get(&edges[1]).map_or(0, FastSet::len);
 
         let corner_rotation = match (edge0_matches == 2, edge1_matches == 2) {
             (true, true) => 1,
@@ -229,25 +625,13 @@ impl From<&HashMap<usize, Tile>> for RawImage {
                 (4 + 2 - old_rotation as usize) % 4
             };
 
-            let old_edge = tiles
-                .get(&old_id)
-                .unwrap()
-                .edges()
-                .nth(old_edge_rotation)
-                .unwrap();
-            let (new_id, edge_flipped, new_edge_rotation) = edge_map
-                .0
-                .get(&old_edge)
-                .unwrap()
-                .iter()
-                .filter(|(id, _, _)| *id != old_id)
-                .next()
-                .unwrap();
+            let (new_id, edge_flipped, new_edge_rotation) =
+                neighbour(tiles, &edge_map, old_id, old_edge_rotation)?;
             // Two unflipped tiles have edges that run opposite one another
             let new_flipped = !(old_flipped ^ edge_flipped);
 
             let new_rotation = if new_flipped {
-                (0 + new_edge_rotation) % 4
+                *new_edge_rotation % 4
             } else {
                 (4 - new_edge_rotation) % 4
             };
@@ -265,20 +649,8 @@ impl From<&HashMap<usize, Tile>> for RawImage {
                     (4 + 1 - old_rotation as usize) % 4
                 };
 
-                let old_edge = tiles
-                    .get(&old_id)
-                    .unwrap()
-                    .edges()
-                    .nth(old_edge_rotation)
-                    .unwrap();
-                let (new_id, edge_flipped, new_edge_rotation) = edge_map
-                    .0
-                    .get(&old_edge)
-                    .unwrap()
-                    .iter()
-                    .filter(|(id, _, _)| *id != old_id)
-                    .next()
-                    .unwrap();
+                let (new_id, edge_flipped, new_edge_rotation) =
+                    neighbour(tiles, &edge_map, old_id, old_edge_rotation)?;
                 // Two unflipped tiles have edges that run opposite one another
                 let new_flipped = !(old_flipped ^ edge_flipped);
 
@@ -295,14 +667,17 @@ impl From<&HashMap<usize, Tile>> for RawImage {
             }
         }
 
-        RawImage(raw_image)
+        let placed: FastSet<usize> = raw_image.iter().flatten().flatten().map(|t| t.0).collect();
+        ensure!(placed.len() == tiles.len(), "The tiles don't fit together");
+
+        Ok(RawImage(raw_image))
     }
 }
 
 struct Image(Vec<Vec<bool>>);
 
 impl Image {
-    fn from_raw_image(raw_image: RawImage, tiles: &HashMap<usize, Tile>) -> Self {
+    fn from_raw_image(raw_image: RawImage, tiles: &FastMap<usize, Tile<'_>>) -> Self {
         let side_len = raw_image.0.len() * 8;
 
         Self(
@@ -325,12 +700,27 @@ impl Image {
         )
     }
 
+    fn frame(&self) -> Bitmap {
+        let width = self.0.first().map_or(0, Vec::len);
+        let mut frame = Bitmap::new(width, self.0.len());
+        for (y, row) in self.0.iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                frame.set(x, y, pixel);
+            }
+        }
+        frame
+    }
+
     fn count_waves(&self) -> usize {
         self.0.iter().flat_map(|v| v.iter()).filter(|b| **b).count()
     }
 
     fn find_sea_monsters(&self, sea_monster: &str) -> usize {
-        let sm_len = sea_monster.lines().next().unwrap().chars().count();
+        let sm_len = sea_monster
+            .lines()
+            .map(|line| line.chars().count())
+            .max()
+            .unwrap();
         let sm_hgt = sea_monster.lines().count();
 
         let sea_monster: Vec<(usize, usize)> = sea_monster
@@ -344,36 +734,25 @@ impl Image {
             })
             .collect();
 
-        let transforms: Vec<Box<dyn Fn(&(usize, usize)) -> (usize, usize)>> = vec![
-            Box::new(|&(x, y)| (x, y)),
-            Box::new(|&(x, y)| {
-                if let None = sm_len.checked_sub(x) {
-                    panic!("sm_len is {}, while x is {}", sm_len, x);
-                } else {
-                    (sm_len - x, y)
-                }
-            }),
-            Box::new(|&(x, y)| (x, sm_hgt - y)),
-            Box::new(|&(x, y)| (sm_len - x, sm_hgt - y)),
-        ];
-
-        let transforms90: Vec<Box<dyn Fn(&(usize, usize)) -> (usize, usize)>> = vec![
-            Box::new(|&(x, y)| (y, x)),
-            Box::new(|&(x, y)| (y, sm_len - x)),
-            Box::new(|&(x, y)| (sm_hgt - y, x)),
-            Box::new(|&(x, y)| (sm_hgt - y, sm_len - x)),
-        ];
-
         let mut count = 0;
 
-        for transform in transforms.into_iter().chain(transforms90) {
-            let sea_monster: Vec<_> = sea_monster.iter().map(transform).collect();
-
-            let max_y = self.0.len() - sm_hgt;
-            let max_x = self.0[0].len() - sm_len;
+        for &symmetry in &Symmetry::ALL {
+            let sea_monster = transform(&sea_monster, symmetry, sm_len, sm_hgt);
+
+            // Transposed monsters swap their width and height.
+            let width = sea_monster.iter().map(|&(x, _)| x).max().unwrap() + 1;
+            let height = sea_monster.iter().map(|&(_, y)| y).max().unwrap() + 1;
+            // Images smaller than the monster, which only come from a handful of tiles.
+            let (max_y, max_x) = match (
+                self.0.len().checked_sub(height),
+                self.0[0].len().checked_sub(width),
+            ) {
+                (Some(max_y), Some(max_x)) => (max_y, max_x),
+                _ => continue,
+            };
 
-            let this_count = (0..max_x)
-                .flat_map(|x| (0..max_y).map(move |y| (x, y)))
+            let this_count = (0..=max_x)
+                .flat_map(|x| (0..=max_y).map(move |y| (x, y)))
                 .filter(|(x, y)| self.contains_monster_at_coords(&sea_monster, *x, *y))
                 .count();
 
@@ -391,24 +770,29 @@ impl Image {
         x: usize,
         y: usize,
     ) -> bool {
-        let sea_monster: HashSet<_> = sea_monster
-            .iter()
-            .map(|(sm_x, sm_y)| (sm_x + x, sm_y + y))
-            .collect();
-
-        self.0
+        sea_monster
             .iter()
-            .enumerate()
-            .flat_map(|(y_index, line)| {
-                let sea_monster = &sea_monster;
-                line.iter()
-                    .enumerate()
-                    .filter(move |(x_index, _pixel)| sea_monster.contains(&(*x_index, y_index)))
-            })
-            .all(|(_, pixel)| *pixel)
+            .all(|(sm_x, sm_y)| self.0[sm_y + y][sm_x + x])
     }
 }
 
+/// The pixels at `points` in a `width` by `height` box, moved by `symmetry`.
+fn transform(
+    points: &[(usize, usize)],
+    symmetry: Symmetry,
+    width: usize,
+    height: usize,
+) -> Vec<(usize, usize)> {
+    points
+        .iter()
+        .map(|&(x, y)| {
+            let point = Point2::new(x as i64, y as i64);
+            let moved = symmetry.apply_within(point, width as i64, height as i64);
+            (moved.x() as usize, moved.y() as usize)
+        })
+        .collect()
+}
+
 impl std::fmt::Display for Image {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for row in self.0.iter() {
@@ -428,206 +812,59 @@ impl std::fmt::Display for Image {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
     #[test]
     fn test_edge() {
-        let tiles = parse_input(INPUT);
+        let input = example_input(20, "example");
+        let tiles = parse_input(&input).unwrap();
         let tile = tiles.get(&2311).unwrap();
         let edge = tile.edges().next().unwrap();
-        assert_eq!(Edge(0b0011010010), edge)
+        assert_eq!(edge_of("..##.#..#."), edge)
     }
 
     #[test]
     fn test_reverse() {
-        assert_eq!(Edge(0b0011100010).reverse(), Edge(0b0100011100))
-    }
-
-    #[test]
-    fn example_part1() {
-        let expected = 20899048083289;
-        let actual = part1(&parse_input(INPUT));
-        assert_eq!(actual, expected)
+        assert_eq!(edge_of("..###...#.").reverse(), edge_of(".#...###.."))
     }
 
-    #[test]
-    fn example_part2() {
-        let expected = 273;
-        let actual = part2(&parse_input(INPUT));
-        assert_eq!(actual, expected)
+    fn edge_of(pixels: &str) -> Edge {
+        pixels.chars().map(|c| c == '#').collect()
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day20.txt");
+        let input = require_input!(20);
 
-        let expected: Solution = (64_802_175_715_999_usize, 0).into();
-        let actual = solver.solve(input);
+        let expected: Solution = (64_802_175_715_999_usize, 2_146).into();
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_tile_from_str() {
-        const INPUT: &str = "\
-Tile 2311:
-..##.#..#.
-##..#.....
-#...##..#.
-####.#...#
-##.##.###.
-##...#.###
-.#.#.#..##
-..#....#..
-###...#.#.
-..###..###";
-        let expected: (usize, Tile) = (
+        let input = example_input(20, "example");
+        let first = input.split("\n\n").next().unwrap();
+        let expected: (usize, Tile<'_>) = (
             2311,
-            Tile(vec![
-                vec![
-                    false, false, true, true, false, true, false, false, true, false,
-                ],
-                vec![
-                    true, true, false, false, true, false, false, false, false, false,
-                ],
-                vec![
-                    true, false, false, false, true, true, false, false, true, false,
-                ],
-                vec![
-                    true, true, true, true, false, true, false, false, false, true,
-                ],
-                vec![
-                    true, true, false, true, true, false, true, true, true, false,
-                ],
-                vec![
-                    true, true, false, false, false, true, false, true, true, true,
-                ],
-                vec![
-                    false, true, false, true, false, true, false, false, true, true,
-                ],
-                vec![
-                    false, false, true, false, false, false, false, true, false, false,
-                ],
-                vec![
-                    true, true, true, false, false, false, true, false, true, false,
-                ],
-                vec![
-                    false, false, true, true, true, false, false, true, true, true,
-                ],
+            Tile([
+                b"..##.#..#.",
+                b"##..#.....",
+                b"#...##..#.",
+                b"####.#...#",
+                b"##.##.###.",
+                b"##...#.###",
+                b".#.#.#..##",
+                b"..#....#..",
+                b"###...#.#.",
+                b"..###..###",
             ]),
         );
-        let actual = Tile::from_str(INPUT);
+        let actual = Tile::from_str(first).unwrap();
         assert_eq!(actual, expected)
     }
-
-    const INPUT: &str = "\
-Tile 2311:
-..##.#..#.
-##..#.....
-#...##..#.
-####.#...#
-##.##.###.
-##...#.###
-.#.#.#..##
-..#....#..
-###...#.#.
-..###..###
-
-Tile 1951:
-#.##...##.
-#.####...#
-.....#..##
-#...######
-.##.#....#
-.###.#####
-###.##.##.
-.###....#.
-..#.#..#.#
-#...##.#..
-
-Tile 1171:
-####...##.
-#..##.#..#
-##.#..#.#.
-.###.####.
-..###.####
-.##....##.
-.#...####.
-#.##.####.
-####..#...
-.....##...
-
-Tile 1427:
-###.##.#..
-.#..#.##..
-.#.##.#..#
-#.#.#.##.#
-....#...##
-...##..##.
-...#.#####
-.#.####.#.
-..#..###.#
-..##.#..#.
-
-Tile 1489:
-##.#.#....
-..##...#..
-.##..##...
-..#...#...
-#####...#.
-#..#.#.#.#
-...#.#.#..
-##.#...##.
-..##.##.##
-###.##.#..
-
-Tile 2473:
-#....####.
-#..#.##...
-#.##..#...
-######.#.#
-.#...#.#.#
-.#########
-.###.#..#.
-########.#
-##...##.#.
-..###.#.#.
-
-Tile 2971:
-..#.#....#
-#...###...
-#.#.###...
-##.##..#..
-.#####..##
-.#..####.#
-#..#.#..#.
-..####.###
-..#.#.###.
-...#.#.#.#
-
-Tile 2729:
-...#.#.#.#
-####.#....
-..#.#.....
-....#..#.#
-.##..##.#.
-.#.####...
-####.#.#..
-##.####...
-##..#.##..
-#.##...##.
-
-Tile 3079:
-#.#.#####.
-.#..######
-..#.......
-######....
-####.#..#.
-.#...#.##.
-#.#####.##
-..#.###...
-..#.......
-..#.###...";
 }
@@ -1,5 +1,13 @@
-use std::collections::{HashMap, HashSet};
+use std::convert::TryFrom;
 
+use anyhow::Context;
+
+use crate::assignment;
+use crate::explain::{Explain, Explanation};
+use crate::generate::{Generate, Rng};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -12,18 +20,199 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Vec<Food<'a>>;
+    type Part1 = usize;
+    type Part2 = String;
+
     fn day(&self) -> u8 {
         21
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let foods = input.lines().map(Food::from).collect::<Vec<Food>>();
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("abcdefghijklmnopqrstuvwxyz(), "),
+            ..Shape::default()
+        }
+    }
+
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Vec<Food<'a>>> {
+        parse(input)
+    }
+
+    fn part1(&self, foods: &Vec<Food<'_>>) -> anyhow::Result<usize> {
+        Ok(part1(foods))
+    }
+
+    fn part2(&self, foods: &Vec<Food<'_>>) -> anyhow::Result<String> {
+        part2(foods)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Some(self)
+    }
+}
 
-        let part1 = part1(&foods);
-        let part2 = part2(&foods);
+impl Explain for Solver {
+    /// The ingredient each allergen is in, which part 2 lists, and how many are left safe.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()> {
+        let foods = parse(input)?;
 
-        (part1, part2).into()
+        let section = explanation.section("Allergens");
+        for (allergen, ingredient) in dangerous_ingredients(&foods)? {
+            section.row(allergen, ingredient);
+        }
+        explanation
+            .section("Ingredients")
+            .row("total", Food::all_ingredients(&foods).len())
+            .row("safe appearances", part1(&foods));
+        Ok(())
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        40
+    }
+
+    /// `size` foods (or more, until the allergens are pinned down) made from 200 ingredients,
+    /// eight of which each contain a different allergen.
+    ///
+    /// Foods don't always list every allergen they contain.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const ALLERGENS: [&str; 8] = [
+            "dairy",
+            "eggs",
+            "fish",
+            "nuts",
+            "peanuts",
+            "sesame",
+            "shellfish",
+            "soy",
+        ];
+
+        let mut ingredients: Vec<String> = Vec::new();
+        while ingredients.len() < 200 {
+            let ingredient = rng.word(3..9);
+            if !ingredients.contains(&ingredient) {
+                ingredients.push(ingredient);
+            }
+        }
+
+        let mut foods = Vec::new();
+        loop {
+            let mut food: Vec<&str> = Vec::new();
+            let mut listed: Vec<&str> = Vec::new();
+            for (i, allergen) in ALLERGENS.iter().enumerate() {
+                if rng.chance(0.3) {
+                    food.push(&ingredients[i]);
+                    if rng.chance(0.8) {
+                        listed.push(allergen);
+                    }
+                }
+            }
+            if listed.is_empty() {
+                listed.push(ALLERGENS[0]);
+                if !food.contains(&ingredients[0].as_str()) {
+                    food.push(&ingredients[0]);
+                }
+            }
+            food.extend(
+                ingredients[ALLERGENS.len()..]
+                    .iter()
+                    .filter(|_| rng.chance(0.3))
+                    .map(String::as_str),
+            );
+            rng.shuffle(&mut food);
+            foods.push(format!(
+                "{} (contains {})\n",
+                food.join(" "),
+                listed.join(", ")
+            ));
+
+            if foods.len() >= size {
+                let input = foods.concat();
+                let parsed = parse(&input).expect("generated foods are well formed");
+                let candidates = Food::allergen_map(&parsed);
+                if candidates.len() == ALLERGENS.len() && candidates.values().all(|c| c.len() == 1)
+                {
+                    return input;
+                }
+            }
+        }
+    }
+}
+
+impl Reference for Solver {
+    /// Narrow down each allergen to the ingredients in every food listing it, then settle them
+    /// one at a time, always picking an allergen that has a single candidate left.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let foods: Vec<(Vec<&str>, Vec<&str>)> = input
+            .lines()
+            .map(|line| {
+                let (ingredients, allergens) = line.split_once(" (contains ").unwrap();
+                (
+                    ingredients.split(' ').collect(),
+                    allergens.trim_end_matches(')').split(", ").collect(),
+                )
+            })
+            .collect();
+
+        let mut candidates: Vec<(&str, Vec<&str>)> = Vec::new();
+        for (ingredients, allergens) in &foods {
+            for &allergen in allergens {
+                match candidates.iter_mut().find(|(name, _)| *name == allergen) {
+                    Some((_, options)) => options.retain(|option| ingredients.contains(option)),
+                    None => candidates.push((allergen, ingredients.clone())),
+                }
+            }
+        }
+
+        let part1 = foods
+            .iter()
+            .flat_map(|(ingredients, _)| ingredients)
+            .filter(|ingredient| {
+                candidates
+                    .iter()
+                    .all(|(_, options)| !options.contains(ingredient))
+            })
+            .count();
+
+        let mut dangerous: Vec<(&str, &str)> = Vec::new();
+        while dangerous.len() < candidates.len() {
+            let (allergen, ingredient) = candidates
+                .iter()
+                .find_map(|(allergen, options)| {
+                    let left: Vec<&&str> = options
+                        .iter()
+                        .filter(|option| dangerous.iter().all(|(_, taken)| taken != *option))
+                        .collect();
+                    match left[..] {
+                        [ingredient] if dangerous.iter().all(|(name, _)| name != allergen) => {
+                            Some((*allergen, *ingredient))
+                        }
+                        _ => None,
+                    }
+                })
+                .expect("Allergens can't be settled one at a time");
+            dangerous.push((allergen, ingredient));
+        }
+        dangerous.sort_unstable();
+        let part2: Vec<&str> = dangerous
+            .iter()
+            .map(|&(_, ingredient)| ingredient)
+            .collect();
+
+        (part1, part2.join(",")).into()
     }
 }
 
@@ -33,10 +222,10 @@ fn part1(foods: &[Food]) -> usize {
 
     let has_allergens = allergen_map
         .values()
-        .flat_map(HashSet::iter)
+        .flat_map(FastSet::iter)
         .cloned()
         .collect();
-    let no_allergens: HashSet<_> = all_ingredients.difference(&has_allergens).collect();
+    let no_allergens: FastSet<_> = all_ingredients.difference(&has_allergens).collect();
 
     let mut count = 0;
     for Food {
@@ -52,42 +241,44 @@ fn part1(foods: &[Food]) -> usize {
     count
 }
 
-fn part2(foods: &[Food]) -> String {
-    let mut allergen_map: Vec<_> = Food::allergen_map(foods).into_iter().collect();
-
-    for idx in 1..allergen_map.len() {
-        allergen_map.sort_by_key(|(_, ingredients)| ingredients.len());
-
-        let (single_ingredients, multi_ingredients) = allergen_map.split_at_mut(idx);
+fn parse(input: &str) -> anyhow::Result<Vec<Food<'_>>> {
+    input.lines().map(Food::try_from).collect()
+}
 
-        if let Some(current_ingredient) = single_ingredients.last().unwrap().1.iter().next() {
-            for ingredients in multi_ingredients {
-                ingredients.1.retain(|i| i != current_ingredient);
-            }
-        }
-    }
+fn part2(foods: &[Food]) -> anyhow::Result<String> {
+    let dangerous: Vec<&str> = dangerous_ingredients(foods)?
+        .into_iter()
+        .map(|(_, ingredient)| ingredient)
+        .collect();
+    Ok(dangerous.join(","))
+}
 
+/// Each allergen and the ingredient it's in, sorted by allergen.
+fn dangerous_ingredients<'f>(foods: &'f [Food]) -> anyhow::Result<Vec<(&'f str, &'f str)>> {
+    let mut allergen_map: Vec<_> = Food::allergen_map(foods).into_iter().collect();
     allergen_map.sort_by_key(|(allergen, _)| *allergen);
 
-    let mut display = String::new();
-    for (_, ingredients) in allergen_map {
-        let ingredient = ingredients.iter().next().unwrap();
-        display.push_str(ingredient);
-        display.push(',');
-    }
-    display.pop();
+    let (allergens, candidates): (Vec<&str>, Vec<FastSet<&str>>) = allergen_map.into_iter().unzip();
 
-    display
+    let dangerous = assignment::assign(&candidates).map_err(|e| {
+        let allergens: Vec<_> = e.items().iter().map(|&i| allergens[i]).collect();
+        anyhow::anyhow!(
+            "Unable to match allergens {:?} to ingredients: {}",
+            allergens,
+            e
+        )
+    })?;
+    Ok(allergens.into_iter().zip(dangerous).collect())
 }
 
 #[derive(Debug, PartialEq, Eq)]
-struct Food<'f> {
-    ingredients: HashSet<&'f str>,
-    allergens: HashSet<&'f str>,
+pub(crate) struct Food<'f> {
+    ingredients: FastSet<&'f str>,
+    allergens: FastSet<&'f str>,
 }
 
 impl Food<'_> {
-    fn all_ingredients(foods: &[Self]) -> HashSet<&str> {
+    fn all_ingredients(foods: &[Self]) -> FastSet<&str> {
         foods
             .iter()
             .flat_map(|food| food.ingredients.iter())
@@ -95,8 +286,8 @@ impl Food<'_> {
             .collect()
     }
 
-    fn allergen_map(foods: &[Self]) -> HashMap<&str, HashSet<&str>> {
-        let mut allergen_map = HashMap::new();
+    fn allergen_map(foods: &[Self]) -> FastMap<&str, FastSet<&str>> {
+        let mut allergen_map = FastMap::default();
         for Food {
             ingredients,
             allergens,
@@ -113,22 +304,20 @@ impl Food<'_> {
     }
 }
 
-impl<'f, 's: 'f> From<&'s str> for Food<'f> {
-    fn from(line: &'s str) -> Self {
-        let mut iter = line.split(" (contains ");
-        let ingredients = iter.next().unwrap().split(' ').collect();
-        let allergens = iter
-            .next()
-            .unwrap()
-            .strip_suffix(')')
-            .unwrap()
-            .split(',')
-            .map(str::trim)
-            .collect();
-        Self {
+impl<'f, 's: 'f> TryFrom<&'s str> for Food<'f> {
+    type Error = anyhow::Error;
+
+    fn try_from(line: &'s str) -> anyhow::Result<Self> {
+        let (ingredients, allergens) = line
+            .split_once(" (contains ")
+            .and_then(|(ingredients, allergens)| Some((ingredients, allergens.strip_suffix(')')?)))
+            .with_context(|| format!("Invalid food: {}", line))?;
+        let ingredients = ingredients.split(' ').collect();
+        let allergens = allergens.split(',').map(str::trim).collect();
+        Ok(Self {
             ingredients,
             allergens,
-        }
+        })
     }
 }
 
@@ -136,94 +325,62 @@ impl<'f, 's: 'f> From<&'s str> for Food<'f> {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
-
-    const INPUT: &str = "\
-mxmxvkd kfcds sqjhc nhms (contains dairy, fish)
-trh fvjkl sbzzf mxmxvkd (contains dairy)
-sqjhc fvjkl (contains soy)
-sqjhc mxmxvkd sbzzf (contains fish)";
+    use std::iter::FromIterator;
 
     #[test]
     fn test_parse() {
         let expected = vec![
             Food {
-                ingredients: HashSet::from(
-                    ["mxmxvkd", "kfcds", "sqjhc", "nhms"]
-                        .iter()
-                        .cloned()
-                        .collect(),
-                ),
-                allergens: HashSet::from(["dairy", "fish"].iter().cloned().collect()),
+                ingredients: FastSet::from_iter(["mxmxvkd", "kfcds", "sqjhc", "nhms"]),
+                allergens: FastSet::from_iter(["dairy", "fish"]),
             },
             Food {
-                ingredients: HashSet::from(
-                    ["trh", "fvjkl", "sbzzf", "mxmxvkd"]
-                        .iter()
-                        .cloned()
-                        .collect(),
-                ),
-                allergens: HashSet::from(["dairy"].iter().cloned().collect()),
+                ingredients: FastSet::from_iter(["trh", "fvjkl", "sbzzf", "mxmxvkd"]),
+                allergens: FastSet::from_iter(["dairy"]),
             },
             Food {
-                ingredients: HashSet::from(["sqjhc", "fvjkl"].iter().cloned().collect()),
-                allergens: HashSet::from(["soy"].iter().cloned().collect()),
+                ingredients: FastSet::from_iter(["sqjhc", "fvjkl"]),
+                allergens: FastSet::from_iter(["soy"]),
             },
             Food {
-                ingredients: HashSet::from(["sqjhc", "mxmxvkd", "sbzzf"].iter().cloned().collect()),
-                allergens: HashSet::from(["fish"].iter().cloned().collect()),
+                ingredients: FastSet::from_iter(["sqjhc", "mxmxvkd", "sbzzf"]),
+                allergens: FastSet::from_iter(["fish"]),
             },
         ];
-        let actual = INPUT.lines().map(Food::from).collect::<Vec<Food>>();
+        let input = example_input(21, "example");
+        let actual = parse(&input).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_food_candidates() {
-        let dairy: HashSet<&str> = HashSet::from(["mxmxvkd"].iter().cloned().collect());
-        let fish: HashSet<&str> = HashSet::from(["mxmxvkd", "sqjhc"].iter().cloned().collect());
-        let soy: HashSet<&str> = HashSet::from(["sqjhc", "fvjkl"].iter().cloned().collect());
-        let expected: HashMap<&str, HashSet<&str>> = HashMap::from(
-            [("dairy", dairy), ("fish", fish), ("soy", soy)]
-                .iter()
-                .cloned()
-                .collect(),
-        );
-
-        let foods = INPUT.lines().map(Food::from).collect::<Vec<Food>>();
+        let dairy: FastSet<&str> = FastSet::from_iter(["mxmxvkd"]);
+        let fish: FastSet<&str> = FastSet::from_iter(["mxmxvkd", "sqjhc"]);
+        let soy: FastSet<&str> = FastSet::from_iter(["sqjhc", "fvjkl"]);
+        let expected: FastMap<&str, FastSet<&str>> =
+            FastMap::from_iter([("dairy", dairy), ("fish", fish), ("soy", soy)]);
+
+        let input = example_input(21, "example");
+        let foods = parse(&input).unwrap();
         let actual = Food::allergen_map(&foods);
 
         assert_eq!(actual, expected)
     }
 
-    #[test]
-    fn example_part1() {
-        let foods = INPUT.lines().map(Food::from).collect::<Vec<Food>>();
-        let expected = 5;
-        let actual = part1(&foods);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
-        let foods = INPUT.lines().map(Food::from).collect::<Vec<Food>>();
-        let expected = String::from("mxmxvkd,sqjhc,fvjkl");
-        let actual = part2(&foods);
-        assert_eq!(actual, expected)
-    }
-
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day21.txt");
+        let input = require_input!(21);
 
         let expected: Solution = (
             2061,
             String::from("cdqvp,dglm,zhqjs,rbpg,xvtrfz,tgmzqjz,mfqgx,rffqhl"),
         )
             .into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,6 +1,13 @@
 use std::collections::VecDeque;
 use std::iter::FromIterator;
 
+use anyhow::{bail, ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::hash::FastSet;
+use crate::lint::Shape;
+use crate::progress::Progress;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -13,54 +20,198 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = (Deck, Deck);
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         22
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let (deck1, deck2) = parse_input(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(2),
+            alphabet: Some("0123456789Player: "),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<(Deck, Deck)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, (deck1, deck2): &(Deck, Deck)) -> anyhow::Result<usize> {
+        part1(deck1, deck2)
+    }
+
+    fn part2(&self, (deck1, deck2): &(Deck, Deck)) -> anyhow::Result<usize> {
+        part2(deck1, deck2)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        25
+    }
+
+    /// Two shuffled decks of `size` cards each, whose game of Combat ends.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let size = size.max(1);
+        loop {
+            let mut cards: Vec<usize> = (1..=2 * size).collect();
+            rng.shuffle(&mut cards);
+            let deck1: Deck = cards[..size].iter().copied().collect();
+            let deck2: Deck = cards[size..].iter().copied().collect();
+
+            // Plain Combat has no rule against repeats, so it can go on forever.
+            let (mut d1, mut d2) = (deck1.clone(), deck2.clone());
+            let mut rounds = 0;
+            while !d1.is_empty() && !d2.is_empty() && rounds < MAX_ROUNDS {
+                d1.combat_round(&mut d2);
+                rounds += 1;
+            }
+            if rounds == MAX_ROUNDS {
+                continue;
+            }
+
+            let deck = |deck: &[usize]| -> String {
+                deck.iter().map(|card| format!("{}\n", card)).collect()
+            };
+            return format!(
+                "Player 1:\n{}\nPlayer 2:\n{}",
+                deck(&cards[..size]),
+                deck(&cards[size..])
+            );
+        }
+    }
+}
 
-        let part1 = part1(&deck1, &deck2);
-        let part2 = part2(&deck1, &deck2);
+impl Reference for Solver {
+    /// Play both games with plain vectors, remembering every earlier round of a game in a list.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let (first, second) = input.split_once("\n\n").unwrap();
+        let deck = |player: &str| -> Vec<usize> {
+            player
+                .lines()
+                .skip(1)
+                .map(|card| card.parse().unwrap())
+                .collect()
+        };
+        let (first, second) = (deck(first), deck(second));
+        let score = |deck: &[usize]| -> usize {
+            deck.iter()
+                .rev()
+                .enumerate()
+                .map(|(i, card)| (i + 1) * card)
+                .sum()
+        };
+
+        let (mut one, mut two) = (first.clone(), second.clone());
+        while !one.is_empty() && !two.is_empty() {
+            let (a, b) = (one.remove(0), two.remove(0));
+            if a > b {
+                one.extend(&[a, b]);
+            } else {
+                two.extend(&[b, a]);
+            }
+        }
+        let part1 = score(&one) + score(&two);
+
+        // Whether player one wins, leaving the decks as they end up.
+        fn recursive(one: &mut Vec<usize>, two: &mut Vec<usize>) -> bool {
+            let mut seen: Vec<(Vec<usize>, Vec<usize>)> = Vec::new();
+            while !one.is_empty() && !two.is_empty() {
+                let round = (one.clone(), two.clone());
+                if seen.contains(&round) {
+                    return true;
+                }
+                seen.push(round);
+
+                let (a, b) = (one.remove(0), two.remove(0));
+                let one_wins = if one.len() >= a && two.len() >= b {
+                    recursive(&mut one[..a].to_vec(), &mut two[..b].to_vec())
+                } else {
+                    a > b
+                };
+                if one_wins {
+                    one.extend(&[a, b]);
+                } else {
+                    two.extend(&[b, a]);
+                }
+            }
+            two.is_empty()
+        }
+        let (mut one, mut two) = (first, second);
+        recursive(&mut one, &mut two);
+        let part2 = score(&one) + score(&two);
 
         (part1, part2).into()
     }
 }
 
-fn parse_input(input: &str) -> (Deck, Deck) {
+/// Rounds of plain Combat after which a game is taken to go on forever, since there's no rule
+/// against repeats to stop it.
+const MAX_ROUNDS: usize = 100_000;
+
+/// Parse both decks, whose cards must all be different so that every round has a winner.
+fn parse_input(input: &str) -> anyhow::Result<(Deck, Deck)> {
     let mut iter = input.split("\n\n");
-    let deck1 = iter
-        .next()
-        .unwrap()
-        .lines()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    let deck2 = iter
-        .next()
-        .unwrap()
-        .lines()
-        .filter_map(|s| s.parse().ok())
-        .collect();
-    (deck1, deck2)
+    let mut deck = |player| -> anyhow::Result<Deck> {
+        iter.next()
+            .with_context(|| format!("Missing deck for player {}", player))?
+            .lines()
+            .skip(1)
+            .map(|s| s.parse().with_context(|| format!("Invalid card: {}", s)))
+            .collect()
+    };
+    let (deck1, deck2) = (deck(1)?, deck(2)?);
+
+    let mut seen = FastSet::default();
+    if let Some(card) = deck1
+        .0
+        .iter()
+        .chain(&deck2.0)
+        .find(|&&card| !seen.insert(card))
+    {
+        bail!("Card {} is repeated", card);
+    }
+    Ok((deck1, deck2))
 }
 
-fn part1(deck1: &Deck, deck2: &Deck) -> usize {
+fn part1(deck1: &Deck, deck2: &Deck) -> anyhow::Result<usize> {
     let (mut deck1, mut deck2) = (deck1.clone(), deck2.clone());
+    let mut rounds = 0;
     while !deck1.is_empty() && !deck2.is_empty() {
+        ensure!(rounds < MAX_ROUNDS, "The game of Combat never ends");
         deck1.combat_round(&mut deck2);
+        rounds += 1;
     }
-    deck1.score() + deck2.score()
+    deck1
+        .score()
+        .or(deck2.score())
+        .context("The score overflows")
 }
 
-fn part2(deck1: &Deck, deck2: &Deck) -> usize {
+fn part2(deck1: &Deck, deck2: &Deck) -> anyhow::Result<usize> {
     let (mut deck1, mut deck2) = (deck1.clone(), deck2.clone());
-    deck1.recursive_combat(&mut deck2);
-    deck1.score() + deck2.score()
+    deck1.recursive_combat(&mut deck2, &mut Progress::unbounded("Day 22", "rounds"));
+    deck1
+        .score()
+        .or(deck2.score())
+        .context("The score overflows")
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
-struct Deck(VecDeque<usize>);
+pub(crate) struct Deck(VecDeque<usize>);
 
 impl Deck {
     fn combat_round(&mut self, other: &mut Deck) {
@@ -89,11 +240,12 @@ impl Deck {
         winner.push_back(losing_card);
     }
 
-    fn recursive_combat(&mut self, other: &mut Deck) -> Winner {
+    /// Play a game of Recursive Combat, counting each round played, in this game or any
+    /// sub-game, towards `progress`.
+    fn recursive_combat(&mut self, other: &mut Deck, progress: &mut Progress) -> Winner {
         use std::cmp::Ordering;
-        use std::collections::HashSet;
 
-        let mut previous_rounds: HashSet<(Vec<usize>, Vec<usize>)> = HashSet::new();
+        let mut previous_rounds: FastSet<(Vec<usize>, Vec<usize>)> = FastSet::default();
 
         while !self.is_empty() && !other.is_empty() {
             let deck_snapshot = (
@@ -105,6 +257,8 @@ impl Deck {
                 return Winner::Player1;
             }
 
+            progress.tick();
+
             let self_card = self.pop_front().unwrap();
             let other_card = other.pop_front().unwrap();
 
@@ -113,7 +267,7 @@ impl Deck {
             // of Recursive Combat.
             let winner = if self.len() >= self_card && other.len() >= other_card {
                 self.subdeck(self_card)
-                    .recursive_combat(&mut other.subdeck(other_card))
+                    .recursive_combat(&mut other.subdeck(other_card), progress)
             } else {
                 match self_card.cmp(&other_card) {
                     Ordering::Less => Winner::Player2,
@@ -148,16 +302,19 @@ impl Deck {
         }
     }
 
-    /// Tally the winner's score.
-    fn score(&self) -> usize {
+    /// Tally the winner's score, or `None` for the loser's empty deck or if it overflows.
+    fn score(&self) -> Option<usize> {
         let len = self.0.len();
+        if len == 0 {
+            return None;
+        }
 
-        let mut score = 0;
+        let mut score = 0_usize;
         for (idx, card) in self.0.iter().enumerate() {
-            score += (len - idx) * card;
+            score = score.checked_add((len - idx).checked_mul(*card)?)?;
         }
 
-        score
+        Some(score)
     }
 
     /// Create a subdeck of `num` cards from original deck.
@@ -182,15 +339,6 @@ impl Deck {
     }
 }
 
-impl From<&str> for Deck {
-    fn from(deck: &str) -> Self {
-        deck.lines()
-            .skip(1)
-            .filter_map(|s| s.parse::<usize>().ok())
-            .collect()
-    }
-}
-
 impl FromIterator<usize> for Deck {
     fn from_iter<T: IntoIterator<Item = usize>>(iter: T) -> Self {
         let mut deck = Deck::default();
@@ -210,73 +358,28 @@ enum Winner {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-Player 1:
-9
-2
-6
-3
-1
-
-Player 2:
-5
-8
-4
-7
-10";
-    const INFINITE: &str = "\
-Player 1:
-43
-19
-
-Player 2:
-2
-29
-14";
-
     #[test]
     fn test_parse_input() {
         let expected = (
             Deck(VecDeque::from(vec![9, 2, 6, 3, 1])),
             Deck(VecDeque::from(vec![5, 8, 4, 7, 10])),
         );
-        let actual = parse_input(INPUT);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part1() {
-        let (deck1, deck2) = parse_input(INPUT);
-        let expected = 306;
-        let actual = part1(&deck1, &deck2);
+        let actual = parse_input(&example_input(22, "example")).unwrap();
         assert_eq!(actual, expected)
     }
 
-    #[test]
-    fn example_part2() {
-        let (deck1, deck2) = parse_input(INPUT);
-        let expected = 291;
-        let actual = part2(&deck1, &deck2);
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn test_infinite() {
-        let (deck1, deck2) = parse_input(INPUT);
-        let _ = part2(&deck1, &deck2);
-    }
-
     #[test]
     #[ignore = "test takes a long time"]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day22.txt");
+        let input = require_input!(22);
 
         let expected: Solution = (34_005, 32_731).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
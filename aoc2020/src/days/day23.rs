@@ -1,3 +1,11 @@
+use std::collections::HashMap;
+
+use anyhow::ensure;
+
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::progress::Progress;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -10,30 +18,147 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = Cups;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         23
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let mut cups = Cups::from(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<Cups> {
+        check_labels(input)?;
+        Ok(Cups::from(input))
+    }
+
+    fn part1(&self, cups: &Cups) -> anyhow::Result<usize> {
+        let mut cups = cups.clone();
         for _ in 0..100 {
             cups.move_cups();
         }
-        let part1 = cups.format_part1();
+        Ok(cups.format_part1())
+    }
 
-        let mut cups = Cups::from(input).extend(1_000_000);
-        for _ in 0..10_000_000 {
+    fn part2(&self, cups: &Cups) -> anyhow::Result<usize> {
+        const MOVES: u64 = 10_000_000;
+
+        let mut cups = cups.clone().extend(1_000_000);
+        let mut progress = Progress::new("Day 23", "moves", MOVES);
+        for moves in 0..MOVES {
+            progress.update(moves);
             cups.move_cups();
         }
-        let part2 = cups.format_part2();
+        Ok(cups.format_part2())
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
+    }
+}
+
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        9
+    }
+
+    /// The cups labeled 1 to 9 in a random order. The puzzle always uses nine cups, so `size` is
+    /// ignored.
+    fn generate(&self, rng: &mut Rng, _size: usize) -> String {
+        let mut cups: Vec<char> = ('1'..='9').collect();
+        rng.shuffle(&mut cups);
+        cups.into_iter().collect::<String>() + "\n"
+    }
+}
+
+impl Reference for Solver {
+    /// Move the cups around a vector for part 1. Part 2 is far too big for that, so it keeps each
+    /// cup's clockwise neighbour in a map.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let labels: Vec<usize> = input
+            .trim_end()
+            .chars()
+            .map(|c| c.to_digit(10).unwrap() as usize)
+            .collect();
+
+        let mut cups = labels.clone();
+        for _ in 0..100 {
+            let picked: Vec<usize> = cups.drain(1..4).collect();
+            let mut destination = cups[0];
+            loop {
+                destination = if destination == 1 { 9 } else { destination - 1 };
+                if !picked.contains(&destination) {
+                    break;
+                }
+            }
+            let at = cups.iter().position(|&cup| cup == destination).unwrap() + 1;
+            cups.splice(at..at, picked);
+            cups.rotate_left(1);
+        }
+        let one = cups.iter().position(|&cup| cup == 1).unwrap();
+        cups.rotate_left(one);
+        let part1: usize = cups[1..].iter().fold(0, |number, cup| number * 10 + cup);
+
+        const CUPS: usize = 1_000_000;
+        let mut next: HashMap<usize, usize> = HashMap::new();
+        let all = labels.iter().copied().chain(labels.len() + 1..=CUPS);
+        for (cup, after) in all.clone().zip(all.skip(1)) {
+            next.insert(cup, after);
+        }
+        next.insert(CUPS, labels[0]);
+        let mut current = labels[0];
+        for _ in 0..10_000_000 {
+            let first = next[&current];
+            let second = next[&first];
+            let third = next[&second];
+            let mut destination = current;
+            loop {
+                destination = if destination == 1 {
+                    CUPS
+                } else {
+                    destination - 1
+                };
+                if ![first, second, third].contains(&destination) {
+                    break;
+                }
+            }
+            next.insert(current, next[&third]);
+            next.insert(third, next[&destination]);
+            next.insert(destination, first);
+            current = next[&current];
+        }
+        let part2 = next[&1] * next[&next[&1]];
 
         (part1, part2).into()
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct Cups {
+/// Make sure the cups are labelled 1 to 9, once each, which both parts rely on.
+fn check_labels(input: &str) -> anyhow::Result<()> {
+    let mut labels: Vec<char> = input.trim_end().chars().collect();
+    labels.sort_unstable();
+    ensure!(
+        labels.iter().copied().eq('1'..='9'),
+        "The cups must be labelled 1 to 9, once each"
+    );
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Cups {
     /// Each index corresponds to the 'cup number'. The value stored corresponds to the index of
     /// the next cup in the sequence.
     order: Vec<usize>,
@@ -109,7 +234,7 @@ impl From<&str> for Cups {
     fn from(s: &str) -> Self {
         let raw: Vec<_> = s
             .chars()
-            .filter(|c| c.is_digit(10))
+            .filter(|c| c.is_ascii_digit())
             .map(|c| c.to_digit(10).unwrap() as usize)
             .collect();
         let mut iter = raw.iter().peekable();
@@ -136,10 +261,13 @@ impl From<&str> for Cups {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "389125467";
+    fn example() -> Cups {
+        Cups::from(example_input(23, "example").trim())
+    }
 
     #[test]
     fn test_parse_input() {
@@ -147,27 +275,27 @@ mod test {
             order: vec![0, 2, 5, 8, 6, 4, 7, 3, 9, 1],
             current: 3,
         };
-        let actual = Cups::from(INPUT);
+        let actual = example();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_format_part1() {
         let expected = 25_467_389;
-        let actual = Cups::from(INPUT).format_part1();
+        let actual = example().format_part1();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_format_part2() {
         let expected = 2 * 5;
-        let actual = Cups::from(INPUT).format_part2();
+        let actual = example().format_part2();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn test_one_move() {
-        let mut cups = Cups::from(INPUT);
+        let mut cups = example();
         let expected = Cups {
             order: vec![0, 5, 8, 2, 6, 4, 7, 3, 9, 1],
             current: 2,
@@ -180,39 +308,14 @@ mod test {
     }
 
     #[test]
-    fn example_part1() {
-        // after 10 moves
+    fn test_ten_moves() {
         let expected = 92_658_374;
-        let mut cups = Cups::from(INPUT);
+        let mut cups = example();
         for _ in 0..10 {
             cups.move_cups();
         }
         let actual = cups.format_part1();
         assert_eq!(actual, expected, "Incorrect solution after 10 moves.");
-
-        // after 100 moves
-        let expected = 67_384_529;
-        let mut cups = Cups::from(INPUT);
-        for _ in 0..100 {
-            cups.move_cups();
-        }
-        let actual = cups.format_part1();
-        assert_eq!(actual, expected, "Incorrect solution after 100 moves.");
-    }
-
-    #[test]
-    fn example_part2() {
-        let expected = 149_245_887_792;
-        let mut cups = Cups::from(INPUT).extend(1_000_000);
-        for _ in 0..10_000_000 {
-            cups.move_cups();
-        }
-        let mut iter = cups.next_after(1).take(2);
-        assert_eq!(iter.next().unwrap(), 934001);
-        assert_eq!(iter.next().unwrap(), 159792);
-
-        let actual = cups.format_part2();
-        assert_eq!(actual, expected)
     }
 
     #[test]
@@ -223,17 +326,17 @@ mod test {
             ],
             current: 3,
         };
-        let actual = Cups::from(INPUT).extend(20);
+        let actual = example().extend(20);
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day23.txt");
+        let input = require_input!(23);
 
         let expected: Solution = (54_896_723_usize, 146_304_752_384_usize).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
@@ -1,12 +1,19 @@
-use std::collections::{hash_set, HashSet};
+use std::collections::hash_set;
+use std::convert::TryFrom;
+use std::io;
 
+use anyhow::bail;
+
+use crate::generate::{Generate, Rng};
+use crate::geometry::{HexDirection, Point2};
+use crate::hash::{FastMap, FastSet};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
+use crate::visualize::{FrameWriter, Svg, Visualize};
 
 pub(crate) struct Solver(());
 
-/// The coordinated used to determine the neighbors of a given 'Hex'
-const NEIGHBORS: [(isize, isize); 6] = [(1, 0), (0, 1), (-1, 1), (-1, 0), (0, -1), (1, -1)];
-
 impl Solver {
     pub fn new() -> Self {
         let solver = Self(());
@@ -15,33 +22,148 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = HexGrid;
+    type Part1 = usize;
+    type Part2 = usize;
+
     fn day(&self) -> u8 {
         24
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let hex_grid = HexGrid::from(input);
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("ensw"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<HexGrid> {
+        HexGrid::try_from(input)
+    }
+
+    fn part1(&self, hex_grid: &HexGrid) -> anyhow::Result<usize> {
+        Ok(hex_grid.len())
+    }
+
+    fn part2(&self, hex_grid: &HexGrid) -> anyhow::Result<usize> {
+        Ok(hex_grid.clone().days(100))
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Some(self)
+    }
 
-        let part1 = hex_grid.len();
-        let part2 = hex_grid.days(100);
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
 
-        (part1, part2).into()
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-enum Direction {
-    East,
-    Southeast,
-    Southwest,
-    West,
-    Northwest,
-    Northeast,
+impl Visualize for Solver {
+    /// The black tiles of the floor on the initial day and after every one of the 100 days.
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()> {
+        let hex_grid = HexGrid::try_from(input)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.to_string()))?;
+        let mut floors = Vec::new();
+        hex_grid.days_with(100, |grid| floors.push(grid.frame()));
+        frames.write_all("floor", floors)
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Default)]
-struct HexGrid(HashSet<Hex>);
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        400
+    }
+
+    /// `size` paths of 10 to 25 steps, ending near enough to each other that some tiles get
+    /// flipped more than once.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        const DIRECTIONS: [&str; 6] = ["e", "se", "sw", "w", "nw", "ne"];
+
+        (0..size)
+            .map(|_| {
+                let mut path: String = (0..rng.range(10..26))
+                    .map(|_| *rng.choose(&DIRECTIONS))
+                    .collect();
+                path.push('\n');
+                path
+            })
+            .collect()
+    }
+}
+
+impl Reference for Solver {
+    /// Flip tiles on a grid in doubled coordinates, where east is two steps along `x` and the
+    /// diagonals are one step along each axis. Every day looks at every tile on the grid.
+    fn solve_reference(&self, input: &str) -> Solution {
+        const DAYS: usize = 100;
+        let paths: Vec<Vec<(isize, isize)>> = input
+            .lines()
+            .map(|line| {
+                let mut steps = Vec::new();
+                let mut rest = line;
+                while !rest.is_empty() {
+                    let (step, len) = match &rest[..rest.len().min(2)] {
+                        "ne" => ((1, 1), 2),
+                        "nw" => ((-1, 1), 2),
+                        "se" => ((1, -1), 2),
+                        "sw" => ((-1, -1), 2),
+                        _ if rest.starts_with('e') => ((2, 0), 1),
+                        _ if rest.starts_with('w') => ((-2, 0), 1),
+                        _ => panic!("Invalid path: {}", line),
+                    };
+                    steps.push(step);
+                    rest = &rest[len..];
+                }
+                steps
+            })
+            .collect();
+
+        // Room for the longest path plus a tile of growth per day in every direction.
+        let reach = paths.iter().map(Vec::len).max().unwrap_or(0) as isize + DAYS as isize + 1;
+        let (width, height) = (4 * reach + 1, 2 * reach + 1);
+        let mut floor = vec![vec![false; width as usize]; height as usize];
+        for path in &paths {
+            let (mut x, mut y) = (2 * reach, reach);
+            for (dx, dy) in path {
+                x += dx;
+                y += dy;
+            }
+            floor[y as usize][x as usize] ^= true;
+        }
+        let black = |floor: &[Vec<bool>]| floor.iter().flatten().filter(|&&tile| tile).count();
+        let part1 = black(&floor);
+
+        for _ in 0..DAYS {
+            let mut next = floor.clone();
+            for y in 1..height - 1 {
+                for x in 2..width - 2 {
+                    // Only squares an even number of steps from the centre are tiles.
+                    if (x + y + reach) % 2 != 0 {
+                        continue;
+                    }
+                    let adjacent = [(2, 0), (-2, 0), (1, 1), (-1, 1), (1, -1), (-1, -1)]
+                        .iter()
+                        .filter(|(dx, dy)| floor[(y + dy) as usize][(x + dx) as usize])
+                        .count();
+                    let tile = &mut next[y as usize][x as usize];
+                    *tile = adjacent == 2 || (*tile && adjacent == 1);
+                }
+            }
+            floor = next;
+        }
+
+        (part1, black(&floor)).into()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub(crate) struct HexGrid(FastSet<Hex>);
 
 impl HexGrid {
     fn insert(&mut self, hex: Hex) {
@@ -54,33 +176,37 @@ impl HexGrid {
         self.0.len()
     }
 
-    fn iter(&self) -> hash_set::Iter<Hex> {
+    fn iter(&self) -> hash_set::Iter<'_, Hex> {
         self.0.iter()
     }
 
     /// Simulates flipping tiles each day according to the following rules:
     ///
     /// - Any *black* tile with *zero* or *more than 2* black tiles immediately adjacent to it is
-    /// flipped to *white*.
+    ///   flipped to *white*.
     ///
     /// - Any *white* tile with *exactly 2* black tiles immediately adjacent to it is flipped to
-    /// *black*.
+    ///   *black*.
     ///
     /// The rules are applied *simultaneously* to every tile; put another way, it is first
     /// determined which tiles need to be flipped, then they are all flipped at the same time.
     ///
     /// Returns the number of black tiles.
-    fn days(mut self, days: usize) -> usize {
-        use std::collections::HashMap;
+    fn days(self, days: usize) -> usize {
+        self.days_with(days, |_| {})
+    }
 
-        let mut neighbors = HashMap::new();
+    /// Same as [`HexGrid::days`], calling `on_day` with the initial floor and after every day.
+    fn days_with(mut self, days: usize, mut on_day: impl FnMut(&Self)) -> usize {
+        let mut neighbors = FastMap::default();
+        on_day(&self);
         for _ in 0..days {
             neighbors.clear();
-            for Hex { index: (q, r) } in self.iter() {
-                for (dq, dr) in &NEIGHBORS {
+            for hex in self.iter() {
+                for direction in &HexDirection::ALL {
                     *neighbors
                         .entry(Hex {
-                            index: (q + dq, r + dr),
+                            index: hex.index + direction.offset(),
                         })
                         .or_insert(0) += 1;
                 }
@@ -91,36 +217,67 @@ impl HexGrid {
                 .filter(|(t, &n)| n == 2 || (n == 1 && self.0.contains(t)))
                 .map(|(&t, _)| t)
                 .collect();
+            on_day(&self);
         }
 
         self.len()
     }
+
+    /// Draw every black tile as a pointy-topped hexagon.
+    fn frame(&self) -> Svg {
+        let sqrt_3 = 3_f64.sqrt();
+        let center = |hex: &Hex| {
+            let (q, r) = (hex.index.x() as f64, hex.index.y() as f64);
+            (sqrt_3 * (q + r / 2.0), 1.5 * r)
+        };
+
+        let mut svg = Svg::fitting(self.iter().map(center), 1.0);
+        for hex in self.iter() {
+            let (x, y) = center(hex);
+            let corners: Vec<_> = (0..6)
+                .map(|i| {
+                    let angle = (60.0 * i as f64 + 30.0).to_radians();
+                    (x + angle.cos(), y + angle.sin())
+                })
+                .collect();
+            svg.polygon(&corners, "black");
+        }
+        svg
+    }
 }
 
-impl From<&str> for HexGrid {
-    fn from(s: &str) -> Self {
+impl TryFrom<&str> for HexGrid {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> anyhow::Result<Self> {
         s.lines()
             .map(|line| {
                 let mut iter = line.chars();
                 let mut directions: Vec<_> = Vec::new();
                 while let Some(c) = iter.next() {
                     match c {
-                        'n' => match iter.next().unwrap() {
-                            'w' => directions.push(Direction::Northwest),
-                            'e' => directions.push(Direction::Northeast),
-                            c => unreachable!("Got invalid direction: n{}", c),
+                        'n' => match iter.next() {
+                            Some('w') => directions.push(HexDirection::NW),
+                            Some('e') => directions.push(HexDirection::NE),
+                            c => bail!(
+                                "Got invalid direction: n{}",
+                                c.map_or(String::new(), String::from)
+                            ),
                         },
-                        's' => match iter.next().unwrap() {
-                            'w' => directions.push(Direction::Southwest),
-                            'e' => directions.push(Direction::Southeast),
-                            c => unreachable!("Got invalid direction: s{}", c),
+                        's' => match iter.next() {
+                            Some('w') => directions.push(HexDirection::SW),
+                            Some('e') => directions.push(HexDirection::SE),
+                            c => bail!(
+                                "Got invalid direction: s{}",
+                                c.map_or(String::new(), String::from)
+                            ),
                         },
-                        'e' => directions.push(Direction::East),
-                        'w' => directions.push(Direction::West),
-                        c => unreachable!("Got invalid direction: {}", c),
+                        'e' => directions.push(HexDirection::E),
+                        'w' => directions.push(HexDirection::W),
+                        c => bail!("Got invalid direction: {}", c),
                     };
                 }
-                Hex::from(directions)
+                Ok(Hex::from(directions))
             })
             .collect()
     }
@@ -140,7 +297,7 @@ impl std::iter::FromIterator<Hex> for HexGrid {
 
 impl IntoIterator for HexGrid {
     type Item = Hex;
-    type IntoIter = <HashSet<<Self as IntoIterator>::Item> as IntoIterator>::IntoIter;
+    type IntoIter = <FastSet<<Self as IntoIterator>::Item> as IntoIterator>::IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
         self.0.into_iter()
@@ -148,28 +305,17 @@ impl IntoIterator for HexGrid {
 }
 
 #[derive(Debug, PartialEq, Eq, Default, Hash, Clone, Copy)]
-struct Hex {
-    index: (isize, isize),
+pub(crate) struct Hex {
+    index: Point2,
 }
 
-impl From<Vec<Direction>> for Hex {
-    fn from(directions: Vec<Direction>) -> Self {
-        let (mut q, mut r) = (0, 0);
-
-        for direction in directions {
-            let (dq, dr) = match direction {
-                Direction::East => NEIGHBORS[0],
-                Direction::Southeast => NEIGHBORS[1],
-                Direction::Southwest => NEIGHBORS[2],
-                Direction::West => NEIGHBORS[3],
-                Direction::Northwest => NEIGHBORS[4],
-                Direction::Northeast => NEIGHBORS[5],
-            };
-            q += dq;
-            r += dr;
+impl From<Vec<HexDirection>> for Hex {
+    fn from(directions: Vec<HexDirection>) -> Self {
+        Self {
+            index: directions.iter().fold(Point2::ORIGIN, |index, direction| {
+                index + direction.offset()
+            }),
         }
-
-        Self { index: (q, r) }
     }
 }
 
@@ -177,41 +323,24 @@ impl From<Vec<Direction>> for Hex {
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
-    const INPUT: &str = "\
-sesenwnenenewseeswwswswwnenewsewsw
-neeenesenwnwwswnenewnwwsewnenwseswesw
-seswneswswsenwwnwse
-nwnwneseeswswnenewneswwnewseswneseene
-swweswneswnenwsewnwneneseenw
-eesenwseswswnenwswnwnwsewwnwsene
-sewnenenenesenwsewnenwwwse
-wenwwweseeeweswwwnwwe
-wsweesenenewnwwnwsenewsenwwsesesenwne
-neeswseenwwswnwswswnw
-nenwswwsewswnenenewsenwsenwnesesenew
-enewnwewneswsewnwswenweswnenwsenwsw
-sweneswneswneneenwnewenewwneswswnese
-swwesenesewenwneswnwwneseswwne
-enesenwswwswneneswsenwnewswseenwsese
-wnwnesenesenenwwnenwsewesewsesesew
-nenewswnwewswnenesenwnesewesw
-eneswnwswnwsenenwnwnwwseeswneewsenese
-neswnwewnwnwseenwseesewsenwsweewe
-wseweeenwnesenwwwswnew";
-
     #[test]
     fn test_parse_input() {
         const INPUT: &str = "nwwswee\nesew";
         let expected = {
-            let mut ex = HashSet::default();
-            ex.insert(Hex { index: (0, 1) });
-            ex.insert(Hex { index: (0, 0) });
+            let mut ex = FastSet::default();
+            ex.insert(Hex {
+                index: Point2::new(0, 1),
+            });
+            ex.insert(Hex {
+                index: Point2::new(0, 0),
+            });
             HexGrid(ex)
         };
-        let actual = HexGrid::from(INPUT);
+        let actual = HexGrid::try_from(INPUT).unwrap();
         assert_eq!(actual, expected)
     }
 
@@ -220,42 +349,36 @@ wseweeenwnesenwwwswnew";
         let expected = HexGrid::default();
         let actual = {
             let mut act = HexGrid::default();
-            act.insert(Hex { index: (1, 1) });
-            act.insert(Hex { index: (1, 1) });
+            act.insert(Hex {
+                index: Point2::new(1, 1),
+            });
+            act.insert(Hex {
+                index: Point2::new(1, 1),
+            });
             act
         };
         assert_eq!(actual, expected)
     }
 
     #[test]
-    fn example_part1() {
-        let expected = 10;
-        let actual = HexGrid::from(INPUT).len();
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn example_part2() {
+    fn test_days() {
+        let input = example_input(24, "example");
         let expected = 15;
-        let actual = HexGrid::from(INPUT).days(1);
+        let actual = HexGrid::try_from(input.as_str()).unwrap().days(1);
         assert_eq!(actual, expected);
 
         let expected = 12;
-        let actual = HexGrid::from(INPUT).days(2);
-        assert_eq!(actual, expected);
-
-        let expected = 2208;
-        let actual = HexGrid::from(INPUT).days(100);
+        let actual = HexGrid::try_from(input.as_str()).unwrap().days(2);
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day24.txt");
+        let input = require_input!(24);
 
         let expected: Solution = (538, 4259).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
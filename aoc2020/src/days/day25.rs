@@ -1,3 +1,8 @@
+use anyhow::{ensure, Context};
+
+use crate::generate::{Generate, Rng};
+use crate::lint::Shape;
+use crate::reference::Reference;
 use crate::solution::Solution;
 
 pub(crate) struct Solver(());
@@ -13,42 +18,125 @@ impl Solver {
     }
 }
 
-impl crate::Solver for Solver {
+impl crate::Day for Solver {
+    type Parsed<'a> = (usize, usize);
+    type Part1 = usize;
+    /// Day 25 has no second puzzle.
+    type Part2 = String;
+
     fn day(&self) -> u8 {
         25
     }
 
-    fn solve(&self, input: &str) -> Solution {
-        let encryption_key = part1(input);
-        (encryption_key, String::new()).into()
+    fn shape(&self) -> Shape {
+        Shape {
+            blocks: Some(1),
+            alphabet: Some("0123456789"),
+            ..Shape::default()
+        }
+    }
+
+    fn parse(&self, input: &str) -> anyhow::Result<(usize, usize)> {
+        parse_input(input)
+    }
+
+    fn part1(&self, &public_keys: &(usize, usize)) -> anyhow::Result<usize> {
+        Ok(part1(public_keys))
+    }
+
+    fn part2(&self, _: &(usize, usize)) -> anyhow::Result<String> {
+        Ok(String::new())
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Some(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Some(self)
     }
 }
 
-fn part1(input: &str) -> usize {
-    let (pk1, pk2) = parse_input(input);
+impl Generate for Solver {
+    fn default_size(&self) -> usize {
+        10_000_000
+    }
+
+    /// Public keys for a card and door whose loop sizes are below `size`.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String {
+        let key =
+            |loop_size: usize| (0..loop_size).fold(1, |key, _| key * SUBJECT_NUMBER % DIVISOR);
+        let card = key(rng.range(1..size.max(2)));
+        let door = loop {
+            let door = key(rng.range(1..size.max(2)));
+            if door != card {
+                break door;
+            }
+        };
+        format!("{}\n{}\n", card, door)
+    }
+}
+
+impl Reference for Solver {
+    /// Find the card's loop size by running the handshake until it produces the card's key, then
+    /// transform the door's key that many times.
+    fn solve_reference(&self, input: &str) -> Solution {
+        let keys: Vec<usize> = input.lines().map(|key| key.parse().unwrap()).collect();
+        let (card, door) = (keys[0], keys[1]);
+
+        let mut value = 1;
+        let mut card_loop = 0;
+        while value != card {
+            value = value * SUBJECT_NUMBER % DIVISOR;
+            card_loop += 1;
+        }
+
+        let mut key = 1;
+        for _ in 0..card_loop {
+            key = key * door % DIVISOR;
+        }
+
+        (key, String::new()).into()
+    }
+}
+
+fn part1((pk1, pk2): (usize, usize)) -> usize {
     let (mut val, mut loop_size) = (1, 0);
     while val != pk1 && val != pk2 {
-        val = val * 7 % DIVISOR;
+        val = val * SUBJECT_NUMBER % DIVISOR;
         loop_size += 1;
     }
     let k1 = if val == pk1 { pk2 } else { pk1 };
     (0..loop_size).fold(1, |x, _| x * k1 % DIVISOR)
 }
 
-fn parse_input(input: &str) -> (usize, usize) {
-    let keys: Vec<usize> = input
+/// Parse the two public keys. The subject number generates every value from 1 up to the divisor,
+/// so keys in that range always have a loop size.
+fn parse_input(input: &str) -> anyhow::Result<(usize, usize)> {
+    let keys = input
         .trim()
         .lines()
-        .filter_map(|l| l.trim().parse().ok())
-        .collect();
-    assert!(keys.len() == 2);
-    (keys[0], keys[1])
+        .map(|l| {
+            l.trim()
+                .parse()
+                .ok()
+                .filter(|key| (1..DIVISOR).contains(key))
+                .with_context(|| format!("Invalid public key: {}", l))
+        })
+        .collect::<anyhow::Result<Vec<usize>>>()?;
+    ensure!(
+        keys.len() == 2,
+        "Expected 2 public keys, found {}",
+        keys.len()
+    );
+    Ok((keys[0], keys[1]))
 }
 
 #[cfg(test)]
 mod test {
     #![allow(unused_imports)]
     use super::*;
+    use crate::examples::example_input;
     use crate::solution::Solution;
     use crate::Solver;
 
@@ -65,25 +153,17 @@ mod test {
     #[test]
     fn test_parse_input() {
         let expected = (5764801, 17807724);
-        let actual = parse_input("5764801\n17807724");
-        assert_eq!(actual, expected)
-    }
-
-    #[test]
-    fn test_get_encryption_key() {
-        let input = "5764801\n17807724";
-        let actual = part1(input);
-        let expected = 14897079;
+        let actual = parse_input(&example_input(25, "example")).unwrap();
         assert_eq!(actual, expected)
     }
 
     #[test]
     fn verify() {
         let solver = super::Solver::new();
-        let input = include_str!("../../input/day25.txt");
+        let input = require_input!(25);
 
         let expected: Solution = (2679568, String::new()).into();
-        let actual = solver.solve(input);
+        let actual = solver.solve(&input).unwrap();
 
         assert_eq!(actual, expected)
     }
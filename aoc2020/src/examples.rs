@@ -0,0 +1,233 @@
+//! Runs every example under `examples/dayNN/*.txt` against that day's solver.
+//!
+//! An example file starts with a header of `key: value` lines, ended by a line holding only
+//! `---`, followed by the puzzle input:
+//!
+//! ```text
+//! part1: 514579
+//! part2: 241861950
+//! ---
+//! 1721
+//! 979
+//! ```
+//!
+//! `part1` and `part2` are the expected answers; leave either out to only solve the other part,
+//! for examples that aren't valid input to both, but not both. Any other key is handed to
+//! [`crate::Solver::solve_part_with`] as a parameter, and must be one of the day's
+//! [`param_keys`](crate::Solver::param_keys). Every implementation of the day is checked.
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use crate::impls::Registry;
+use crate::params::Params;
+use crate::Solver;
+
+const EXAMPLE_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples");
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Example {
+    part1: Option<String>,
+    part2: Option<String>,
+    params: Params,
+    input: String,
+}
+
+impl Example {
+    fn parse(contents: &str) -> Result<Self, String> {
+        let contents = contents.replace('\r', "");
+        let mut lines = contents.split_inclusive('\n');
+        let mut example = Self::default();
+
+        loop {
+            let line = lines
+                .next()
+                .ok_or("Missing '---' line after the header")?
+                .trim_end();
+            if line == "---" {
+                break;
+            }
+
+            let (key, value) = line
+                .split_once(':')
+                .ok_or_else(|| format!("Invalid header line: '{}'", line))?;
+            let value = value.trim().to_owned();
+            match key.trim() {
+                "part1" => example.part1 = Some(value),
+                "part2" => example.part2 = Some(value),
+                key => example.params.insert(key, value),
+            }
+        }
+
+        if example.part1.is_none() && example.part2.is_none() {
+            return Err("Expects an answer to neither part".to_owned());
+        }
+
+        example.input = lines.collect();
+        if !example.input.ends_with('\n') {
+            example.input.push('\n');
+        }
+        Ok(example)
+    }
+
+    fn check(&self, solver: &dyn Solver) -> Result<(), String> {
+        if let Some(key) = self
+            .params
+            .keys()
+            .find(|key| !solver.param_keys().contains(key))
+        {
+            return Err(format!("Unknown parameter '{}'", key));
+        }
+
+        let mut errors = Vec::new();
+        for (part, expected) in &[(1, &self.part1), (2, &self.part2)] {
+            if let Some(expected) = expected {
+                let actual = panic::catch_unwind(AssertUnwindSafe(|| {
+                    solver.solve_part_with(&self.input, &self.params, *part)
+                }))
+                .map_err(|_| "Solver panicked".to_owned())?
+                .map_err(|error| format!("Solver failed on part {}: {:#}", part, error))?;
+
+                if *expected != actual {
+                    errors.push(format!(
+                        "part {}: expected {}, got {}",
+                        part, expected, actual
+                    ));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+}
+
+/// Every example file, along with the day its directory is named after.
+fn discover(directory: &Path) -> Vec<(u8, PathBuf)> {
+    let mut examples = Vec::new();
+
+    for entry in fs::read_dir(directory).into_iter().flatten().flatten() {
+        let day = match entry
+            .file_name()
+            .to_str()
+            .and_then(|name| name.strip_prefix("day"))
+            .and_then(|day| day.parse().ok())
+        {
+            Some(day) => day,
+            None => continue,
+        };
+
+        for file in fs::read_dir(entry.path()).into_iter().flatten().flatten() {
+            let path = file.path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                examples.push((day, path));
+            }
+        }
+    }
+
+    examples.sort();
+    examples
+}
+
+fn run(registry: &Registry, day: u8, path: &Path) -> Result<(), String> {
+    let solvers = registry.day(day);
+    if solvers.is_empty() {
+        return Err(format!("No solver for day {}", day));
+    }
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let example = Example::parse(&contents)?;
+
+    let errors: Vec<String> = solvers
+        .iter()
+        .filter_map(|solver| {
+            example
+                .check(*solver)
+                .err()
+                .map(|e| format!("{}: {}", solver.name(), e))
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+/// The input of `examples/dayNN/<name>.txt`, for tests that look inside a parsed example rather
+/// than only at its answers.
+pub(crate) fn example_input(day: u8, name: &str) -> String {
+    let path = Path::new(EXAMPLE_DIR)
+        .join(format!("day{:02}", day))
+        .join(format!("{}.txt", name));
+    let contents = fs::read_to_string(&path)
+        .unwrap_or_else(|error| panic!("Unable to read {}: {}", path.display(), error));
+    match Example::parse(&contents) {
+        Ok(example) => example.input,
+        Err(error) => panic!("Invalid example {}: {}", path.display(), error),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_example() {
+        let actual = Example::parse("part1: 7\nwindow: 5\n---\n#..\n.#.").unwrap();
+
+        let mut params = Params::new();
+        params.insert("window", "5");
+        let expected = Example {
+            part1: Some(String::from("7")),
+            part2: None,
+            params,
+            input: String::from("#..\n.#.\n"),
+        };
+
+        assert_eq!(actual, expected);
+        assert!(Example::parse("part1: 7\n1721\n").is_err());
+        assert!(Example::parse("par1: 7\n---\n1721\n").is_err());
+    }
+
+    #[test]
+    fn test_unknown_param() {
+        let registry = Registry::builtin();
+        let check = |header: &str| {
+            let example = Example::parse(&format!("{}\n---\n1\n2\n3\n10\n", header)).unwrap();
+            example.check(registry.day(9)[0])
+        };
+
+        assert_eq!(check("part1: 10\nwindow: 3"), Ok(()));
+        assert_eq!(
+            check("part1: 10\npreamble: 3"),
+            Err("Unknown parameter 'preamble'".to_owned())
+        );
+    }
+
+    #[test]
+    fn examples() {
+        let registry = Registry::builtin();
+        let examples = discover(Path::new(EXAMPLE_DIR));
+        assert!(!examples.is_empty(), "No examples found in {}", EXAMPLE_DIR);
+
+        let failures: Vec<String> = examples
+            .iter()
+            .filter_map(|(day, path)| {
+                run(&registry, *day, path)
+                    .err()
+                    .map(|e| format!("{}: {}", path.display(), e))
+            })
+            .collect();
+
+        assert!(
+            failures.is_empty(),
+            "{} of {} examples failed:\n{}",
+            failures.len(),
+            examples.len(),
+            failures.join("\n")
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Accounts of how a day reached its answers, for when one looks wrong.
+//!
+//! Days opt in by implementing [`Explain`] and returning themselves from
+//! [`crate::Solver::explainer`]. An [`Explanation`] is a list of titled sections, each a table of
+//! key and value rows: the histogram, mapping or intermediate numbers the answer was built from.
+use std::fmt;
+
+pub trait Explain {
+    /// Record how the answers for `input` come about.
+    fn explain(&self, input: &str, explanation: &mut Explanation) -> anyhow::Result<()>;
+}
+
+#[derive(Debug, Default)]
+pub struct Explanation {
+    sections: Vec<Section>,
+}
+
+impl Explanation {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a section headed `title`, and return it to add rows to.
+    pub fn section(&mut self, title: impl Into<String>) -> &mut Section {
+        self.sections.push(Section {
+            title: title.into(),
+            rows: Vec::new(),
+        });
+        self.sections.last_mut().unwrap()
+    }
+}
+
+#[derive(Debug)]
+pub struct Section {
+    title: String,
+    rows: Vec<(String, String)>,
+}
+
+impl Section {
+    pub fn row(&mut self, key: impl fmt::Display, value: impl fmt::Display) -> &mut Self {
+        self.rows.push((key.to_string(), value.to_string()));
+        self
+    }
+}
+
+impl fmt::Display for Explanation {
+    /// Each section's title, then its rows indented with the values lined up.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in &self.sections {
+            writeln!(f, "{}", section.title)?;
+            let width = section.rows.iter().map(|(key, _)| key.len()).max();
+            for (key, value) in &section.rows {
+                let row = format!("  {:width$}  {}", key, value, width = width.unwrap_or(0));
+                writeln!(f, "{}", row.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut explanation = Explanation::new();
+        explanation
+            .section("Jolt differences")
+            .row(1, 22)
+            .row(3, 10);
+        explanation.section("Empty");
+        explanation
+            .section("Fields")
+            .row("row", "column 3")
+            .row("departure", "");
+
+        let expected = "\
+Jolt differences
+  1  22
+  3  10
+Empty
+Fields
+  row        column 3
+  departure
+";
+        assert_eq!(explanation.to_string(), expected);
+    }
+}
@@ -0,0 +1,112 @@
+//! Random puzzle inputs for stress-testing the solvers.
+//!
+//! Days opt in by implementing [`Generate`] and returning themselves from
+//! [`crate::Solver::generator`]. Generators are deterministic for a given seed, and only produce
+//! inputs that are well formed and have a single answer, so any panic or disagreement on one is
+//! a solver bug.
+use std::ops::Range;
+
+pub trait Generate {
+    /// Size used when none is given, roughly matching the real puzzle input.
+    fn default_size(&self) -> usize;
+
+    /// Produce an input. What `size` counts (lines, tiles, cards...) is up to each day.
+    fn generate(&self, rng: &mut Rng, size: usize) -> String;
+}
+
+/// Small deterministic PRNG (SplitMix64). Not suitable for anything but test data.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `range`, which must not be empty.
+    pub fn range(&mut self, range: Range<usize>) -> usize {
+        assert!(!range.is_empty(), "Empty range {:?}", range);
+        let len = (range.end - range.start) as u128;
+        range.start + ((u128::from(self.next_u64()) * len) >> 64) as usize
+    }
+
+    /// `true` with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        ((self.next_u64() >> 11) as f64 / (1_u64 << 53) as f64) < p
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.range(0..items.len())]
+    }
+
+    /// Fisher-Yates shuffle.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..i + 1));
+        }
+    }
+
+    /// Random lowercase word with a length in `len`.
+    pub fn word(&mut self, len: Range<usize>) -> String {
+        let len = self.range(len);
+        (0..len)
+            .map(|_| (b'a' + self.range(0..26) as u8) as char)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::panic::{self, AssertUnwindSafe};
+
+    use super::*;
+    use crate::days::get_solvers;
+
+    #[test]
+    fn test_rng() {
+        let mut a = Rng::new(7);
+        let mut b = Rng::new(7);
+        for _ in 0..100 {
+            let n = a.range(3..10);
+            assert_eq!(n, b.range(3..10));
+            assert!((3..10).contains(&n));
+        }
+
+        let mut items: Vec<usize> = (0..20).collect();
+        a.shuffle(&mut items);
+        items.sort_unstable();
+        assert_eq!(items, (0..20).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_generated_inputs_solve() {
+        for solver in get_solvers() {
+            let generator = match solver.generator() {
+                Some(generator) => generator,
+                None => continue,
+            };
+
+            for seed in 0..3 {
+                // Smaller than the real inputs, to keep the slower solvers quick in debug builds.
+                let size = (generator.default_size() / 4).max(1);
+                let input = generator.generate(&mut Rng::new(seed), size);
+                let result = panic::catch_unwind(AssertUnwindSafe(|| solver.solve(&input)));
+                assert!(
+                    matches!(result, Ok(Ok(_))),
+                    "Day {:02} failed on the input generated with seed {}:\n{}",
+                    solver.day(),
+                    seed,
+                    input
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,443 @@
+//! Points, directions and symmetries shared between the days set on grids.
+//!
+//! Coordinates follow the screen: `x` grows east, to the right, and `y` grows south, down the
+//! rows of an input. Rotations are clockwise as seen that way, so turning east a quarter turn
+//! clockwise faces south.
+
+use std::fmt;
+use std::ops::{Add, AddAssign, Index, IndexMut, Mul, Neg, Sub};
+
+/// A point, or an offset between two, in `D` dimensions.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point<const D: usize>(pub [i64; D]);
+
+pub type Point2 = Point<2>;
+
+impl<const D: usize> Point<D> {
+    pub const ORIGIN: Self = Self([0; D]);
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let mut sum = self;
+        for (a, b) in sum.0.iter_mut().zip(&other.0) {
+            *a = a.checked_add(*b)?;
+        }
+        Some(sum)
+    }
+
+    pub fn checked_mul(self, factor: i64) -> Option<Self> {
+        let mut product = self;
+        for a in &mut product.0 {
+            *a = a.checked_mul(factor)?;
+        }
+        Some(product)
+    }
+
+    /// The Manhattan distance from the origin, or `None` if it doesn't fit.
+    pub fn manhattan(self) -> Option<u64> {
+        self.0
+            .iter()
+            .try_fold(0_u64, |sum, a| sum.checked_add(a.unsigned_abs()))
+    }
+
+    /// Every offset to a point touching this one, diagonally included: the `3^D - 1` offsets
+    /// with each coordinate in `-1..=1`, other than the origin.
+    pub fn neighbour_offsets() -> Vec<Self> {
+        let mut offsets = vec![Self::ORIGIN];
+        for axis in 0..D {
+            offsets = offsets
+                .into_iter()
+                .flat_map(|offset| {
+                    (-1..=1).map(move |step| {
+                        let mut offset = offset;
+                        offset.0[axis] = step;
+                        offset
+                    })
+                })
+                .collect();
+        }
+        offsets.retain(|&offset| offset != Self::ORIGIN);
+        offsets
+    }
+}
+
+impl Point2 {
+    pub const fn new(x: i64, y: i64) -> Self {
+        Self([x, y])
+    }
+
+    pub fn x(self) -> i64 {
+        self.0[0]
+    }
+
+    pub fn y(self) -> i64 {
+        self.0[1]
+    }
+
+    /// Turned about the origin.
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        self.checked_rotate(rotation)
+            .expect("Rotating a point with i64::MIN in it")
+    }
+
+    /// Turned about the origin, or `None` if a coordinate can't be negated.
+    pub fn checked_rotate(self, rotation: Rotation) -> Option<Self> {
+        let (x, y) = (self.x(), self.y());
+        Some(match rotation.0 {
+            0 => self,
+            1 => Self::new(y.checked_neg()?, x),
+            2 => Self::new(x.checked_neg()?, y.checked_neg()?),
+            _ => Self::new(y, x.checked_neg()?),
+        })
+    }
+
+    /// Reflected in the vertical axis.
+    pub fn mirror(self) -> Self {
+        Self::new(-self.x(), self.y())
+    }
+}
+
+impl<const D: usize> Default for Point<D> {
+    fn default() -> Self {
+        Self::ORIGIN
+    }
+}
+
+impl<const D: usize> fmt::Debug for Point<D> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self.0)
+    }
+}
+
+impl<const D: usize> Index<usize> for Point<D> {
+    type Output = i64;
+
+    fn index(&self, axis: usize) -> &i64 {
+        &self.0[axis]
+    }
+}
+
+impl<const D: usize> IndexMut<usize> for Point<D> {
+    fn index_mut(&mut self, axis: usize) -> &mut i64 {
+        &mut self.0[axis]
+    }
+}
+
+impl<const D: usize> Add for Point<D> {
+    type Output = Self;
+
+    fn add(mut self, other: Self) -> Self {
+        self += other;
+        self
+    }
+}
+
+impl<const D: usize> AddAssign for Point<D> {
+    fn add_assign(&mut self, other: Self) {
+        for (a, b) in self.0.iter_mut().zip(&other.0) {
+            *a += b;
+        }
+    }
+}
+
+impl<const D: usize> Sub for Point<D> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self + -other
+    }
+}
+
+impl<const D: usize> Neg for Point<D> {
+    type Output = Self;
+
+    fn neg(mut self) -> Self {
+        for a in &mut self.0 {
+            *a = -*a;
+        }
+        self
+    }
+}
+
+impl<const D: usize> Mul<i64> for Point<D> {
+    type Output = Self;
+
+    fn mul(mut self, factor: i64) -> Self {
+        for a in &mut self.0 {
+            *a *= factor;
+        }
+        self
+    }
+}
+
+/// A turn by a whole number of right angles, as quarter turns clockwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Rotation(u8);
+
+impl Rotation {
+    pub const NONE: Self = Self(0);
+
+    pub const fn quarter_turns(turns: i64) -> Self {
+        Self(turns.rem_euclid(4) as u8)
+    }
+
+    /// A clockwise turn of `degrees`, or `None` if that's not a multiple of 90.
+    pub fn clockwise(degrees: i64) -> Option<Self> {
+        match degrees % 90 {
+            0 => Some(Self::quarter_turns(degrees / 90)),
+            _ => None,
+        }
+    }
+
+    pub fn counter_clockwise(degrees: i64) -> Option<Self> {
+        Some(Self::clockwise(degrees)?.inverse())
+    }
+
+    /// How many quarter turns clockwise, from 0 to 3.
+    pub fn turns(self) -> u8 {
+        self.0
+    }
+
+    pub fn inverse(self) -> Self {
+        Self::quarter_turns(-i64::from(self.0))
+    }
+}
+
+/// The eight compass points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compass {
+    N,
+    NE,
+    E,
+    SE,
+    S,
+    SW,
+    W,
+    NW,
+}
+
+impl Compass {
+    /// Clockwise from north.
+    pub const ALL: [Self; 8] = [
+        Self::N,
+        Self::NE,
+        Self::E,
+        Self::SE,
+        Self::S,
+        Self::SW,
+        Self::W,
+        Self::NW,
+    ];
+
+    /// One step this way.
+    pub fn offset(self) -> Point2 {
+        match self {
+            Self::N => Point2::new(0, -1),
+            Self::NE => Point2::new(1, -1),
+            Self::E => Point2::new(1, 0),
+            Self::SE => Point2::new(1, 1),
+            Self::S => Point2::new(0, 1),
+            Self::SW => Point2::new(-1, 1),
+            Self::W => Point2::new(-1, 0),
+            Self::NW => Point2::new(-1, -1),
+        }
+    }
+
+    pub fn rotate(self, rotation: Rotation) -> Self {
+        let index = Self::ALL.iter().position(|&d| d == self).unwrap();
+        Self::ALL[(index + 2 * rotation.turns() as usize) % 8]
+    }
+}
+
+/// The six directions between hexagons, in axial coordinates: `x` counts steps east and `y` steps
+/// south-east, so that rows of hexagons run east to west.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HexDirection {
+    E,
+    SE,
+    SW,
+    W,
+    NW,
+    NE,
+}
+
+impl HexDirection {
+    /// Clockwise from east.
+    pub const ALL: [Self; 6] = [Self::E, Self::SE, Self::SW, Self::W, Self::NW, Self::NE];
+
+    /// One step this way.
+    pub fn offset(self) -> Point2 {
+        match self {
+            Self::E => Point2::new(1, 0),
+            Self::SE => Point2::new(0, 1),
+            Self::SW => Point2::new(-1, 1),
+            Self::W => Point2::new(-1, 0),
+            Self::NW => Point2::new(0, -1),
+            Self::NE => Point2::new(1, -1),
+        }
+    }
+}
+
+/// One of the eight ways to turn a square onto itself: mirroring it in the vertical axis or not,
+/// then rotating it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Symmetry {
+    pub mirrored: bool,
+    pub rotation: Rotation,
+}
+
+impl Symmetry {
+    pub const ALL: [Self; 8] = [
+        Self::new(false, Rotation(0)),
+        Self::new(false, Rotation(1)),
+        Self::new(false, Rotation(2)),
+        Self::new(false, Rotation(3)),
+        Self::new(true, Rotation(0)),
+        Self::new(true, Rotation(1)),
+        Self::new(true, Rotation(2)),
+        Self::new(true, Rotation(3)),
+    ];
+
+    pub const fn new(mirrored: bool, rotation: Rotation) -> Self {
+        Self { mirrored, rotation }
+    }
+
+    /// `point` moved by this symmetry about the origin.
+    pub fn apply(self, point: Point2) -> Point2 {
+        let point = if self.mirrored { point.mirror() } else { point };
+        point.rotate(self.rotation)
+    }
+
+    /// `point` in a `width` by `height` box moved by this symmetry, with the box moved back to
+    /// where it was. Boxes that aren't square swap their width and height on a quarter turn.
+    pub fn apply_within(self, point: Point2, width: i64, height: i64) -> Point2 {
+        let corner = |x, y| self.apply(Point2::new(x, y));
+        let corners = [
+            corner(0, 0),
+            corner(width - 1, 0),
+            corner(0, height - 1),
+            corner(width - 1, height - 1),
+        ];
+        let min_x = corners.iter().map(|c| c.x()).min().unwrap();
+        let min_y = corners.iter().map(|c| c.y()).min().unwrap();
+        self.apply(point) - Point2::new(min_x, min_y)
+    }
+
+    pub fn inverse(self) -> Self {
+        match self.mirrored {
+            // Every reflection undoes itself.
+            true => self,
+            false => Self::new(false, self.rotation.inverse()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_points() {
+        let a = Point2::new(3, -4);
+
+        assert_eq!(a + Point2::new(1, 1), Point2::new(4, -3));
+        assert_eq!(a - a, Point2::ORIGIN);
+        assert_eq!(a * 2, Point2::new(6, -8));
+        assert_eq!(a.manhattan(), Some(7));
+        assert_eq!(Point2::new(i64::MIN, i64::MIN).manhattan(), None);
+        assert_eq!(a.checked_add(Point2::new(i64::MAX, 0)), None);
+        assert_eq!(Point([1, 2, 3])[2], 3);
+    }
+
+    #[test]
+    fn test_neighbour_offsets() {
+        let mut offsets = Point::<3>::neighbour_offsets();
+        offsets.sort();
+        offsets.dedup();
+
+        assert_eq!(offsets.len(), 26);
+        assert!(offsets
+            .iter()
+            .all(|o| o.0.iter().all(|a| (-1..=1).contains(a))));
+        assert!(!offsets.contains(&Point::ORIGIN));
+        assert_eq!(Point::<4>::neighbour_offsets().len(), 80);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let east = Point2::new(1, 0);
+
+        assert_eq!(
+            east.rotate(Rotation::clockwise(90).unwrap()),
+            Compass::S.offset()
+        );
+        assert_eq!(
+            east.rotate(Rotation::clockwise(-90).unwrap()),
+            Compass::N.offset()
+        );
+        assert_eq!(
+            east.rotate(Rotation::counter_clockwise(540).unwrap()),
+            Compass::W.offset()
+        );
+        assert_eq!(Rotation::clockwise(45), None);
+        assert_eq!(Compass::NE.rotate(Rotation::quarter_turns(3)), Compass::NW);
+        assert_eq!(
+            Point2::new(0, i64::MIN).checked_rotate(Rotation::quarter_turns(1)),
+            None
+        );
+        for &direction in &Compass::ALL {
+            for turns in 0..4 {
+                let rotation = Rotation::quarter_turns(turns);
+                assert_eq!(
+                    direction.rotate(rotation).offset(),
+                    direction.offset().rotate(rotation)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_symmetry_group() {
+        let point = Point2::new(2, 5);
+        let images: Vec<Point2> = Symmetry::ALL.iter().map(|s| s.apply(point)).collect();
+        let mut distinct = images.clone();
+        distinct.sort();
+        distinct.dedup();
+        assert_eq!(distinct.len(), 8);
+
+        for &symmetry in &Symmetry::ALL {
+            assert_eq!(symmetry.inverse().apply(symmetry.apply(point)), point);
+        }
+    }
+
+    #[test]
+    fn test_apply_within() {
+        let quarter = Symmetry::new(false, Rotation::quarter_turns(1));
+        let mirrored = Symmetry::new(true, Rotation::NONE);
+
+        // The top left corner of a 3 by 2 box ends up top right in the 2 by 3 box it turns into.
+        assert_eq!(
+            quarter.apply_within(Point2::new(0, 0), 3, 2),
+            Point2::new(1, 0)
+        );
+        assert_eq!(
+            quarter.apply_within(Point2::new(2, 1), 3, 2),
+            Point2::new(0, 2)
+        );
+        assert_eq!(
+            mirrored.apply_within(Point2::new(0, 1), 3, 2),
+            Point2::new(2, 1)
+        );
+        for &symmetry in &Symmetry::ALL {
+            let inverse = symmetry.inverse();
+            let moved = symmetry.apply_within(Point2::new(1, 0), 3, 2);
+            let (width, height) = match symmetry.rotation.turns() % 2 {
+                0 => (3, 2),
+                _ => (2, 3),
+            };
+            assert_eq!(
+                inverse.apply_within(moved, width, height),
+                Point2::new(1, 0)
+            );
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Directed graph utilities shared between days.
+//!
+//! Nodes are dense `usize` ids, usually handed out by an [`Interner`], and edges carry a `usize`
+//! weight (use `1` for unweighted graphs).
+
+use std::borrow::Borrow;
+use std::hash::Hash;
+
+use crate::hash::{FastMap, FastSet};
+
+/// Maps keys (e.g. bag names) to dense ids usable as [`Graph`] nodes.
+#[derive(Debug, Clone)]
+pub struct Interner<K> {
+    ids: FastMap<K, usize>,
+    keys: Vec<K>,
+}
+
+impl<K: Hash + Eq + Clone> Interner<K> {
+    pub fn new() -> Self {
+        Self {
+            ids: FastMap::default(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// Get the id for `key`, assigning the next free id if it hasn't been seen before.
+    pub fn intern(&mut self, key: K) -> usize {
+        if let Some(&id) = self.ids.get(&key) {
+            return id;
+        }
+
+        let id = self.keys.len();
+        self.keys.push(key.clone());
+        self.ids.insert(key, id);
+        id
+    }
+
+    /// Get the id for `key` without interning it.
+    pub fn get<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.ids.get(key).copied()
+    }
+
+    /// Get the key that was assigned `id`.
+    pub fn resolve(&self, id: usize) -> &K {
+        &self.keys[id]
+    }
+}
+
+impl<K: Hash + Eq + Clone> Default for Interner<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Weighted directed graph stored as an adjacency list.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Graph {
+    edges: Vec<Vec<(usize, usize)>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a graph with nodes `0..len` and no edges.
+    pub fn with_nodes(len: usize) -> Self {
+        Self {
+            edges: vec![Vec::new(); len],
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.edges.len()
+    }
+
+    /// Add an edge `from -> to`, growing the graph if either node doesn't exist yet.
+    pub fn add_edge(&mut self, from: usize, to: usize, weight: usize) {
+        let len = from.max(to) + 1;
+        if self.edges.len() < len {
+            self.edges.resize(len, Vec::new());
+        }
+        self.edges[from].push((to, weight));
+    }
+
+    /// Build the graph with every edge pointing the other way.
+    pub fn reverse(&self) -> Self {
+        let mut reversed = Self::with_nodes(self.len());
+        for (from, edges) in self.edges.iter().enumerate() {
+            for &(to, weight) in edges {
+                reversed.add_edge(to, from, weight);
+            }
+        }
+        reversed
+    }
+
+    /// Every node reachable from `from` through at least one edge.
+    ///
+    /// `from` itself is only included if it lies on a cycle.
+    pub fn reachable(&self, from: usize) -> FastSet<usize> {
+        let mut seen = FastSet::default();
+        let mut stack: Vec<usize> = self.edges[from].iter().map(|&(to, _)| to).collect();
+
+        while let Some(node) = stack.pop() {
+            if seen.insert(node) {
+                stack.extend(self.edges[node].iter().map(|&(to, _)| to));
+            }
+        }
+
+        seen
+    }
+
+    /// Weighted number of paths from `from` to `to`, where each path counts as the product of its
+    /// edge weights. Every node's count is computed once, so this is linear in the graph size.
+    ///
+    /// Returns `None` if a cycle is reachable from `from`, or the count overflows.
+    // Only day 10's tests use this, to check its part 2 against counting paths directly.
+    #[cfg(test)]
+    pub fn count_paths(&self, from: usize, to: usize) -> Option<usize> {
+        let mut paths = vec![0_usize; self.len()];
+        for node in self.postorder(from)? {
+            paths[node] = if node == to {
+                1
+            } else {
+                self.edges[node].iter().try_fold(0_usize, |sum, &(n, w)| {
+                    sum.checked_add(w.checked_mul(paths[n])?)
+                })?
+            };
+        }
+        Some(paths[from])
+    }
+
+    /// Weighted number of nodes below `from`: every path starting at `from` counts as the product
+    /// of its edge weights. Every node's total is computed once.
+    ///
+    /// Returns `None` if a cycle is reachable from `from`, or the total overflows.
+    pub fn path_weight_sum(&self, from: usize) -> Option<usize> {
+        let mut totals = vec![0_usize; self.len()];
+        for node in self.postorder(from)? {
+            totals[node] = self.edges[node].iter().try_fold(0_usize, |sum, &(n, w)| {
+                sum.checked_add(w.checked_mul(totals[n].checked_add(1)?)?)
+            })?;
+        }
+        Some(totals[from])
+    }
+
+    /// Nodes reachable from `from` (including itself), with every node after all of its
+    /// successors. Returns `None` if a cycle is reachable.
+    fn postorder(&self, from: usize) -> Option<Vec<usize>> {
+        let mut state = vec![Visit::New; self.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![(from, 0)];
+        state[from] = Visit::Active;
+
+        while let Some((node, next_edge)) = stack.pop() {
+            match self.edges[node].get(next_edge) {
+                Some(&(to, _)) => {
+                    stack.push((node, next_edge + 1));
+                    match state[to] {
+                        Visit::New => {
+                            state[to] = Visit::Active;
+                            stack.push((to, 0));
+                        }
+                        Visit::Active => return None,
+                        Visit::Done => {}
+                    }
+                }
+                None => {
+                    state[node] = Visit::Done;
+                    order.push(node);
+                }
+            }
+        }
+
+        Some(order)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Visit {
+    New,
+    Active,
+    Done,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// ```text
+    /// 0 -2-> 1 -3-> 3
+    /// 0 -1-> 2 -1-> 3
+    /// ```
+    fn diamond() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_edge(0, 1, 2);
+        graph.add_edge(0, 2, 1);
+        graph.add_edge(1, 3, 3);
+        graph.add_edge(2, 3, 1);
+        graph
+    }
+
+    #[test]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        let a = interner.intern("shiny gold");
+        let b = interner.intern("dark red");
+        assert_eq!(interner.intern("shiny gold"), a);
+        assert_ne!(a, b);
+        assert_eq!(interner.get("dark red"), Some(b));
+        assert_eq!(interner.get("faded blue"), None);
+        assert_eq!(*interner.resolve(b), "dark red");
+    }
+
+    #[test]
+    fn test_reachable() {
+        let graph = diamond();
+        let expected: FastSet<usize> = [1, 2, 3].iter().copied().collect();
+        assert_eq!(graph.reachable(0), expected);
+
+        let expected: FastSet<usize> = [0, 1, 2].iter().copied().collect();
+        assert_eq!(graph.reverse().reachable(3), expected);
+    }
+
+    #[test]
+    fn test_cycle() {
+        let mut graph = diamond();
+        graph.add_edge(3, 1, 1);
+        assert_eq!(graph.count_paths(0, 3), None);
+        assert_eq!(graph.path_weight_sum(0), None);
+    }
+
+    #[test]
+    fn test_count_paths() {
+        let graph = diamond();
+        assert_eq!(graph.count_paths(0, 3), Some(2 * 3 + 1));
+        assert_eq!(graph.count_paths(1, 3), Some(3));
+        assert_eq!(graph.count_paths(3, 0), Some(0));
+    }
+
+    #[test]
+    fn test_path_weight_sum() {
+        let graph = diamond();
+        // 0 holds 2 of 1 (each holding 3 of 3) and 1 of 2 (holding 1 of 3).
+        assert_eq!(graph.path_weight_sum(0), Some(2 * (1 + 3) + (1 + 1)));
+    }
+}
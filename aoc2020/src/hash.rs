@@ -0,0 +1,110 @@
+//! Maps and sets with a fast hasher, for the days that hash numbers, points and short strings by
+//! the million.
+//!
+//! The standard library hashes with SipHash, seeded at random, which stops crafted keys from
+//! making a map slow. SipHash costs more than the rest of a lookup on the small keys the days use,
+//! so [`FastHasher`] is the multiply-and-rotate hash from Firefox and rustc instead. It has no
+//! seed, so a map filled the same way iterates in the same order on every run, which keeps debug
+//! output comparable between runs.
+//!
+//! Without a seed, though, anyone can work out keys that all collide and make a day's maps
+//! quadratic. That's no risk for inputs people solve themselves, but [`serve`](crate::serve) takes
+//! them from any local client, so it caps their size and gives up on solves that run too long.
+use std::collections::{HashMap, HashSet};
+use std::convert::TryInto;
+use std::hash::{BuildHasherDefault, Hasher};
+
+pub type FastMap<K, V> = HashMap<K, V, BuildHasherDefault<FastHasher>>;
+pub type FastSet<T> = HashSet<T, BuildHasherDefault<FastHasher>>;
+
+/// An odd constant with its bits well mixed, from rustc's `FxHasher`.
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FastHasher {
+    hash: u64,
+}
+
+impl FastHasher {
+    #[inline]
+    fn add(&mut self, word: u64) {
+        self.hash = (self.hash.rotate_left(5) ^ word).wrapping_mul(SEED);
+    }
+}
+
+impl Hasher for FastHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            self.add(u64::from_le_bytes(chunk.try_into().unwrap()));
+        }
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let mut word = [0; 8];
+            word[..rest.len()].copy_from_slice(rest);
+            self.add(u64::from_le_bytes(word));
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, n: u8) {
+        self.add(u64::from(n));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, n: u16) {
+        self.add(u64::from(n));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, n: u32) {
+        self.add(u64::from(n));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, n: u64) {
+        self.add(n);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, n: usize) {
+        self.add(n as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::hash::{BuildHasher, Hash};
+
+    fn hash<T: Hash>(value: T) -> u64 {
+        BuildHasherDefault::<FastHasher>::default().hash_one(value)
+    }
+
+    #[test]
+    fn test_deterministic() {
+        // The same on every run and every platform, unlike SipHash's random seed.
+        assert_eq!(hash(0_u64), 0);
+        assert_eq!(hash(1_u64), SEED);
+        assert_eq!(hash("ab"), hash("ab"));
+        assert_ne!(hash("ab"), hash("ba"));
+        assert_ne!(hash((1, 2)), hash((2, 1)));
+
+        let fill = || -> FastSet<u32> { (0..1000).map(|n| n * 7919).collect() };
+        let order: Vec<u32> = fill().into_iter().collect();
+        assert_eq!(fill().into_iter().collect::<Vec<_>>(), order);
+    }
+
+    #[test]
+    fn test_bytes() {
+        // The tail of a string that doesn't fill a word still counts.
+        assert_ne!(hash("abcdefghi"), hash("abcdefghj"));
+        assert_ne!(hash([0_u8; 9].as_ref()), hash([0_u8; 10].as_ref()));
+    }
+}
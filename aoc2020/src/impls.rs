@@ -0,0 +1,272 @@
+//! Every implementation of each day, so that one can be picked with `--impl` and all of them
+//! checked against each other with `--compare-impls`.
+//!
+//! Each day has its default implementation, named [`BUILTIN`](crate::BUILTIN). Some also have built-in
+//! alternatives, trying a different algorithm under their own name, and [plugins](crate::plugins)
+//! can add more.
+use std::fmt;
+use std::time::Duration;
+
+use anyhow::ensure;
+
+use crate::days::{get_alternatives, get_solvers};
+use crate::plugins::Plugin;
+use crate::solution::Solution;
+use crate::Solver;
+
+pub struct Registry {
+    /// Ordered by day, each day's default first.
+    implementations: Vec<Box<dyn Solver>>,
+}
+
+impl Registry {
+    /// The implementations built into the crate.
+    pub fn builtin() -> Self {
+        let mut registry = Self {
+            implementations: get_solvers(),
+        };
+        for alternative in get_alternatives() {
+            registry.add(alternative).unwrap();
+        }
+        registry
+    }
+
+    /// Add `implementation`, after any others of its day.
+    pub fn add(&mut self, implementation: Box<dyn Solver>) -> anyhow::Result<()> {
+        let day = implementation.day();
+        ensure!(
+            self.day(day)
+                .iter()
+                .all(|i| i.name() != implementation.name()),
+            "Day {:02} already has an implementation named '{}'",
+            day,
+            implementation.name()
+        );
+        let position = self
+            .implementations
+            .iter()
+            .position(|i| i.day() > day)
+            .unwrap_or(self.implementations.len());
+        self.implementations.insert(position, implementation);
+        Ok(())
+    }
+
+    pub fn add_plugins(&mut self, plugins: Vec<Plugin>) -> anyhow::Result<()> {
+        for plugin in plugins {
+            self.add(Box::new(plugin))?;
+        }
+        Ok(())
+    }
+
+    /// Every implementation of `day`, the default first.
+    pub fn day(&self, day: u8) -> Vec<&dyn Solver> {
+        self.implementations
+            .iter()
+            .filter(|i| i.day() == day)
+            .map(|i| &**i)
+            .collect()
+    }
+
+    /// One solver for each day: the implementation called `name` for the days that have one, and
+    /// the default for the rest.
+    pub fn select(self, name: &str) -> anyhow::Result<Vec<Box<dyn Solver>>> {
+        ensure!(
+            self.implementations.iter().any(|i| i.name() == name),
+            "No implementation named '{}'. `aoc2020 impls` lists them.",
+            name
+        );
+
+        let mut solvers: Vec<Box<dyn Solver>> = Vec::new();
+        for implementation in self.implementations {
+            match solvers.last_mut() {
+                Some(last) if last.day() == implementation.day() => {
+                    if implementation.name() == name {
+                        *last = implementation;
+                    }
+                }
+                _ => solvers.push(implementation),
+            }
+        }
+        Ok(solvers)
+    }
+
+    /// Every implementation, ordered by day.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Solver> {
+        self.implementations.iter().map(|i| &**i)
+    }
+}
+
+/// How each implementation of a day fared on the same input.
+pub struct Comparison {
+    day: u8,
+    runs: Vec<Run>,
+}
+
+struct Run {
+    name: String,
+    /// The answers and how long they took, or the error.
+    result: Result<(Solution, Duration), String>,
+}
+
+impl Comparison {
+    /// Whether every implementation gave the same answers.
+    pub fn agree(&self) -> bool {
+        match self.runs.split_first() {
+            Some((
+                Run {
+                    result: Ok((first, _)),
+                    ..
+                },
+                rest,
+            )) => rest
+                .iter()
+                .all(|run| matches!(&run.result, Ok((solution, _)) if solution == first)),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    /// A line per implementation with its answers and time, under a verdict.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = match self.runs.len() {
+            1 if self.agree() => "only one implementation",
+            _ if self.agree() => "implementations agree",
+            _ => "implementations DISAGREE",
+        };
+        writeln!(f, "Day {:02}: {}", self.day, verdict)?;
+
+        let cells: Vec<[String; 4]> = self
+            .runs
+            .iter()
+            .map(|run| match &run.result {
+                Ok((solution, elapsed)) => [
+                    run.name.clone(),
+                    solution.part1().to_owned(),
+                    solution.part2().to_owned(),
+                    format!("{:?}", elapsed),
+                ],
+                Err(error) => [
+                    run.name.clone(),
+                    format!("error: {}", error),
+                    String::new(),
+                    String::new(),
+                ],
+            })
+            .collect();
+        let width = |column: usize| cells.iter().map(|row| row[column].len()).max();
+        let widths = [width(0), width(1), width(2)];
+
+        for row in &cells {
+            let mut line = String::new();
+            for (cell, width) in row.iter().zip(&widths) {
+                line += &format!("  {:width$}", cell, width = width.unwrap_or(0));
+            }
+            line += &format!("  {}", row[3]);
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+/// Solve `input` with each of `implementations`, all of the same day.
+pub fn compare(implementations: &[&dyn Solver], input: &str) -> Comparison {
+    let runs = implementations
+        .iter()
+        .map(|implementation| {
+            aoc_info!(
+                "Day {:02}: solving with {}",
+                implementation.day(),
+                implementation.name()
+            );
+            let result = implementation
+                .solve_timed(input)
+                .map(|(solution, timings)| (solution, timings.total()))
+                .map_err(|error| format!("{:#}", error));
+            Run {
+                name: implementation.name().to_owned(),
+                result,
+            }
+        })
+        .collect();
+
+    Comparison {
+        day: implementations.first().map_or(0, |i| i.day()),
+        runs,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::BUILTIN;
+
+    #[test]
+    fn test_registry() {
+        let registry = Registry::builtin();
+        let names = |day| -> Vec<&str> { registry.day(day).iter().map(|i| i.name()).collect() };
+
+        assert_eq!(names(1), vec![BUILTIN]);
+        assert_eq!(names(15), vec![BUILTIN, "flat"]);
+        assert_eq!(names(19), vec![BUILTIN, "earley", "memoized"]);
+        assert!(registry.iter().map(|i| i.day()).is_sorted());
+
+        let mut registry = registry;
+        assert!(registry.add(get_solvers().remove(0)).is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let selected = Registry::builtin().select("earley").unwrap();
+        let names: Vec<&str> = selected.iter().map(|i| i.name()).collect();
+
+        assert_eq!(selected.len(), 25);
+        assert_eq!(names[18], "earley");
+        assert!(names
+            .iter()
+            .enumerate()
+            .all(|(i, &name)| i == 18 || name == BUILTIN));
+        assert!(selected.iter().zip(1..).all(|(i, day)| i.day() == day));
+
+        let defaults = Registry::builtin().select(BUILTIN).unwrap();
+        assert!(defaults.iter().all(|i| i.name() == BUILTIN));
+        assert!(Registry::builtin().select("missing").is_err());
+    }
+
+    #[test]
+    fn test_compare() {
+        let registry = Registry::builtin();
+        let input = "0: 1 2\n1: \"a\"\n2: 1 3 | 3 1\n3: \"b\"\n\naab\naba\nab\n";
+
+        let comparison = compare(&registry.day(19), input);
+        assert!(comparison.agree());
+        let text = comparison.to_string();
+        assert!(text.starts_with("Day 19: implementations agree\n  builtin   2  2 "));
+        assert!(text.contains("\n  earley    2  2 "));
+        assert!(text.contains("\n  memoized  2  2 "));
+
+        let run = |name: &str, result| Run {
+            name: name.to_owned(),
+            result,
+        };
+        let comparison = Comparison {
+            day: 3,
+            runs: vec![
+                run(
+                    BUILTIN,
+                    Ok((Solution::new(7, 336), Duration::from_millis(2))),
+                ),
+                run("fast", Ok((Solution::new(7, 33), Duration::ZERO))),
+                run("broken", Err("Bad input".to_owned())),
+            ],
+        };
+        let expected = "\
+Day 03: implementations DISAGREE
+  builtin  7                 336  2ms
+  fast     7                 33   0ns
+  broken   error: Bad input
+";
+        assert!(!comparison.agree());
+        assert_eq!(comparison.to_string(), expected);
+    }
+}
@@ -0,0 +1,660 @@
+//! Finding puzzle inputs, wherever the binary or tests are run from.
+//!
+//! Day `N`'s input is `dayNN.txt` in the first of these directories that has it:
+//!
+//! 1. the `--input-dir` flag,
+//! 2. the `AOC_INPUT_DIR` environment variable,
+//! 3. `input-dir` in the [config file](crate::config),
+//! 4. `input/` next to the crate's `Cargo.toml`.
+//!
+//! Inputs can also be kept per person, as profiles: with a profile selected, day `N`'s input is
+//! `<profile>/dayNN.txt` under those directories instead. Each profile (and the unnamed default
+//! one) can record its expected answers in an `answers.txt` beside the inputs, in the same
+//! `key = value` format as the config file:
+//!
+//! ```text
+//! day01.part1 = 1018336
+//! day01.part2 = 288756720
+//! ```
+//!
+//! Inputs are loaded as bytes, into an [`Input`]. [`Input::load_mapped`] maps files of a megabyte
+//! or more into memory on Unix rather than reading them, but only one-shot runs should use it:
+//! a mapped file that's edited or truncated meanwhile changes under the solver or kills the process
+//! with `SIGBUS`.
+use std::collections::{BTreeSet, HashMap};
+use std::convert::TryFrom;
+use std::env;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, ensure, Context};
+
+use crate::config::Config;
+use crate::solution::Solution;
+
+pub const INPUT_DIR_VAR: &str = "AOC_INPUT_DIR";
+
+const MANIFEST_INPUT_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/input");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Flag,
+    Environment,
+    Config,
+    Manifest,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Flag => write!(f, "--input-dir"),
+            Self::Environment => write!(f, "${}", INPUT_DIR_VAR),
+            Self::Config => write!(f, "config"),
+            Self::Manifest => write!(f, "crate"),
+        }
+    }
+}
+
+const ANSWERS_FILE: &str = "answers.txt";
+
+/// The directories to look for inputs in, in order, and the profile to look for.
+#[derive(Debug, Clone)]
+pub struct SearchPath {
+    directories: Vec<(Source, PathBuf)>,
+    profile: Option<String>,
+}
+
+impl SearchPath {
+    /// The search path for this run, with `flag` as the `--input-dir` value if given.
+    pub fn new(flag: Option<&str>) -> anyhow::Result<Self> {
+        let environment = env::var_os(INPUT_DIR_VAR).filter(|dir| !dir.is_empty());
+        Ok(Self::build(
+            flag.map(PathBuf::from),
+            environment.map(PathBuf::from),
+            &Config::load()?,
+        ))
+    }
+
+    pub(crate) fn build(
+        flag: Option<PathBuf>,
+        environment: Option<PathBuf>,
+        config: &Config,
+    ) -> Self {
+        let directories = vec![
+            (Source::Flag, flag),
+            (Source::Environment, environment),
+            (Source::Config, config.get_path("input-dir")),
+            (Source::Manifest, Some(PathBuf::from(MANIFEST_INPUT_DIR))),
+        ];
+
+        Self {
+            directories: directories
+                .into_iter()
+                .filter_map(|(source, dir)| Some((source, dir?)))
+                .collect(),
+            profile: None,
+        }
+    }
+
+    /// Look for `profile`'s inputs rather than the default ones.
+    pub fn with_profile(mut self, profile: Option<&str>) -> anyhow::Result<Self> {
+        if let Some(profile) = profile {
+            ensure!(
+                !profile.is_empty() && !profile.contains(['/', '\\']),
+                "Invalid profile name: '{}'",
+                profile
+            );
+        }
+        self.profile = profile.map(str::to_owned);
+        Ok(self)
+    }
+
+    pub fn profile(&self) -> Option<&str> {
+        self.profile.as_deref()
+    }
+
+    /// The directories the selected profile's files are in, in search order.
+    fn profile_directories(&self) -> impl Iterator<Item = (Source, PathBuf)> + '_ {
+        self.directories.iter().map(move |(source, dir)| {
+            let dir = match &self.profile {
+                Some(profile) => dir.join(profile),
+                None => dir.clone(),
+            };
+            (*source, dir)
+        })
+    }
+
+    /// Every profile with at least one input in any of the directories, sorted by name.
+    pub fn profiles(&self) -> Vec<String> {
+        let mut profiles = BTreeSet::new();
+
+        for (_, dir) in &self.directories {
+            for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+                let has_inputs =
+                    (1..=25).any(|day| entry.path().join(input_file_name(day)).is_file());
+                if let (true, Some(name)) = (has_inputs, entry.file_name().to_str()) {
+                    profiles.insert(name.to_owned());
+                }
+            }
+        }
+
+        profiles.into_iter().collect()
+    }
+
+    /// Where day `day`'s input is, listing every place tried if it's nowhere.
+    pub fn find(&self, day: u8) -> anyhow::Result<PathBuf> {
+        let file_name = input_file_name(day);
+
+        for (source, dir) in self.profile_directories() {
+            let path = dir.join(&file_name);
+            if path.is_file() {
+                aoc_debug!("Day {:02}: using {} from {}", day, path.display(), source);
+                return Ok(path);
+            }
+        }
+
+        let tried: Vec<String> = self
+            .profile_directories()
+            .map(|(source, dir)| format!("  {} ({})", dir.join(&file_name).display(), source))
+            .collect();
+        bail!("No input for day {:02}. Tried:\n{}", day, tried.join("\n"))
+    }
+
+    /// The selected profile's expected answers, from the first `answers.txt` on the search path.
+    /// Having none recorded isn't an error.
+    pub fn answers(&self) -> anyhow::Result<Answers> {
+        let path = match self
+            .profile_directories()
+            .map(|(_, dir)| dir.join(ANSWERS_FILE))
+            .find(|path| path.is_file())
+        {
+            Some(path) => path,
+            None => return Ok(Answers::default()),
+        };
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Unable to read {}", path.display()))?;
+        Answers::parse(&contents).with_context(|| format!("Invalid answers in {}", path.display()))
+    }
+
+    /// Load day `day`'s input, with line endings normalised and a final newline.
+    pub fn load(&self, day: u8) -> anyhow::Result<Input> {
+        let path = self.find(day)?;
+        Input::load(&path)
+            .with_context(|| format!("Error while reading input file {}", path.display()))
+    }
+
+    /// Like [`SearchPath::load`], but mapping a large input rather than reading it.
+    ///
+    /// # Safety
+    ///
+    /// As for [`Input::load_mapped`].
+    pub unsafe fn load_mapped(&self, day: u8) -> anyhow::Result<Input> {
+        let path = self.find(day)?;
+        Input::load_mapped(&path)
+            .with_context(|| format!("Error while reading input file {}", path.display()))
+    }
+
+    /// Like [`SearchPath::load`], but as text.
+    pub fn read(&self, day: u8) -> anyhow::Result<String> {
+        Ok(self.load(day)?.as_str()?.to_owned())
+    }
+}
+
+/// Inputs at least this big are mapped by [`Input::load_mapped`] instead of read.
+const MAP_THRESHOLD: u64 = 1 << 20;
+
+/// A day's input as bytes, with every `\r` dropped and a newline at the end.
+///
+/// Neither is done by copying. Carriage returns are squeezed out where the bytes already are, and
+/// the newline goes in the spare byte the buffer was given for it. A mapped file is private to
+/// this process, so the fixes never reach the disk, and one that needed none is never written to
+/// at all.
+pub struct Input(Contents);
+
+enum Contents {
+    Owned(Vec<u8>),
+    #[cfg(unix)]
+    Mapped(Mapping),
+}
+
+impl Input {
+    /// Read the file at `path` into memory.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        // Safety: nothing is mapped below a threshold no file reaches.
+        unsafe { Self::load_mapping_from(path, u64::MAX) }
+    }
+
+    /// Load the file at `path`, mapping it if it's big enough to be worth it.
+    ///
+    /// # Safety
+    ///
+    /// The file mustn't change until the `Input` is dropped. The mapping is copy-on-write, but
+    /// only for the pages this process writes, so an edit can show through and change bytes
+    /// that are meant to be immutable. A truncation is worse: reading past the new end raises
+    /// `SIGBUS`, which kills the process. Only use this to solve once and drop the input
+    /// straight after, never in a long-running mode such as `tui` or `serve`.
+    pub unsafe fn load_mapped(path: &Path) -> io::Result<Self> {
+        Self::load_mapping_from(path, MAP_THRESHOLD)
+    }
+
+    /// # Safety
+    ///
+    /// As for [`Input::load_mapped`], if the file is at least `threshold` bytes.
+    unsafe fn load_mapping_from(path: &Path, threshold: u64) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+
+        #[cfg(unix)]
+        {
+            if len > 0 && len >= threshold {
+                if let Some(mapping) = Mapping::new(&file, len)? {
+                    return Ok(Self(Contents::Mapped(mapping)));
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = threshold;
+
+        let mut bytes = Vec::with_capacity(usize::try_from(len).map_or(0, |len| len + 1));
+        file.read_to_end(&mut bytes)?;
+        Ok(Self::from(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match &self.0 {
+            Contents::Owned(bytes) => bytes,
+            #[cfg(unix)]
+            Contents::Mapped(mapping) => mapping.as_bytes(),
+        }
+    }
+
+    /// The input as text, or an error if it isn't UTF-8.
+    pub fn as_str(&self) -> anyhow::Result<&str> {
+        crate::as_text(self.as_bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.as_bytes().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.as_bytes().is_empty()
+    }
+
+    fn is_mapped(&self) -> bool {
+        !matches!(self.0, Contents::Owned(_))
+    }
+}
+
+impl From<Vec<u8>> for Input {
+    fn from(mut bytes: Vec<u8>) -> Self {
+        let len = strip_carriage_returns(&mut bytes);
+        bytes.truncate(len);
+        if bytes.last() != Some(&b'\n') {
+            bytes.push(b'\n');
+        }
+        Self(Contents::Owned(bytes))
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Input")
+            .field("len", &self.len())
+            .field("mapped", &self.is_mapped())
+            .finish()
+    }
+}
+
+/// Drop every `\r` from `bytes`, moving the rest to the front. Returns how many bytes are left.
+fn strip_carriage_returns(bytes: &mut [u8]) -> usize {
+    let first = match bytes.iter().position(|&b| b == b'\r') {
+        Some(first) => first,
+        None => return bytes.len(),
+    };
+    let mut kept = first;
+    for i in first + 1..bytes.len() {
+        if bytes[i] != b'\r' {
+            bytes[kept] = bytes[i];
+            kept += 1;
+        }
+    }
+    kept
+}
+
+/// The lines of `input`, split the way [`str::lines`] splits text.
+pub(crate) fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let body = match input {
+        [] => None,
+        _ => Some(input.strip_suffix(b"\n").unwrap_or(input)),
+    };
+    body.into_iter()
+        .flat_map(|body| body.split(|&b| b == b'\n'))
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+}
+
+/// `digits` as a number, or `None` if it's empty, has anything but ASCII digits or overflows.
+pub(crate) fn parse_unsigned(digits: &[u8]) -> Option<u64> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0_u64, |n, &digit| match digit {
+        b'0'..=b'9' => n.checked_mul(10)?.checked_add(u64::from(digit - b'0')),
+        _ => None,
+    })
+}
+
+/// A file mapped copy-on-write, normalised like any other [`Input`]. The file mustn't change while
+/// it's mapped; see [`Input::load_mapped`].
+#[cfg(unix)]
+struct Mapping {
+    ptr: *mut u8,
+    /// How much is mapped, which may be a byte past the end of the file.
+    mapped: usize,
+    /// How much of that is the input.
+    len: usize,
+}
+
+// Safety: the mapping is private to this process and only written to before it's shared.
+#[cfg(unix)]
+unsafe impl Send for Mapping {}
+#[cfg(unix)]
+unsafe impl Sync for Mapping {}
+
+#[cfg(unix)]
+impl Mapping {
+    /// Map `len` bytes of `file` and normalise them, or `None` if there's no room for a final
+    /// newline the file lacks. There's room if a `\r` was dropped, or if the file ends partway
+    /// through a page: the rest of that page can be mapped too, and reads as zeroes.
+    fn new(file: &File, len: u64) -> io::Result<Option<Self>> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = usize::try_from(len).map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        // Safety: sysconf has no preconditions.
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+        let page = usize::try_from(page).map_err(|_| io::Error::last_os_error())?;
+        let mapped = if len.is_multiple_of(page) {
+            len
+        } else {
+            len + 1
+        };
+
+        // Safety: a fresh private mapping doesn't alias anything, and `Drop` unmaps it.
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                mapped,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+
+        let mut mapping = Self {
+            ptr: ptr.cast(),
+            mapped,
+            len,
+        };
+        // Safety: all `mapped` bytes are mapped and nothing else refers to them yet.
+        let bytes = unsafe { std::slice::from_raw_parts_mut(mapping.ptr, mapped) };
+        mapping.len = strip_carriage_returns(&mut bytes[..len]);
+        if bytes[..mapping.len].last() != Some(&b'\n') {
+            if mapping.len == mapped {
+                return Ok(None);
+            }
+            bytes[mapping.len] = b'\n';
+            mapping.len += 1;
+        }
+        Ok(Some(mapping))
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        // Safety: the first `len` bytes are mapped for as long as `self` lives.
+        unsafe { std::slice::from_raw_parts(self.ptr, self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // Safety: `ptr` and `mapped` are exactly what mmap returned and was given.
+        unsafe {
+            libc::munmap(self.ptr.cast(), self.mapped);
+        }
+    }
+}
+
+fn input_file_name(day: u8) -> String {
+    format!("day{:02}.txt", day)
+}
+
+/// A profile's expected answers, by day and part.
+#[derive(Debug, Default)]
+pub struct Answers(HashMap<(u8, u8), String>);
+
+impl Answers {
+    pub fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut answers = HashMap::new();
+
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .with_context(|| format!("Expected 'dayNN.partN = answer', found '{}'", line))?;
+            let (day, part) = key
+                .trim()
+                .strip_prefix("day")
+                .and_then(|key| key.split_once(".part"))
+                .and_then(|(day, part)| Some((day.parse().ok()?, part.parse().ok()?)))
+                .filter(|&(_, part)| part == 1 || part == 2)
+                .with_context(|| format!("Invalid answer key: '{}'", key.trim()))?;
+            answers.insert((day, part), value.trim().to_owned());
+        }
+
+        Ok(Self(answers))
+    }
+
+    pub fn get(&self, day: u8, part: u8) -> Option<&str> {
+        self.0.get(&(day, part)).map(String::as_str)
+    }
+
+    /// Compare `solution` to what was recorded for `day`: `None` if nothing was, otherwise the
+    /// parts that differ.
+    pub fn check(&self, day: u8, solution: &Solution) -> Option<Vec<u8>> {
+        let actual = [solution.part1(), solution.part2()];
+        let mut recorded = false;
+        let mut wrong = Vec::new();
+
+        for (part, actual) in (1..=2).zip(actual.iter()) {
+            if let Some(expected) = self.get(day, part) {
+                recorded = true;
+                if expected != *actual {
+                    wrong.push(part);
+                }
+            }
+        }
+
+        if recorded {
+            Some(wrong)
+        } else {
+            None
+        }
+    }
+}
+
+/// The input for a day's `verify` test, or `None` (after saying so) if there isn't one so the test
+/// can skip itself. Inputs aren't shipped with every checkout.
+#[cfg(test)]
+pub(crate) fn for_test(day: u8) -> Option<String> {
+    let search_path = SearchPath::new(None).expect("Unable to build the input search path");
+    match search_path.read(day) {
+        Ok(input) => Some(input),
+        Err(error) => {
+            eprintln!("Skipping test, {:#}", error);
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_search_order() {
+        let config = Config::parse("input-dir = /from/config").unwrap();
+        let search_path = SearchPath::build(
+            Some(PathBuf::from("/from/flag")),
+            Some(PathBuf::from("/from/env")),
+            &config,
+        );
+
+        let sources: Vec<Source> = search_path
+            .directories
+            .iter()
+            .map(|(source, _)| *source)
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                Source::Flag,
+                Source::Environment,
+                Source::Config,
+                Source::Manifest
+            ]
+        );
+        assert_eq!(search_path.directories[2].1, PathBuf::from("/from/config"));
+    }
+
+    #[test]
+    fn test_find() {
+        let dir = env::temp_dir().join(format!("aoc2020-inputs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("day07.txt"), "light red bags\r\n").unwrap();
+
+        let search_path = SearchPath::build(
+            Some(PathBuf::from("/does/not/exist")),
+            Some(dir.clone()),
+            &Config::default(),
+        );
+        let found = search_path.find(7);
+        let read = search_path.read(7);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(found.unwrap(), dir.join("day07.txt"));
+        assert_eq!(read.unwrap(), "light red bags\n");
+        let missing = search_path.find(26).unwrap_err().to_string();
+        assert!(missing.contains("/does/not/exist/day26.txt (--input-dir)"));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let dir = env::temp_dir().join(format!("aoc2020-profiles-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("alice")).unwrap();
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(dir.join("day01.txt"), "1721\n").unwrap();
+        std::fs::write(dir.join("alice").join("day01.txt"), "979\n").unwrap();
+        std::fs::write(dir.join("alice").join("answers.txt"), "day01.part1 = 7").unwrap();
+
+        let search_path = SearchPath::build(Some(dir.clone()), None, &Config::default());
+        let alice = search_path.clone().with_profile(Some("alice")).unwrap();
+        let profiles = search_path.profiles();
+        let default_input = search_path.read(1);
+        let alice_input = alice.read(1);
+        let alice_answers = alice.answers();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(profiles, vec!["alice"]);
+        assert_eq!(default_input.unwrap(), "1721\n");
+        assert_eq!(alice_input.unwrap(), "979\n");
+        assert_eq!(alice_answers.unwrap().get(1, 1), Some("7"));
+        assert!(search_path.with_profile(Some("../bob")).is_err());
+    }
+
+    #[test]
+    fn test_load() {
+        let dir = env::temp_dir().join(format!("aoc2020-load-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let load = |contents: &[u8], threshold| {
+            let path = dir.join("input.txt");
+            std::fs::write(&path, contents).unwrap();
+            // Safety: nothing else touches the file until it's been copied out.
+            let input = unsafe { Input::load_mapping_from(&path, threshold) }.unwrap();
+            (input.as_bytes().to_vec(), input.is_mapped())
+        };
+        let big = vec![b'#'; MAP_THRESHOLD as usize];
+        std::fs::write(dir.join("big.txt"), &big).unwrap();
+        assert!(!Input::load(&dir.join("big.txt")).unwrap().is_mapped());
+
+        // Read, then mapped where that's supported.
+        for &threshold in &[u64::MAX, 1] {
+            let mapped = cfg!(unix) && threshold == 1;
+            assert_eq!(load(b"1\n2\n", threshold), (b"1\n2\n".to_vec(), mapped));
+            assert_eq!(load(b"1\r\n2\r\n", threshold), (b"1\n2\n".to_vec(), mapped));
+            assert_eq!(load(b"1\r\n2", threshold), (b"1\n2\n".to_vec(), mapped));
+            assert_eq!(load(b"1\n2", threshold), (b"1\n2\n".to_vec(), mapped));
+        }
+        assert_eq!(load(b"", 1), (b"\n".to_vec(), false));
+
+        // A whole number of pages leaves no room for the newline, unless a `\r` goes.
+        #[cfg(unix)]
+        {
+            // Safety: sysconf has no preconditions.
+            let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+            let mut full = vec![b'#'; page];
+            let mut expected = full.clone();
+            expected.push(b'\n');
+            assert_eq!(load(&full, 1), (expected, false));
+            full[page - 2] = b'\r';
+            full[page - 1] = b'\n';
+            let mut expected = vec![b'#'; page - 2];
+            expected.push(b'\n');
+            assert_eq!(load(&full, 1), (expected, true));
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(Input::from(b"\xff".to_vec()).as_bytes(), b"\xff\n");
+        assert!(Input::from(b"\xff".to_vec()).as_str().is_err());
+    }
+
+    #[test]
+    fn test_lines() {
+        for text in &["", "\n", "a", "a\n", "a\n\nb", "a\r\nb\r\n", "a\n\n"] {
+            let expected: Vec<&[u8]> = text.lines().map(str::as_bytes).collect();
+            let actual: Vec<&[u8]> = lines(text.as_bytes()).collect();
+            assert_eq!(actual, expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_parse_unsigned() {
+        assert_eq!(parse_unsigned(b"2020"), Some(2020));
+        assert_eq!(parse_unsigned(b"007"), Some(7));
+        assert_eq!(parse_unsigned(b"18446744073709551615"), Some(u64::MAX));
+        assert_eq!(parse_unsigned(b"18446744073709551616"), None);
+        assert_eq!(parse_unsigned(b""), None);
+        assert_eq!(parse_unsigned(b"-1"), None);
+        assert_eq!(parse_unsigned(b"1 "), None);
+    }
+
+    #[test]
+    fn test_check_answers() {
+        let answers = Answers::parse("# Day 1\nday01.part1 = 514579\nday01.part2 = 1").unwrap();
+
+        let solution: Solution = (514579, 241861950).into();
+        assert_eq!(answers.check(1, &solution), Some(vec![2]));
+        assert_eq!(answers.check(2, &solution), None);
+        assert!(Answers::parse("day01.part3 = 1").is_err());
+        assert!(Answers::parse("day01 = 1").is_err());
+    }
+}
@@ -0,0 +1,311 @@
+//! Solving a day in a child process with limits on its memory and CPU time, so that a day that
+//! runs away takes down only itself.
+//!
+//! The parent runs its own binary again with the hidden [`CHILD_COMMAND`], hands it the input on
+//! stdin and reads the answers back from its stdout. The limits are set on the child before it
+//! starts, and the child is also killed if it's still running after a timeout, in case it's stuck
+//! without using CPU time. On platforms other than Unix only the timeout applies.
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use anyhow::{bail, Context};
+
+use crate::solution::Solution;
+use crate::Solver;
+
+/// The subcommand that makes the binary act as the child.
+pub const CHILD_COMMAND: &str = "isolated-day";
+
+/// What allocation failures look like on stderr, since they abort rather than exit.
+const OUT_OF_MEMORY: &str = "memory allocation of";
+
+#[derive(Debug, Clone, Copy)]
+pub struct Limits {
+    /// The most address space the child may map, in bytes.
+    pub memory: u64,
+    pub cpu_time: Duration,
+    /// How long the child may run in real time, for when it's stuck without using CPU time.
+    pub timeout: Duration,
+}
+
+/// How a day fared in its child process.
+#[derive(Debug, PartialEq)]
+pub enum Outcome {
+    Solved(Solution),
+    /// The solver returned an error.
+    Failed(String),
+    OutOfMemory,
+    CpuTimeExceeded,
+    TimedOut(Duration),
+    /// Killed by a signal that isn't one of the limits'.
+    Signal(i32),
+    /// Exited without answering, as on a panic, with the end of what it wrote to stderr.
+    Crashed(String),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Solved(solution) => write!(f, "{}", solution),
+            Self::Failed(error) => write!(f, "failed: {}", error),
+            Self::OutOfMemory => write!(f, "out of memory"),
+            Self::CpuTimeExceeded => write!(f, "out of CPU time"),
+            Self::TimedOut(after) => write!(f, "timed out after {:?}", after),
+            Self::Signal(signal) => write!(f, "killed by signal {}", signal),
+            Self::Crashed(stderr) => write!(f, "crashed: {}", stderr),
+        }
+    }
+}
+
+/// Solve `input` in a child process run as `command`, which must end up calling [`child`] with
+/// the day's solver.
+pub fn run(mut command: Command, input: &[u8], limits: &Limits) -> anyhow::Result<Outcome> {
+    command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    apply_limits(&mut command, limits);
+
+    let start = Instant::now();
+    let mut child = command
+        .spawn()
+        .context("Unable to start the child process")?;
+    // Each pipe gets its own thread, so that a full one can't stall the others.
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = thread::spawn(move || stdin.write_all(&input));
+    let stdout = read_all(child.stdout.take().unwrap());
+    let stderr = read_all(child.stderr.take().unwrap());
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if start.elapsed() >= limits.timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(Outcome::TimedOut(limits.timeout));
+        }
+        thread::sleep(Duration::from_millis(10));
+    };
+
+    // The child may exit without reading its input, which breaks the pipe.
+    let _ = writer.join();
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    Ok(outcome(status, &stdout, &stderr))
+}
+
+fn read_all(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut text = Vec::new();
+        let _ = pipe.read_to_end(&mut text);
+        String::from_utf8_lossy(&text).into_owned()
+    })
+}
+
+fn outcome(status: ExitStatus, stdout: &str, stderr: &str) -> Outcome {
+    if stderr.contains(OUT_OF_MEMORY) {
+        return Outcome::OutOfMemory;
+    }
+    if let Some(signal) = signal(status) {
+        return match signal {
+            #[cfg(unix)]
+            libc::SIGXCPU => Outcome::CpuTimeExceeded,
+            signal => Outcome::Signal(signal),
+        };
+    }
+    match decode(stdout) {
+        Some(outcome) if status.success() => outcome,
+        _ => {
+            let lines: Vec<&str> = stderr.lines().filter(|l| !l.is_empty()).collect();
+            let tail = lines[lines.len().saturating_sub(2)..].join(" ");
+            match status.code() {
+                Some(code) if tail.is_empty() => Outcome::Crashed(format!("exit status {}", code)),
+                _ => Outcome::Crashed(tail),
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal(status: ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn signal(_: ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(unix)]
+fn apply_limits(command: &mut Command, limits: &Limits) {
+    use std::os::unix::process::CommandExt;
+
+    let memory = limits.memory as libc::rlim_t;
+    let cpu_time = limits.cpu_time.as_secs().max(1) as libc::rlim_t;
+    let set = |resource, soft: libc::rlim_t, hard: libc::rlim_t| {
+        let limit = libc::rlimit {
+            rlim_cur: soft,
+            rlim_max: hard,
+        };
+        // Safety: setrlimit only reads the struct it's given.
+        match unsafe { libc::setrlimit(resource, &limit) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    };
+    // Safety: the closure only makes system calls, which is all that's allowed between fork and
+    // exec.
+    unsafe {
+        command.pre_exec(move || {
+            set(libc::RLIMIT_AS, memory, memory)?;
+            // SIGXCPU at the soft limit, then SIGKILL a second later if that's ignored.
+            set(libc::RLIMIT_CPU, cpu_time, cpu_time + 1)
+        });
+    }
+}
+
+#[cfg(not(unix))]
+fn apply_limits(_: &mut Command, _: &Limits) {}
+
+/// Act as the child: solve the input on stdin with `solver` and write the answers to stdout.
+pub fn child(solver: &dyn Solver) -> anyhow::Result<()> {
+    let mut input = Vec::new();
+    io::stdin()
+        .read_to_end(&mut input)
+        .context("Unable to read the input")?;
+
+    let result = solver
+        .solve_bytes(&input)
+        .map_err(|error| format!("{:#}", error));
+    io::stdout()
+        .write_all(encode(&result).as_bytes())
+        .context("Unable to write the answers")
+}
+
+/// The answers as `ok` and a line for each part, or `error` and a line with the message.
+fn encode(result: &Result<Solution, String>) -> String {
+    let escape = |text: &str| text.replace('\\', "\\\\").replace('\n', "\\n");
+    match result {
+        Ok(solution) => format!(
+            "ok\n{}\n{}\n",
+            escape(solution.part1()),
+            escape(solution.part2())
+        ),
+        Err(error) => format!("error\n{}\n", escape(error)),
+    }
+}
+
+fn decode(text: &str) -> Option<Outcome> {
+    fn unescape(text: &str) -> String {
+        let mut unescaped = String::new();
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                unescaped.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(c) => unescaped.push(c),
+                None => {}
+            }
+        }
+        unescaped
+    }
+
+    let lines: Vec<&str> = text.lines().collect();
+    match lines.as_slice() {
+        ["ok", part1, part2] => Some(Outcome::Solved(Solution::new(
+            unescape(part1),
+            unescape(part2),
+        ))),
+        ["error", message] => Some(Outcome::Failed(unescape(message))),
+        _ => None,
+    }
+}
+
+/// Parse a limit given on the command line, in whole units of `scale`.
+pub fn parse_limit(value: &str, what: &str, scale: u64) -> anyhow::Result<u64> {
+    match value.parse::<u64>() {
+        Ok(value) if value > 0 => Ok(value.saturating_mul(scale)),
+        _ => bail!("{} must be a positive whole number, not '{}'", what, value),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        memory: 1 << 30,
+        cpu_time: Duration::from_secs(1),
+        timeout: Duration::from_secs(20),
+    };
+
+    fn shell(script: &str) -> Outcome {
+        shell_with(script, &LIMITS)
+    }
+
+    fn shell_with(script: &str, limits: &Limits) -> Outcome {
+        let mut command = Command::new("sh");
+        command.args(["-c", script]);
+        run(command, b"1\n2\n", limits).unwrap()
+    }
+
+    #[test]
+    fn test_protocol() {
+        let solved = Ok(Solution::new("a\\b", "line\nbreak"));
+        let failed = Err("Bad input".to_owned());
+
+        assert_eq!(
+            decode(&encode(&solved)),
+            Some(Outcome::Solved(Solution::new("a\\b", "line\nbreak")))
+        );
+        assert_eq!(
+            decode(&encode(&failed)),
+            Some(Outcome::Failed("Bad input".to_owned()))
+        );
+        assert_eq!(decode("ok\n1\n"), None);
+    }
+
+    #[test]
+    fn test_outcomes() {
+        assert_eq!(
+            shell("read a; read b; printf 'ok\\n%s\\n%s\\n' $a $b"),
+            Outcome::Solved(Solution::new(1, 2))
+        );
+        assert_eq!(
+            shell("printf 'error\\nNo\\n'"),
+            Outcome::Failed("No".to_owned())
+        );
+        assert_eq!(
+            shell("echo 'panicked at src/days/day19.rs' >&2; echo 'Oh no' >&2; exit 101"),
+            Outcome::Crashed("panicked at src/days/day19.rs Oh no".to_owned())
+        );
+        assert_eq!(
+            shell("echo 'memory allocation of 8 bytes failed' >&2; kill -ABRT $$"),
+            Outcome::OutOfMemory
+        );
+        assert_eq!(shell("kill -SEGV $$"), Outcome::Signal(libc::SIGSEGV));
+        assert_eq!(shell("while :; do :; done"), Outcome::CpuTimeExceeded);
+        let timeout = Duration::from_millis(200);
+        assert_eq!(
+            shell_with("sleep 5", &Limits { timeout, ..LIMITS }),
+            Outcome::TimedOut(timeout)
+        );
+    }
+
+    #[test]
+    fn test_parse_limit() {
+        assert_eq!(parse_limit("2", "Memory", 1 << 20).unwrap(), 2 << 20);
+        assert!(parse_limit("0", "Memory", 1).is_err());
+        assert!(parse_limit("lots", "Memory", 1).is_err());
+    }
+}
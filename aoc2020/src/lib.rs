@@ -0,0 +1,316 @@
+//! Advent of Code 2020 solutions, along with the tooling to run, check and fuzz them.
+//!
+//! The `aoc2020` binary is the usual way in. The library exists so that tools outside the crate,
+//! like the fuzz targets, can reach every day through [`get_solvers`].
+use std::fmt;
+use std::str;
+use std::time::Instant;
+
+use anyhow::Context;
+
+#[macro_use]
+pub mod logging;
+
+#[cfg(test)]
+mod allocations;
+mod assignment;
+mod bitset;
+pub mod cache;
+pub mod config;
+#[cfg(test)]
+mod crashes;
+pub mod dashboard;
+mod days;
+#[cfg(test)]
+mod examples;
+pub mod explain;
+pub mod generate;
+mod geometry;
+mod graph;
+mod hash;
+pub mod impls;
+pub mod inputs;
+pub mod isolate;
+pub mod lint;
+pub mod matrix;
+pub mod params;
+pub mod plugins;
+pub mod progress;
+pub mod reference;
+pub mod serve;
+pub mod solution;
+pub mod visualize;
+
+pub use days::{get_alternatives, get_solvers};
+use explain::Explain;
+use generate::Generate;
+use inputs::{Input, SearchPath};
+use lint::Shape;
+use params::Params;
+use reference::Reference;
+use solution::{Solution, Timings};
+use visualize::Visualize;
+
+/// A day's puzzle, solved in stages: parse the input once, then answer each part from the parsed
+/// form. Every `Day` is a [`Solver`] through the blanket implementation below, so days implement
+/// this and tools use `Solver`.
+pub trait Day {
+    /// The input, parsed. It may borrow from the input text.
+    type Parsed<'a>;
+    type Part1: fmt::Display;
+    type Part2: fmt::Display;
+
+    fn day(&self) -> u8;
+    /// Which implementation of the day this is. Only [alternatives](impls) need to override it.
+    fn name(&self) -> &str {
+        BUILTIN
+    }
+    /// Bump this whenever a change could alter the answers, so that [cached](cache) ones are
+    /// recomputed.
+    fn version(&self) -> u32 {
+        1
+    }
+    /// What the input should look like, for [linting](lint).
+    fn shape(&self) -> Shape {
+        Shape::default()
+    }
+    /// Parse `input`, or explain what's wrong with it. Like the parts, this must not panic or run
+    /// forever, however malformed the input.
+    fn parse<'a>(&self, input: &'a str) -> anyhow::Result<Self::Parsed<'a>>;
+    /// Parse `input` straight from the bytes [loaded](inputs::Input) for it. Days that only need
+    /// ASCII can override this to skip checking that the input is UTF-8, and have `parse` call it.
+    fn parse_bytes<'a>(&self, input: &'a [u8]) -> anyhow::Result<Self::Parsed<'a>> {
+        self.parse(as_text(input)?)
+    }
+    /// Parse with some of the day's constants overridden, as examples often need. The overrides
+    /// travel with the parsed input. Days without anything to tune ignore `params`.
+    fn parse_with<'a>(&self, input: &'a str, params: &Params) -> anyhow::Result<Self::Parsed<'a>> {
+        let _ = params;
+        self.parse(input)
+    }
+    /// The keys `parse_with` reads from its params, so that an example can't set one that's
+    /// silently ignored.
+    fn param_keys(&self) -> &[&str] {
+        &[]
+    }
+    fn part1(&self, parsed: &Self::Parsed<'_>) -> anyhow::Result<Self::Part1>;
+    fn part2(&self, parsed: &Self::Parsed<'_>) -> anyhow::Result<Self::Part2>;
+    /// Days that can draw their state return themselves here.
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        None
+    }
+    /// Days that can make up random inputs return themselves here.
+    fn generator(&self) -> Option<&dyn Generate> {
+        None
+    }
+    /// Days with a brute-force solution to check against return themselves here.
+    fn reference(&self) -> Option<&dyn Reference> {
+        None
+    }
+    /// Days that can show their working return themselves here.
+    fn explainer(&self) -> Option<&dyn Explain> {
+        None
+    }
+}
+
+/// The name of each day's default implementation.
+pub const BUILTIN: &str = "builtin";
+
+/// A day with its stages hidden, so that every day can sit in one list.
+pub trait Solver: Send {
+    fn day(&self) -> u8;
+    /// Which implementation of the day this is, for `--impl`.
+    fn name(&self) -> &str {
+        BUILTIN
+    }
+    fn version(&self) -> u32;
+    fn shape(&self) -> Shape;
+    /// Solve both parts, or explain what's wrong with `input`. No input, however malformed, may
+    /// make this panic or run forever.
+    fn solve(&self, input: &str) -> anyhow::Result<Solution> {
+        self.solve_timed(input).map(|(solution, _)| solution)
+    }
+    /// Like [`Solver::solve`], but also report how long each stage took.
+    fn solve_timed(&self, input: &str) -> anyhow::Result<(Solution, Timings)>;
+    /// Like [`Solver::solve`], but from bytes, which needn't be UTF-8 for days that don't care.
+    fn solve_bytes(&self, input: &[u8]) -> anyhow::Result<Solution> {
+        self.solve_bytes_timed(input).map(|(solution, _)| solution)
+    }
+    /// Like [`Solver::solve_timed`], but from bytes.
+    fn solve_bytes_timed(&self, input: &[u8]) -> anyhow::Result<(Solution, Timings)> {
+        self.solve_timed(as_text(input)?)
+    }
+    /// Solve with some of the day's constants overridden, as examples often need. Days without
+    /// anything to tune ignore `params`.
+    fn solve_with(&self, input: &str, params: &Params) -> anyhow::Result<Solution>;
+    /// The keys `solve_with` understands.
+    fn param_keys(&self) -> &[&str];
+    /// Like [`Solver::solve_with`], but answer only `part`, for examples that don't make sense as
+    /// input to the other part.
+    fn solve_part_with(&self, input: &str, params: &Params, part: u8) -> anyhow::Result<String> {
+        let solution = self.solve_with(input, params)?;
+        Ok(match part {
+            1 => solution.part1(),
+            _ => solution.part2(),
+        }
+        .to_owned())
+    }
+    fn visualizer(&self) -> Option<&dyn Visualize>;
+    fn generator(&self) -> Option<&dyn Generate>;
+    fn reference(&self) -> Option<&dyn Reference>;
+    fn explainer(&self) -> Option<&dyn Explain>;
+    /// Read this day's input from the [search path](inputs).
+    fn get_input(&self, search_path: &SearchPath) -> anyhow::Result<Input> {
+        search_path.load(self.day())
+    }
+}
+
+impl<D: Day + Send> Solver for D {
+    fn day(&self) -> u8 {
+        Day::day(self)
+    }
+
+    fn name(&self) -> &str {
+        Day::name(self)
+    }
+
+    fn version(&self) -> u32 {
+        Day::version(self)
+    }
+
+    fn shape(&self) -> Shape {
+        Day::shape(self)
+    }
+
+    fn solve_timed(&self, input: &str) -> anyhow::Result<(Solution, Timings)> {
+        solve_stages(self, || self.parse(input))
+    }
+
+    fn solve_bytes_timed(&self, input: &[u8]) -> anyhow::Result<(Solution, Timings)> {
+        solve_stages(self, || self.parse_bytes(input))
+    }
+
+    fn solve_with(&self, input: &str, params: &Params) -> anyhow::Result<Solution> {
+        let parsed = self.parse_with(input, params)?;
+        Ok(Solution::new(self.part1(&parsed)?, self.part2(&parsed)?))
+    }
+
+    fn param_keys(&self) -> &[&str] {
+        Day::param_keys(self)
+    }
+
+    fn solve_part_with(&self, input: &str, params: &Params, part: u8) -> anyhow::Result<String> {
+        let parsed = self.parse_with(input, params)?;
+        Ok(match part {
+            1 => self.part1(&parsed)?.to_string(),
+            _ => self.part2(&parsed)?.to_string(),
+        })
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        Day::visualizer(self)
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        Day::generator(self)
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        Day::reference(self)
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        Day::explainer(self)
+    }
+}
+
+/// Parse with `parse`, then answer both parts, timing each stage.
+fn solve_stages<'a, D: Day>(
+    day: &D,
+    parse: impl FnOnce() -> anyhow::Result<D::Parsed<'a>>,
+) -> anyhow::Result<(Solution, Timings)> {
+    let start = Instant::now();
+    let parsed = parse()?;
+    let parsed_at = Instant::now();
+    let part1 = day.part1(&parsed)?;
+    let part1_at = Instant::now();
+    let part2 = day.part2(&parsed)?;
+    let part2_at = Instant::now();
+
+    let timings = Timings {
+        parse: parsed_at - start,
+        part1: part1_at - parsed_at,
+        part2: part2_at - part1_at,
+    };
+    Ok((Solution::new(part1, part2), timings))
+}
+
+pub(crate) fn as_text(input: &[u8]) -> anyhow::Result<&str> {
+    str::from_utf8(input).context("The input isn't valid UTF-8")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Sums the numbers in part 1 and multiplies them in part 2, by a factor that can be tuned.
+    struct Toy;
+
+    impl Day for Toy {
+        type Parsed<'a> = (Vec<u32>, u32);
+        type Part1 = u32;
+        type Part2 = u32;
+
+        fn day(&self) -> u8 {
+            26
+        }
+
+        fn parse(&self, input: &str) -> anyhow::Result<(Vec<u32>, u32)> {
+            self.parse_with(input, &Params::new())
+        }
+
+        fn parse_with(&self, input: &str, params: &Params) -> anyhow::Result<(Vec<u32>, u32)> {
+            let numbers = input
+                .split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()?;
+            Ok((numbers, params.get_or("factor", 1)?))
+        }
+
+        fn param_keys(&self) -> &[&str] {
+            &["factor"]
+        }
+
+        fn part1(&self, (numbers, _): &(Vec<u32>, u32)) -> anyhow::Result<u32> {
+            Ok(numbers.iter().sum())
+        }
+
+        fn part2(&self, (numbers, factor): &(Vec<u32>, u32)) -> anyhow::Result<u32> {
+            Ok(numbers.iter().product::<u32>() * factor)
+        }
+    }
+
+    #[test]
+    fn test_blanket_solver() {
+        let solver: Box<dyn Solver> = Box::new(Toy);
+        let mut params = Params::new();
+        params.insert("factor", "10");
+
+        assert_eq!(solver.day(), 26);
+        assert_eq!(solver.solve("2 3 4").unwrap(), (9, 24).into());
+        assert_eq!(
+            solver.solve_with("2 3 4", &params).unwrap(),
+            (9, 240).into()
+        );
+        assert!(solver.solve("2 x").is_err());
+        assert_eq!(solver.solve_bytes(b"2 3 4").unwrap(), (9, 24).into());
+        assert!(solver.solve_bytes(b"2 \xff").is_err());
+
+        let (solution, timings) = solver.solve_timed("5").unwrap();
+        assert_eq!(solution, (5, 5).into());
+        assert_eq!(
+            timings.total(),
+            timings.parse + timings.part1 + timings.part2
+        );
+    }
+}
@@ -0,0 +1,242 @@
+//! Checks an input file against what its day expects, so that a bad download or a stray edit is
+//! caught with a line number instead of surfacing as a confusing error deep in a solver.
+//!
+//! Every file is checked for a byte order mark, non-ASCII bytes, tabs and trailing spaces. On top
+//! of that, each day describes its input's [`Shape`]: how many blank-line-separated blocks it has,
+//! which characters it may contain and whether its lines form a grid.
+use std::fmt;
+
+/// What a day's input looks like. The default allows anything the generic checks do.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Shape {
+    /// How many blank-line-separated blocks the input has, if that's fixed.
+    pub blocks: Option<usize>,
+    /// The only characters allowed on a line, if restricted.
+    pub alphabet: Option<&'static str>,
+    /// Whether every line in a block must be as long as the block's first.
+    pub grid: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Problem {
+    /// 1-based, or `None` if it's about the whole file.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl Problem {
+    fn at(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line: Some(line),
+            message: message.into(),
+        }
+    }
+
+    fn file(message: impl Into<String>) -> Self {
+        Self {
+            line: None,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// Everything wrong with `input` for a day of the given shape, in line order.
+pub fn lint(input: &[u8], shape: &Shape) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    if input.iter().all(u8::is_ascii_whitespace) {
+        problems.push(Problem::file("Input is empty"));
+        return problems;
+    }
+
+    let input = match input.strip_prefix(b"\xef\xbb\xbf") {
+        Some(rest) => {
+            problems.push(Problem::at(1, "Starts with a byte order mark"));
+            rest
+        }
+        None => input,
+    };
+    // Line endings are normalised on reading, so a final `\r` is no problem.
+    let lines: Vec<&[u8]> = input
+        .strip_suffix(b"\n")
+        .unwrap_or(input)
+        .split(|&b| b == b'\n')
+        .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+        .collect();
+
+    for (number, line) in (1..).zip(&lines) {
+        lint_line(number, line, shape, &mut problems);
+    }
+    lint_blocks(&lines, shape, &mut problems);
+
+    problems.sort_by_key(|problem| problem.line.unwrap_or(usize::MAX));
+    problems
+}
+
+fn lint_line(number: usize, line: &[u8], shape: &Shape, problems: &mut Vec<Problem>) {
+    if !line.is_ascii() {
+        problems.push(Problem::at(number, "Contains non-ASCII bytes"));
+    }
+    if line.contains(&b'\t') {
+        problems.push(Problem::at(number, "Contains a tab"));
+    }
+    if line.ends_with(b" ") {
+        problems.push(Problem::at(number, "Has trailing spaces"));
+    }
+
+    if let Some(alphabet) = shape.alphabet {
+        let unexpected = line
+            .iter()
+            .find(|&&b| b.is_ascii() && b != b'\t' && !alphabet.as_bytes().contains(&b));
+        if let Some(&b) = unexpected {
+            problems.push(Problem::at(
+                number,
+                format!("Unexpected character {:?}", char::from(b)),
+            ));
+        }
+    }
+}
+
+fn lint_blocks(lines: &[&[u8]], shape: &Shape, problems: &mut Vec<Problem>) {
+    // Each block as the line number it starts on and its lines.
+    let mut blocks: Vec<(usize, Vec<&[u8]>)> = Vec::new();
+    let mut previous_blank = true;
+    let is_blank = |line: &&[u8]| line.iter().all(u8::is_ascii_whitespace);
+    let end = lines
+        .iter()
+        .rposition(|line| !is_blank(line))
+        .map_or(0, |last| last + 1);
+    for (number, line) in (1..).zip(&lines[..end]) {
+        let blank = is_blank(line);
+        match (blank, previous_blank) {
+            (true, true) => problems.push(Problem::at(number, "Unexpected blank line")),
+            (false, true) => blocks.push((number, vec![line])),
+            (false, false) => blocks.last_mut().unwrap().1.push(line),
+            (true, false) => {}
+        }
+        previous_blank = blank;
+    }
+    match lines.len() - end {
+        0 => {}
+        1 => problems.push(Problem::at(end + 1, "Trailing blank line")),
+        trailing => problems.push(Problem::at(
+            end + 1,
+            format!("{} trailing blank lines", trailing),
+        )),
+    }
+
+    if let Some(expected) = shape.blocks {
+        if blocks.len() != expected {
+            problems.push(Problem::file(format!(
+                "Expected {} blank-line-separated block{}, found {}",
+                expected,
+                if expected == 1 { "" } else { "s" },
+                blocks.len()
+            )));
+        }
+    }
+
+    if shape.grid {
+        for (start, block) in &blocks {
+            let width = block[0].len();
+            for (number, line) in (*start..).zip(block) {
+                if line.len() != width {
+                    problems.push(Problem::at(
+                        number,
+                        format!(
+                            "Grid row is {} characters long, but line {} is {}",
+                            line.len(),
+                            start,
+                            width
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::get_solvers;
+    use crate::inputs::SearchPath;
+
+    fn messages(input: &str, shape: &Shape) -> Vec<String> {
+        lint(input.as_bytes(), shape)
+            .iter()
+            .map(Problem::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_generic() {
+        let input = "\u{feff}1\r\n2 \n\tthree\nfour\u{e9}\n";
+
+        assert_eq!(
+            messages(input, &Shape::default()),
+            vec![
+                "line 1: Starts with a byte order mark",
+                "line 2: Has trailing spaces",
+                "line 3: Contains a tab",
+                "line 4: Contains non-ASCII bytes",
+            ]
+        );
+        assert_eq!(messages("\n\n", &Shape::default()), vec!["Input is empty"]);
+        assert_eq!(
+            messages("1\n\n", &Shape::default()),
+            vec!["line 2: Trailing blank line"]
+        );
+        assert_eq!(
+            messages("1\n\n \n\n", &Shape::default()),
+            vec![
+                "line 2: 3 trailing blank lines",
+                "line 3: Has trailing spaces"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_shape() {
+        let shape = Shape {
+            blocks: Some(3),
+            alphabet: Some(".#"),
+            grid: true,
+        };
+
+        assert!(messages("..#\n#..\n\n##\n..\n\n#\n", &shape).is_empty());
+        assert_eq!(
+            messages("..#\n#.\n\n\n##\n.x", &shape),
+            vec![
+                "line 2: Grid row is 2 characters long, but line 1 is 3",
+                "line 4: Unexpected blank line",
+                "line 6: Unexpected character 'x'",
+                "Expected 3 blank-line-separated blocks, found 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inputs() {
+        let search_path = SearchPath::new(None).unwrap();
+        for solver in get_solvers() {
+            let path = match search_path.find(solver.day()) {
+                Ok(path) => path,
+                Err(_) => {
+                    eprintln!("Skipping day {:02}, which has no input", solver.day());
+                    continue;
+                }
+            };
+            let problems = lint(&std::fs::read(&path).unwrap(), &solver.shape());
+            assert!(problems.is_empty(), "{}: {}", path.display(), problems[0]);
+        }
+    }
+}
@@ -0,0 +1,87 @@
+//! Leveled diagnostics written to stderr, so they never mix with the answers on stdout.
+//!
+//! Nothing is printed unless the verbosity is raised with `-v` (info), `-vv` (debug) or `-vvv`
+//! (trace). Use the [`aoc_info!`], [`aoc_debug!`] and [`aoc_trace!`] macros; their arguments
+//! aren't evaluated when the level is disabled. They're exported for the binary, so they carry a
+//! prefix rather than take `info!` and friends at the crate root.
+use std::fmt;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static VERBOSITY: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info = 1,
+    Debug = 2,
+    Trace = 3,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Info => write!(f, "info"),
+            Self::Debug => write!(f, "debug"),
+            Self::Trace => write!(f, "trace"),
+        }
+    }
+}
+
+/// Set how many `-v` flags were given.
+pub fn set_verbosity(verbosity: u64) {
+    VERBOSITY.store(verbosity.min(u64::from(u8::MAX)) as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= level as u8
+}
+
+#[doc(hidden)]
+pub fn log(level: Level, args: fmt::Arguments<'_>) {
+    eprintln!("[{}] {}", level, args);
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! aoc_log_at {
+    ($level:expr, $($arg:tt)+) => {
+        if $crate::logging::enabled($level) {
+            $crate::logging::log($level, format_args!($($arg)+));
+        }
+    };
+}
+
+/// Log progress worth seeing with `-v`.
+#[macro_export]
+macro_rules! aoc_info {
+    ($($arg:tt)+) => {
+        $crate::aoc_log_at!($crate::logging::Level::Info, $($arg)+)
+    };
+}
+
+/// Log intermediate results worth seeing with `-vv`.
+#[macro_export]
+macro_rules! aoc_debug {
+    ($($arg:tt)+) => {
+        $crate::aoc_log_at!($crate::logging::Level::Debug, $($arg)+)
+    };
+}
+
+/// Log step-by-step traces worth seeing with `-vvv`.
+#[macro_export]
+macro_rules! aoc_trace {
+    ($($arg:tt)+) => {
+        $crate::aoc_log_at!($crate::logging::Level::Trace, $($arg)+)
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_levels() {
+        assert!(Level::Info < Level::Debug);
+        assert!(Level::Debug < Level::Trace);
+        assert_eq!(Level::Debug.to_string(), "debug");
+    }
+}
@@ -1,65 +1,433 @@
-#![feature(array_windows)]
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::net::{Ipv4Addr, TcpListener};
+use std::ops::RangeInclusive;
 use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
 
 use anyhow::{bail, ensure, Context};
-use clap::{App, Arg};
-
-mod days;
-mod solution;
-
-use days::get_solvers;
-use solution::Solution;
-
-pub trait Solver {
-    fn day(&self) -> u8;
-    fn solve(&self, input: &str) -> Solution;
-    fn get_input(&self, directory: Option<&str>) -> anyhow::Result<String> {
-        let path = input_path(self.day(), directory);
-        let input_string = match std::fs::read_to_string(path) {
-            Ok(inp) => add_newline(inp.replace("\r", "")),
-            Err(error) => bail!("Error while reading input file: {}", error),
+use clap::{App, AppSettings, Arg, ArgGroup, ArgMatches, SubCommand};
+
+use aoc2020::cache::Cache;
+use aoc2020::explain::Explanation;
+use aoc2020::generate::Rng;
+use aoc2020::impls::{self, Registry};
+use aoc2020::inputs::{Answers, Input, SearchPath};
+use aoc2020::isolate::{self, Limits, Outcome};
+use aoc2020::progress::{self, Style};
+use aoc2020::solution::Solution;
+use aoc2020::visualize::FrameWriter;
+use aoc2020::{
+    aoc_info, dashboard, get_solvers, lint, logging, matrix, plugins, reference, serve, Solver,
+};
+
+fn solve(
+    solver: &dyn Solver,
+    search_path: &SearchPath,
+    cache: Option<&Cache>,
+    explain_answers: bool,
+    visualize_directory: Option<&str>,
+) -> anyhow::Result<()> {
+    let inp = &load_mapped(solver, search_path)?;
+    aoc_info!("Day {:02}: read {} bytes of input", solver.day(), inp.len());
+    let solution = solve_cached(solver, inp.as_bytes(), cache)?;
+
+    println!("Day {:02}\n{}", solver.day(), solution);
+    check_answers(solver.day(), &solution, &search_path.answers()?);
+    if explain_answers && !explain(solver, inp.as_str()?)? {
+        eprintln!("Day {:02} has no explanation to give", solver.day());
+    }
+
+    if let Some(dir) = visualize_directory {
+        visualize(solver, inp.as_str()?, dir)?;
+    }
+
+    Ok(())
+}
+
+fn solve_all(
+    solvers: Vec<Box<dyn Solver>>,
+    search_path: &SearchPath,
+    cache: Option<&Cache>,
+    explain_answers: bool,
+    visualize_directory: Option<&str>,
+) -> anyhow::Result<()> {
+    let answers = search_path.answers()?;
+
+    for solver in &solvers {
+        let input = load_mapped(&**solver, search_path)?;
+        aoc_info!(
+            "Day {:02}: read {} bytes of input",
+            solver.day(),
+            input.len()
+        );
+
+        let solution = solve_cached(&**solver, input.as_bytes(), cache)?;
+
+        println!("Day {:02}\n{}", solver.day(), solution);
+        check_answers(solver.day(), &solution, &answers);
+        if explain_answers {
+            explain(&**solver, input.as_str()?)?;
+        }
+
+        if let Some(dir) = visualize_directory {
+            visualize(&**solver, input.as_str()?, dir)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Load `solver`'s input for a one-shot solve, mapping it if it's large.
+fn load_mapped(solver: &dyn Solver, search_path: &SearchPath) -> anyhow::Result<Input> {
+    // Safety: the input is dropped as soon as the day is done, and nothing else in this process
+    // writes to it. Editing it from outside during that time is the one hazard left, and is the
+    // user's to avoid.
+    unsafe { search_path.load_mapped(solver.day()) }
+}
+
+/// Solve `input`, or take the answer from `cache` if it's there.
+fn solve_cached(
+    solver: &dyn Solver,
+    input: &[u8],
+    cache: Option<&Cache>,
+) -> anyhow::Result<Solution> {
+    if let Some(solution) = cache.and_then(|cache| cache.get(solver, input)) {
+        aoc_info!("Day {:02}: answer from cache", solver.day());
+        return Ok(solution);
+    }
+
+    let (solution, timings) = solver
+        .solve_bytes_timed(input)
+        .with_context(|| format!("Unable to solve day {:02}", solver.day()))?;
+    aoc_info!("Day {:02}: {}", solver.day(), timings);
+
+    if let Some(cache) = cache {
+        if let Err(error) = cache.put(solver, input, &solution) {
+            eprintln!(
+                "Day {:02}: unable to cache the answer: {:#}",
+                solver.day(),
+                error
+            );
+        }
+    }
+
+    Ok(solution)
+}
+
+/// Write how the day reached its answers to stderr, keeping stdout for the answers themselves.
+/// Returns whether the day had anything to say.
+fn explain(solver: &dyn Solver, input: &str) -> anyhow::Result<bool> {
+    let explainer = match solver.explainer() {
+        Some(explainer) => explainer,
+        None => return Ok(false),
+    };
+
+    let mut explanation = Explanation::new();
+    explainer
+        .explain(input, &mut explanation)
+        .with_context(|| format!("Unable to explain day {:02}", solver.day()))?;
+    eprint!("Day {:02} explained\n{}", solver.day(), explanation);
+    Ok(true)
+}
+
+/// Warn about any part that doesn't match the profile's recorded answer.
+fn check_answers(day: u8, solution: &Solution, answers: &Answers) {
+    for part in answers.check(day, solution).unwrap_or_default() {
+        eprintln!(
+            "Day {:02}: part {} doesn't match the recorded answer {}",
+            day,
+            part,
+            answers.get(day, part).unwrap_or_default()
+        );
+    }
+}
+
+fn matrix(solvers: &[Box<dyn Solver>], search_path: &SearchPath) -> anyhow::Result<()> {
+    let matrix = matrix::run(solvers, search_path)?;
+    print!("{}", matrix);
+
+    let failures = matrix.failures();
+    ensure!(failures == 0, "{} day and profile pairs failed", failures);
+    Ok(())
+}
+
+/// The built-in implementations, along with any plugins.
+fn load_registry(plugin_dir: Option<&str>) -> anyhow::Result<Registry> {
+    let plugin_dir = plugins::plugin_dir(plugin_dir)?;
+    let plugins = plugins::load_dir(&plugin_dir)?;
+    aoc_info!(
+        "Loaded {} plugins from {}",
+        plugins.len(),
+        plugin_dir.display()
+    );
+
+    let mut registry = Registry::builtin();
+    registry.add_plugins(plugins)?;
+    Ok(registry)
+}
+
+fn list_impls(registry: &Registry) {
+    for day in 1..=25 {
+        let names: Vec<&str> = registry.day(day).iter().map(|i| i.name()).collect();
+        if !names.is_empty() {
+            println!("Day {:02}: {}", day, names.join(", "));
+        }
+    }
+}
+
+/// Solve each day's input with every implementation of the day, and fail if any disagree.
+fn compare_impls(
+    registry: &Registry,
+    days: RangeInclusive<u8>,
+    search_path: &SearchPath,
+) -> anyhow::Result<()> {
+    let mut disagreements = 0;
+    for day in days {
+        let implementations = registry.day(day);
+        let input = match implementations.first() {
+            Some(implementation) => implementation.get_input(search_path)?,
+            None => continue,
         };
 
-        Ok(input_string)
+        let comparison = impls::compare(&implementations, input.as_str()?);
+        print!("{}", comparison);
+        if !comparison.agree() {
+            disagreements += 1;
+        }
     }
+
+    ensure!(
+        disagreements == 0,
+        "Implementations disagree on {} days",
+        disagreements
+    );
+    Ok(())
 }
 
-fn input_path(day: u8, directory: Option<&str>) -> std::path::PathBuf {
-    if let Some(dir) = directory {
-        Path::new(dir).join(format!("day{:02}.txt", day))
-    } else {
-        Path::new("./input").join(format!("day{:02}.txt", day))
+/// Solve each day in a child process under `limits`, reporting the days that fail instead of
+/// stopping at them.
+fn solve_isolated(
+    solvers: &[Box<dyn Solver>],
+    search_path: &SearchPath,
+    limits: &Limits,
+    args: &ArgMatches<'_>,
+) -> anyhow::Result<()> {
+    let answers = search_path.answers()?;
+    let exe = env::current_exe().context("Unable to find the aoc2020 binary")?;
+
+    let mut failures = 0;
+    for solver in solvers {
+        let input = solver.get_input(search_path)?;
+        let mut command = Command::new(&exe);
+        for flag in &["impl", "plugin-dir"] {
+            if let Some(value) = args.value_of(flag) {
+                command.arg(format!("--{}", flag)).arg(value);
+            }
+        }
+        command
+            .arg(isolate::CHILD_COMMAND)
+            .arg("--day")
+            .arg(solver.day().to_string());
+
+        aoc_info!("Day {:02}: solving in a child process", solver.day());
+        match isolate::run(command, input.as_bytes(), limits)? {
+            Outcome::Solved(solution) => {
+                println!("Day {:02}\n{}", solver.day(), solution);
+                check_answers(solver.day(), &solution, &answers);
+            }
+            outcome => {
+                failures += 1;
+                println!("Day {:02}: {}", solver.day(), outcome);
+            }
+        }
     }
+
+    ensure!(
+        failures == 0,
+        "{} of {} days failed",
+        failures,
+        solvers.len()
+    );
+    Ok(())
 }
 
-fn add_newline(mut inp: String) -> String {
-    if inp.as_bytes().last() != Some(&b'\n') {
-        inp.push('\n');
+fn lint(
+    solvers: &[Box<dyn Solver>],
+    search_path: &SearchPath,
+    args: &ArgMatches<'_>,
+) -> anyhow::Result<()> {
+    let solvers = match args.value_of("day") {
+        Some(day) => {
+            let day = day.parse::<usize>().context("Day is not a valid number")?;
+            ensure!(
+                1 <= day && day <= solvers.len(),
+                "Day out of range. It must be between 1 and {}.",
+                solvers.len()
+            );
+            &solvers[day - 1..day]
+        }
+        None => solvers,
     };
 
-    inp
+    let mut failures = 0;
+    for solver in solvers {
+        let path = match search_path.find(solver.day()) {
+            Ok(path) => path,
+            Err(_) if args.is_present("all") => {
+                println!("Day {:02}: no input", solver.day());
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        let input =
+            fs::read(&path).with_context(|| format!("Unable to read {}", path.display()))?;
+
+        let problems = lint::lint(&input, &solver.shape());
+        if problems.is_empty() {
+            println!("Day {:02}: ok", solver.day());
+        } else {
+            failures += 1;
+            println!("Day {:02}: {}", solver.day(), path.display());
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
+
+    ensure!(
+        failures == 0,
+        "Problems found in {} of {} inputs",
+        failures,
+        solvers.len()
+    );
+    Ok(())
 }
 
-fn solve(solver: &dyn Solver, input_directory: Option<&str>) -> anyhow::Result<()> {
-    let inp = &solver.get_input(input_directory)?;
-    let solution = solver.solve(&inp);
+fn serve(solvers: Vec<Box<dyn Solver>>, args: &ArgMatches<'_>) -> anyhow::Result<()> {
+    let port = args
+        .value_of("port")
+        .unwrap()
+        .parse::<u16>()
+        .context("Port is not a valid number")?;
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))
+        .with_context(|| format!("Unable to listen on port {}", port))?;
 
-    println!("Day {:02}\n{}", solver.day(), solution);
+    eprintln!("Serving on http://{}", listener.local_addr()?);
+    serve::serve(solvers, listener)
+}
+
+fn visualize(solver: &dyn Solver, input: &str, directory: &str) -> anyhow::Result<()> {
+    let visualizer = match solver.visualizer() {
+        Some(visualizer) => visualizer,
+        None => return Ok(()),
+    };
+
+    let directory = Path::new(directory).join(format!("day{:02}", solver.day()));
+    let mut frames = FrameWriter::new(&directory)
+        .with_context(|| format!("Unable to create {}", directory.display()))?;
+    visualizer
+        .visualize(input, &mut frames)
+        .with_context(|| format!("Unable to write frames to {}", directory.display()))?;
+
+    eprintln!("Wrote frames to {}", directory.display());
 
     Ok(())
 }
 
-fn solve_all(solvers: Vec<Box<dyn Solver>>, input_directory: Option<&str>) -> anyhow::Result<()> {
-    let mut inputs = Vec::new();
+fn generate(solvers: &[Box<dyn Solver>], args: &ArgMatches<'_>) -> anyhow::Result<()> {
+    let day = args
+        .value_of("day")
+        .unwrap()
+        .parse::<usize>()
+        .context("Day is not a valid number")?;
+    ensure!(
+        1 <= day && day <= solvers.len(),
+        "Day out of range. It must be between 1 and {}.",
+        solvers.len()
+    );
+    let seed = args
+        .value_of("seed")
+        .unwrap()
+        .parse::<u64>()
+        .context("Seed is not a valid number")?;
 
-    for solver in &solvers {
-        let input = solver.get_input(input_directory)?;
+    let generator = match solvers[day - 1].generator() {
+        Some(generator) => generator,
+        None => bail!("Day {:02} has no input generator", day),
+    };
+    let size = match args.value_of("size") {
+        Some(size) => size
+            .parse::<usize>()
+            .context("Size is not a valid number")?,
+        None => generator.default_size(),
+    };
 
-        let solution = solver.solve(&input);
+    aoc_info!(
+        "Day {:02}: generating input of size {} with seed {}",
+        day,
+        size,
+        seed
+    );
+    print!("{}", generator.generate(&mut Rng::new(seed), size));
 
-        println!("Day {:02}\n{}", solver.day(), solution);
+    Ok(())
+}
 
-        inputs.push(input);
+fn differ(solvers: &[Box<dyn Solver>], args: &ArgMatches<'_>) -> anyhow::Result<()> {
+    let seed = match args.value_of("seed") {
+        Some(seed) => seed.parse::<u64>().context("Seed is not a valid number")?,
+        None => 0,
+    };
+    let runs = match args.value_of("runs") {
+        Some(runs) => runs.parse::<u64>().context("Runs is not a valid number")?,
+        None => 100,
+    };
+    let size = match args.value_of("size") {
+        Some(size) => Some(
+            size.parse::<usize>()
+                .context("Size is not a valid number")?,
+        ),
+        None => None,
+    };
+    let solvers = match args.value_of("day") {
+        Some(day) => {
+            let day = day.parse::<usize>().context("Day is not a valid number")?;
+            ensure!(
+                1 <= day && day <= solvers.len(),
+                "Day out of range. It must be between 1 and {}.",
+                solvers.len()
+            );
+            &solvers[day - 1..day]
+        }
+        None => solvers,
+    };
+
+    for solver in solvers {
+        let (reference, generator) = match (solver.reference(), solver.generator()) {
+            (Some(reference), Some(generator)) => (reference, generator),
+            _ if args.is_present("day") => {
+                bail!("Day {:02} has no reference solution", solver.day())
+            }
+            _ => continue,
+        };
+        let size = size.unwrap_or_else(|| generator.default_size());
+
+        aoc_info!(
+            "Day {:02}: checking {} inputs of size {} from seed {}",
+            solver.day(),
+            runs,
+            size,
+            seed
+        );
+        if let Err(mismatch) =
+            reference::differ(&**solver, reference, generator, seed..seed + runs, size)
+        {
+            bail!("{}", mismatch);
+        }
+        println!("Day {:02}: {} inputs agree", solver.day(), runs);
     }
 
     Ok(())
@@ -73,6 +441,15 @@ fn run() -> anyhow::Result<()> {
                 .short("a")
                 .help("Solve all days"),
         )
+        .arg(
+            Arg::with_name("compare-impls")
+                .long("compare-impls")
+                .conflicts_with("impl")
+                .help(
+                    "Solve with every implementation of the day instead, and report whether they \
+                     agree and how long each took.",
+                ),
+        )
         .arg(
             Arg::with_name("day")
                 .long("day")
@@ -80,38 +457,329 @@ fn run() -> anyhow::Result<()> {
                 .takes_value(true)
                 .help("Solve a single day."),
         )
+        .arg(
+            Arg::with_name("explain")
+                .long("explain")
+                .help("Show how each answer was reached, on stderr. Not every day can."),
+        )
+        .arg(
+            Arg::with_name("impl")
+                .long("impl")
+                .takes_value(true)
+                .value_name("NAME")
+                .help(
+                    "Use the implementation NAME for the days that have one, and the default one \
+                     for the rest.",
+                ),
+        )
         .arg(
             Arg::with_name("input-directory")
                 .long("input-directory")
                 .alias("input-dir")
                 .takes_value(true)
                 .value_name("DIR")
-                .help("Directory for inputs."),
+                .help(
+                    "Directory for inputs. Searched before $AOC_INPUT_DIR, the config file and the \
+                     crate's input/ directory.",
+                ),
+        )
+        .arg(
+            Arg::with_name("isolate")
+                .long("isolate")
+                .conflicts_with_all(&["compare-impls", "explain", "visualize"])
+                .help(
+                    "Solve each day in a child process with limited memory and CPU time, so that \
+                     one running away doesn't stop the rest. Ignores the answer cache.",
+                ),
+        )
+        .arg(
+            Arg::with_name("memory-limit")
+                .long("memory-limit")
+                .takes_value(true)
+                .value_name("MB")
+                .default_value("2048")
+                .help("With --isolate, how much memory each day may use."),
+        )
+        .arg(
+            Arg::with_name("cpu-limit")
+                .long("cpu-limit")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .default_value("60")
+                .help(
+                    "With --isolate, how much CPU time each day may use. A day is also stopped \
+                     after twice this long in real time.",
+                ),
+        )
+        .arg(
+            Arg::with_name("no-cache")
+                .long("no-cache")
+                .help("Solve every day again, ignoring and not updating the answer cache."),
+        )
+        .arg(
+            Arg::with_name("plugin-dir")
+                .long("plugin-dir")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Directory to load plugins from, instead of the config file's or plugins/."),
+        )
+        .arg(
+            Arg::with_name("profile")
+                .long("profile")
+                .short("p")
+                .takes_value(true)
+                .value_name("NAME")
+                .help("Use the inputs in <input dir>/NAME instead of the default ones."),
+        )
+        .arg(
+            Arg::with_name("verbose")
+                .short("v")
+                .multiple(true)
+                .help("Log diagnostics to stderr. Repeat for more detail (-vv, -vvv)."),
+        )
+        .arg(
+            Arg::with_name("visualize")
+                .long("visualize")
+                .takes_value(true)
+                .value_name("DIR")
+                .help("Write frames for days that support it into DIR/dayNN."),
+        )
+        .subcommand(
+            SubCommand::with_name("matrix")
+                .about("Solve every day with every profile's input and check the recorded answers"),
+        )
+        .subcommand(
+            SubCommand::with_name("impls")
+                .about("List each day's implementations, built in and from plugins, default first"),
+        )
+        .subcommand(
+            SubCommand::with_name("lint")
+                .about("Check inputs for problems that would trip up their solvers")
+                .arg(
+                    Arg::with_name("day")
+                        .long("day")
+                        .short("d")
+                        .takes_value(true)
+                        .help("Check a single day's input."),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .short("a")
+                        .help("Check every day's input, skipping days without one."),
+                )
+                .group(
+                    ArgGroup::with_name("days")
+                        .args(&["day", "all"])
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name(isolate::CHILD_COMMAND)
+                .setting(AppSettings::Hidden)
+                .about("Solve the input on stdin for --isolate")
+                .arg(
+                    Arg::with_name("day")
+                        .long("day")
+                        .takes_value(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("tui")
+                .about("Run and inspect every day from an interactive dashboard"),
+        )
+        .subcommand(
+            SubCommand::with_name("serve")
+                .about("Answer solve requests over HTTP on localhost")
+                .arg(
+                    Arg::with_name("port")
+                        .long("port")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Port to listen on. 0 picks a free one."),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("gen")
+                .about("Print a random input for a day")
+                .arg(
+                    Arg::with_name("day")
+                        .long("day")
+                        .short("d")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Day to generate an input for."),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .short("s")
+                        .takes_value(true)
+                        .required(true)
+                        .help("Seed for the random generator. The same seed gives the same input."),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .short("k")
+                        .takes_value(true)
+                        .help(
+                            "How big the input should be. Defaults to about the real input's size.",
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("diff")
+                .about("Check solvers against their reference solutions on random inputs")
+                .arg(
+                    Arg::with_name("day")
+                        .long("day")
+                        .short("d")
+                        .takes_value(true)
+                        .help("Check a single day. Defaults to every day with a reference."),
+                )
+                .arg(
+                    Arg::with_name("seed")
+                        .long("seed")
+                        .short("s")
+                        .takes_value(true)
+                        .help("First seed to generate inputs from. Defaults to 0."),
+                )
+                .arg(
+                    Arg::with_name("runs")
+                        .long("runs")
+                        .short("n")
+                        .takes_value(true)
+                        .help("How many inputs to check per day. Defaults to 100."),
+                )
+                .arg(
+                    Arg::with_name("size")
+                        .long("size")
+                        .short("k")
+                        .takes_value(true)
+                        .help("Size of each input. Defaults to about the real input's size."),
+                ),
         )
         .get_matches();
 
-    let input_dir = args.value_of("input-directory");
+    logging::set_verbosity(args.occurrences_of("verbose"));
+
+    let search_path = SearchPath::new(args.value_of("input-directory"))?;
+    let visualize_dir = args.value_of("visualize");
+    let explain_answers = args.is_present("explain");
+    let mut solvers = get_solvers();
+    if args.subcommand_matches("impls").is_some() {
+        list_impls(&load_registry(args.value_of("plugin-dir"))?);
+        return Ok(());
+    }
+    if let Some(name) = args.value_of("impl") {
+        solvers = load_registry(args.value_of("plugin-dir"))?.select(name)?;
+    }
+
+    if let Some(args) = args.subcommand_matches(isolate::CHILD_COMMAND) {
+        let day = args.value_of("day").unwrap().parse::<usize>()?;
+        ensure!(1 <= day && day <= solvers.len(), "Day out of range");
+        return isolate::child(&*solvers[day - 1]);
+    }
+    if args.subcommand_matches("matrix").is_some() {
+        return matrix(&solvers, &search_path);
+    }
+    let search_path = search_path.with_profile(args.value_of("profile"))?;
+
+    if let Some(args) = args.subcommand_matches("lint") {
+        return lint(&solvers, &search_path, args);
+    }
+    if args.subcommand_matches("tui").is_some() {
+        return dashboard::run(solvers, search_path);
+    }
+    if let Some(args) = args.subcommand_matches("serve") {
+        return serve(solvers, args);
+    }
+    if let Some(args) = args.subcommand_matches("gen") {
+        return generate(&solvers, args);
+    }
+    if let Some(args) = args.subcommand_matches("diff") {
+        return differ(&solvers, args);
+    }
+
+    let day = match args.value_of("day") {
+        Some(day) => {
+            let day = day.parse::<usize>().context("Day is not a valid number")?;
+            ensure!(
+                1 <= day && day <= solvers.len(),
+                "Day out of range. It must be between 1 and {}.",
+                solvers.len()
+            );
+            day
+        }
+        None => solvers.len(),
+    };
+
+    // Only now, since the dashboard owns the terminal and the server has no one watching.
+    progress::set_style(if io::stderr().is_terminal() {
+        Style::Bar
+    } else {
+        Style::Lines
+    });
+
+    if args.is_present("compare-impls") {
+        let registry = load_registry(args.value_of("plugin-dir"))?;
+        let days = if args.is_present("all") {
+            1..=solvers.len() as u8
+        } else {
+            day as u8..=day as u8
+        };
+        return compare_impls(&registry, days, &search_path);
+    }
 
-    let solvers = get_solvers();
+    if args.is_present("isolate") {
+        let cpu_time = isolate::parse_limit(args.value_of("cpu-limit").unwrap(), "CPU limit", 1)?;
+        let limits = Limits {
+            memory: isolate::parse_limit(
+                args.value_of("memory-limit").unwrap(),
+                "Memory limit",
+                1 << 20,
+            )?,
+            cpu_time: Duration::from_secs(cpu_time),
+            timeout: Duration::from_secs(cpu_time * 2),
+        };
+        let solvers = if args.is_present("all") {
+            &solvers[..]
+        } else {
+            &solvers[day - 1..day]
+        };
+        return solve_isolated(solvers, &search_path, &limits, &args);
+    }
 
+    // Only now, as nothing above reads or writes answers.
+    let cache = if args.is_present("no-cache") {
+        None
+    } else {
+        Cache::open()?
+    };
     if args.is_present("all") {
-        solve_all(solvers, input_dir)
-    } else if let Some(day) = args.value_of("day") {
-        let day = day.parse::<usize>().context("Day is not a valid number")?;
-        ensure!(
-            1 <= day && day <= solvers.len(),
-            "Day out of range. It must be between 1 and {}.",
-            solvers.len()
-        );
-        solve(&*solvers[day - 1], input_dir)
+        solve_all(
+            solvers,
+            &search_path,
+            cache.as_ref(),
+            explain_answers,
+            visualize_dir,
+        )
     } else {
-        let day = solvers.len() - 1;
-        solve(&*solvers[day], input_dir)
+        solve(
+            &*solvers[day - 1],
+            &search_path,
+            cache.as_ref(),
+            explain_answers,
+            visualize_dir,
+        )
     }
 }
 
 fn main() {
     if let Err(error) = run() {
-        eprintln!("{}", error);
+        eprintln!("{:#}", error);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,250 @@
+//! Runs every day against every input profile and checks each against its recorded answers. Some
+//! bugs only show up on someone else's input.
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::ensure;
+
+use crate::inputs::{Answers, SearchPath};
+use crate::Solver;
+
+/// The name the profile without one goes by in the grid, so no profile may have it.
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub enum Cell {
+    Pass,
+    /// The parts that didn't match the recorded answers.
+    Fail(Vec<u8>),
+    Error(String),
+    /// Solved, but there's no recorded answer to check against.
+    Unchecked,
+    /// The profile has no input for the day.
+    Missing,
+}
+
+impl Cell {
+    fn is_failure(&self) -> bool {
+        matches!(self, Self::Fail(_) | Self::Error(_))
+    }
+}
+
+impl fmt::Display for Cell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Pass => "ok".to_owned(),
+            Self::Fail(parts) => {
+                let parts: Vec<String> = parts.iter().map(u8::to_string).collect();
+                format!("FAIL {}", parts.join(","))
+            }
+            Self::Error(_) => "ERROR".to_owned(),
+            Self::Unchecked => "?".to_owned(),
+            Self::Missing => "-".to_owned(),
+        };
+        f.pad(&text)
+    }
+}
+
+pub struct Matrix {
+    profiles: Vec<String>,
+    /// One row per day, one cell per profile.
+    rows: Vec<(u8, Vec<Cell>)>,
+}
+
+impl Matrix {
+    pub fn failures(&self) -> usize {
+        self.rows
+            .iter()
+            .flat_map(|(_, cells)| cells)
+            .filter(|cell| cell.is_failure())
+            .count()
+    }
+}
+
+impl fmt::Display for Matrix {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let widths: Vec<usize> = self
+            .profiles
+            .iter()
+            .map(|profile| profile.len().max("FAIL 1,2".len()))
+            .collect();
+
+        let mut header = String::from("Day");
+        for (profile, width) in self.profiles.iter().zip(&widths) {
+            header += &format!("  {:width$}", profile, width = width);
+        }
+        writeln!(f, "{}", header.trim_end())?;
+
+        for (day, cells) in &self.rows {
+            let mut row = format!(" {:02}", day);
+            for (cell, width) in cells.iter().zip(&widths) {
+                row += &format!("  {:width$}", cell, width = width);
+            }
+            writeln!(f, "{}", row.trim_end())?;
+        }
+
+        for (day, cells) in &self.rows {
+            for (cell, profile) in cells.iter().zip(&self.profiles) {
+                if let Cell::Error(error) = cell {
+                    writeln!(f, "Day {:02} ({}): {}", day, profile, error)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Solve every day with every profile's input on `search_path`. The default profile gets a column
+/// only if it has any inputs.
+pub fn run(solvers: &[Box<dyn Solver>], search_path: &SearchPath) -> anyhow::Result<Matrix> {
+    let mut columns = Vec::new();
+    if solvers
+        .iter()
+        .any(|solver| search_path.find(solver.day()).is_ok())
+    {
+        columns.push((DEFAULT_PROFILE.to_owned(), search_path.clone()));
+    }
+    for profile in search_path.profiles() {
+        ensure!(
+            profile != DEFAULT_PROFILE,
+            "A profile can't be called '{}', which names the inputs outside any profile",
+            DEFAULT_PROFILE
+        );
+        let profile_path = search_path.clone().with_profile(Some(&profile))?;
+        columns.push((profile, profile_path));
+    }
+
+    let mut answers = Vec::new();
+    for (_, profile_path) in &columns {
+        answers.push(profile_path.answers()?);
+    }
+
+    let rows = solvers
+        .iter()
+        .map(|solver| {
+            let day = solver.day();
+            let cells = columns
+                .iter()
+                .zip(&answers)
+                .map(|((profile, profile_path), answers)| {
+                    cell(&**solver, profile, profile_path, answers)
+                })
+                .collect();
+            (day, cells)
+        })
+        .collect();
+
+    Ok(Matrix {
+        profiles: columns.into_iter().map(|(profile, _)| profile).collect(),
+        rows,
+    })
+}
+
+/// How `solver` fares on `profile`'s input. A missing input isn't a failure, but one that can't be
+/// loaded is, as is a solver that panics on it.
+fn cell(solver: &dyn Solver, profile: &str, profile_path: &SearchPath, answers: &Answers) -> Cell {
+    let day = solver.day();
+    if profile_path.find(day).is_err() {
+        return Cell::Missing;
+    }
+    let input = match profile_path.load(day) {
+        Ok(input) => input,
+        Err(error) => return Cell::Error(format!("{:#}", error)),
+    };
+
+    aoc_info!("Day {:02}: solving with profile {}", day, profile);
+    match panic::catch_unwind(AssertUnwindSafe(|| solver.solve_bytes(input.as_bytes()))) {
+        Ok(Ok(solution)) => match answers.check(day, &solution) {
+            Some(wrong) if wrong.is_empty() => Cell::Pass,
+            Some(wrong) => Cell::Fail(wrong),
+            None => Cell::Unchecked,
+        },
+        Ok(Err(error)) => Cell::Error(format!("{:#}", error)),
+        Err(_) => Cell::Error("Solver panicked".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Config;
+    use crate::Day;
+    use std::env;
+    use std::fs;
+
+    /// Panics on any input with more than one line.
+    struct Fragile;
+
+    impl Day for Fragile {
+        type Parsed<'a> = &'a str;
+        type Part1 = usize;
+        type Part2 = usize;
+
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn parse<'a>(&self, input: &'a str) -> anyhow::Result<&'a str> {
+            Ok(input)
+        }
+
+        fn part1(&self, input: &&str) -> anyhow::Result<usize> {
+            assert!(input.lines().count() < 2);
+            Ok(input.len())
+        }
+
+        fn part2(&self, _: &&str) -> anyhow::Result<usize> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let dir = env::temp_dir().join(format!("aoc2020-matrix-{}", std::process::id()));
+        fs::create_dir_all(dir.join("alice")).unwrap();
+        fs::create_dir_all(dir.join("bob")).unwrap();
+        fs::write(dir.join("alice").join("day01.txt"), "1\n").unwrap();
+        fs::write(dir.join("bob").join("day01.txt"), "1\n2\n").unwrap();
+        let search_path = SearchPath::build(Some(dir.clone()), None, &Config::default());
+        let solvers: Vec<Box<dyn Solver>> = vec![Box::new(Fragile)];
+
+        let matrix = run(&solvers, &search_path).unwrap();
+        fs::create_dir_all(dir.join(DEFAULT_PROFILE)).unwrap();
+        fs::write(dir.join(DEFAULT_PROFILE).join("day01.txt"), "1\n").unwrap();
+        let clash = run(&solvers, &search_path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // The crate's own inputs may add a default column in front.
+        let cells = &matrix.rows[0].1;
+        assert!(matrix
+            .profiles
+            .ends_with(&["alice".to_owned(), "bob".to_owned()]));
+        assert!(matches!(
+            cells[cells.len() - 2..],
+            [Cell::Unchecked, Cell::Error(_)]
+        ));
+        assert!(clash.is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let matrix = Matrix {
+            profiles: vec![DEFAULT_PROFILE.to_owned(), "bob".to_owned()],
+            rows: vec![
+                (1, vec![Cell::Pass, Cell::Fail(vec![2])]),
+                (2, vec![Cell::Unchecked, Cell::Error("Bad".to_owned())]),
+                (3, vec![Cell::Missing, Cell::Pass]),
+            ],
+        };
+
+        let expected = "\
+Day  default   bob
+ 01  ok        FAIL 2
+ 02  ?         ERROR
+ 03  -         ok
+Day 02 (bob): Bad
+";
+        assert_eq!(matrix.to_string(), expected);
+        assert_eq!(matrix.failures(), 2);
+    }
+}
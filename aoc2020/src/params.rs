@@ -0,0 +1,55 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::str::FromStr;
+
+use anyhow::Context;
+
+/// Named settings that override a day's puzzle constants, e.g. the smaller preamble an example
+/// uses.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.0.keys().map(String::as_str)
+    }
+
+    /// Parse the value of `key`, or use `default` if it isn't set.
+    pub fn get_or<T>(&self, key: &str, default: T) -> anyhow::Result<T>
+    where
+        T: FromStr,
+        T::Err: Error + Send + Sync + 'static,
+    {
+        match self.0.get(key) {
+            Some(value) => value
+                .parse()
+                .with_context(|| format!("Invalid value for '{}': '{}'", key, value)),
+            None => Ok(default),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_or() {
+        let mut params = Params::new();
+        params.insert("window", "5");
+
+        assert_eq!(params.get_or("window", 25).unwrap(), 5);
+        assert_eq!(params.get_or("moves", 100).unwrap(), 100);
+
+        params.insert("window", "five");
+        assert!(params.get_or("window", 25).is_err());
+    }
+}
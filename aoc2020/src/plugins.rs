@@ -0,0 +1,350 @@
+//! Solvers loaded from shared libraries, so that alternative solutions can be tried without
+//! touching the crate.
+//!
+//! A plugin is a shared library (`.so`, `.dylib` or `.dll`) in the plugin directory that exports
+//! `aoc2020_plugin`, a function returning a [`PluginApi`]. `plugins/aoc2020_plugin.h` declares
+//! the same interface for C, and `plugins/example` is a plugin written in Rust. Each plugin
+//! solves one day under an implementation name, and `--impl <name>` swaps it in for the built-in
+//! solver of that day. See [`crate::impls`].
+//!
+//! The plugin directory is the `--plugin-dir` flag, otherwise `plugin-dir` in the
+//! [config file](crate::config), otherwise `plugins/` next to the crate's `Cargo.toml`.
+use std::ffi::CStr;
+use std::fmt;
+use std::fs;
+use std::os::raw::c_char;
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::Instant;
+
+use anyhow::{bail, ensure, Context};
+use libloading::Library;
+
+use crate::config::Config;
+use crate::explain::Explain;
+use crate::generate::Generate;
+use crate::lint::Shape;
+use crate::params::Params;
+use crate::reference::Reference;
+use crate::solution::{Solution, Timings};
+use crate::visualize::Visualize;
+use crate::{Solver, BUILTIN};
+
+/// Bumped whenever [`PluginApi`] changes, so that old plugins are refused rather than misread.
+pub const ABI_VERSION: u32 = 1;
+
+/// The function every plugin exports.
+const ENTRY_POINT: &[u8] = b"aoc2020_plugin\0";
+
+const MANIFEST_PLUGIN_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/plugins");
+
+/// What a plugin hands over, mirroring [`Solver::day`] and [`Solver::solve`]. It must stay valid
+/// for as long as the library is loaded, and its functions may be called from any thread, though
+/// never from two at once.
+#[repr(C)]
+pub struct PluginApi {
+    /// Always [`ABI_VERSION`].
+    pub abi: u32,
+    /// The implementation's name, as given to `--impl`. Only ASCII letters, digits, `_` and `-`,
+    /// as it ends up in cache file names.
+    pub name: *const c_char,
+    /// Like [`Solver::version`], bumped whenever the answers could change.
+    pub version: u32,
+    pub day: extern "C" fn() -> u8,
+    /// Solve the `len` bytes at `input`. On success, point `part1` and `part2` at the answers
+    /// and return 0. On failure, return anything else and point `part1` at an error message, or
+    /// leave it null.
+    pub solve: extern "C" fn(
+        input: *const u8,
+        len: usize,
+        part1: *mut *mut c_char,
+        part2: *mut *mut c_char,
+    ) -> i32,
+    /// Free a string that `solve` returned.
+    pub free_string: extern "C" fn(*mut c_char),
+}
+
+// The interface requires plugins to cope with being called from any thread.
+unsafe impl Sync for PluginApi {}
+
+pub struct Plugin {
+    api: &'static PluginApi,
+    name: String,
+    day: u8,
+    /// Keeps `api` valid. `None` for plugins built into the binary, as in tests.
+    _library: Option<Library>,
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin")
+            .field("name", &self.name)
+            .field("day", &self.day)
+            .finish()
+    }
+}
+
+impl Plugin {
+    /// Load the plugin in the library at `path`.
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        // Safety: loading runs the library's initialisers, and the entry point is trusted to have
+        // the declared signature. There's no checking either short of not loading plugins.
+        unsafe {
+            let library = Library::new(path).context("Unable to load the library")?;
+            let entry_point = library
+                .get::<extern "C" fn() -> *const PluginApi>(ENTRY_POINT)
+                .context("The library has no aoc2020_plugin function")?;
+            let api = entry_point();
+            ensure!(!api.is_null(), "aoc2020_plugin returned null");
+            Self::new(&*api, Some(library))
+        }
+    }
+
+    /// Check what `api` describes, kept valid by `library`.
+    fn new(api: &'static PluginApi, library: Option<Library>) -> anyhow::Result<Self> {
+        ensure!(
+            api.abi == ABI_VERSION,
+            "Built for plugin interface version {}, not {}",
+            api.abi,
+            ABI_VERSION
+        );
+        ensure!(!api.name.is_null(), "The plugin has no name");
+        // Safety: the interface requires a null-terminated name that lives as long as `api`.
+        let name = unsafe { CStr::from_ptr(api.name) }
+            .to_str()
+            .context("The plugin's name isn't UTF-8")?;
+        ensure!(
+            !name.is_empty()
+                && name != BUILTIN
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-'),
+            "Invalid plugin name: '{}'",
+            name
+        );
+        let day = (api.day)();
+        ensure!((1..=25).contains(&day), "Invalid day: {}", day);
+
+        Ok(Self {
+            api,
+            name: name.to_owned(),
+            day,
+            _library: library,
+        })
+    }
+
+    /// Take ownership of a string the plugin returned.
+    fn take_string(&self, string: *mut c_char) -> Option<String> {
+        if string.is_null() {
+            return None;
+        }
+        // Safety: the interface requires `solve` to return null-terminated strings.
+        let owned = unsafe { CStr::from_ptr(string) }
+            .to_string_lossy()
+            .into_owned();
+        (self.api.free_string)(string);
+        Some(owned)
+    }
+}
+
+impl Solver for Plugin {
+    fn day(&self) -> u8 {
+        self.day
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn version(&self) -> u32 {
+        self.api.version
+    }
+
+    fn shape(&self) -> Shape {
+        Shape::default()
+    }
+
+    /// Plugins solve both parts in one call, so all of the time is counted as part 1's.
+    fn solve_timed(&self, input: &str) -> anyhow::Result<(Solution, Timings)> {
+        let (mut part1, mut part2) = (ptr::null_mut(), ptr::null_mut());
+        let start = Instant::now();
+        let status = (self.api.solve)(input.as_ptr(), input.len(), &mut part1, &mut part2);
+        let elapsed = start.elapsed();
+
+        let (part1, part2) = (self.take_string(part1), self.take_string(part2));
+        if status != 0 {
+            bail!(
+                "{}",
+                part1.unwrap_or_else(|| format!("Plugin failed with status {}", status))
+            );
+        }
+        match (part1, part2) {
+            (Some(part1), Some(part2)) => {
+                let timings = Timings {
+                    part1: elapsed,
+                    ..Timings::default()
+                };
+                Ok((Solution::new(part1, part2), timings))
+            }
+            _ => bail!("Plugin reported success but gave no answers"),
+        }
+    }
+
+    fn solve_with(&self, input: &str, _: &Params) -> anyhow::Result<Solution> {
+        self.solve(input)
+    }
+
+    fn param_keys(&self) -> &[&str] {
+        &[]
+    }
+
+    fn visualizer(&self) -> Option<&dyn Visualize> {
+        None
+    }
+
+    fn generator(&self) -> Option<&dyn Generate> {
+        None
+    }
+
+    fn reference(&self) -> Option<&dyn Reference> {
+        None
+    }
+
+    fn explainer(&self) -> Option<&dyn Explain> {
+        None
+    }
+}
+
+/// Where plugins are looked for, with `flag` as the `--plugin-dir` value if given.
+pub fn plugin_dir(flag: Option<&str>) -> anyhow::Result<PathBuf> {
+    if let Some(dir) = flag {
+        return Ok(PathBuf::from(dir));
+    }
+    Ok(Config::load()?
+        .get_path("plugin-dir")
+        .unwrap_or_else(|| PathBuf::from(MANIFEST_PLUGIN_DIR)))
+}
+
+/// Every plugin in `dir`, sorted by file name. A missing directory holds no plugins.
+pub fn load_dir(dir: &Path) -> anyhow::Result<Vec<Plugin>> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.is_file()
+                    && path.extension().and_then(|e| e.to_str())
+                        == Some(std::env::consts::DLL_EXTENSION)
+            })
+            .collect(),
+        Err(_) => return Ok(Vec::new()),
+    };
+    paths.sort();
+
+    paths
+        .iter()
+        .map(|path| {
+            let plugin =
+                Plugin::load(path).with_context(|| format!("Invalid plugin {}", path.display()))?;
+            aoc_debug!(
+                "Day {:02}: loaded {} from {}",
+                plugin.day,
+                plugin.name,
+                path.display()
+            );
+            Ok(plugin)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::impls::Registry;
+    use std::ffi::CString;
+
+    extern "C" fn day() -> u8 {
+        1
+    }
+
+    /// Counts the lines, or fails on an empty input.
+    extern "C" fn solve(
+        input: *const u8,
+        len: usize,
+        part1: *mut *mut c_char,
+        part2: *mut *mut c_char,
+    ) -> i32 {
+        let input = unsafe { std::slice::from_raw_parts(input, len) };
+        let lines = input.iter().filter(|&&b| b == b'\n').count();
+        unsafe {
+            if lines == 0 {
+                *part1 = CString::new("No lines").unwrap().into_raw();
+                return 1;
+            }
+            *part1 = CString::new(lines.to_string()).unwrap().into_raw();
+            *part2 = CString::new("two").unwrap().into_raw();
+        }
+        0
+    }
+
+    extern "C" fn free_string(string: *mut c_char) {
+        drop(unsafe { CString::from_raw(string) });
+    }
+
+    static LINE_COUNTER: PluginApi = PluginApi {
+        abi: ABI_VERSION,
+        name: b"lines\0".as_ptr() as *const c_char,
+        version: 3,
+        day,
+        solve,
+        free_string,
+    };
+
+    #[test]
+    fn test_solve() {
+        let plugin = Plugin::new(&LINE_COUNTER, None).unwrap();
+
+        assert_eq!(
+            (plugin.day(), plugin.name(), plugin.version()),
+            (1, "lines", 3)
+        );
+        assert_eq!(plugin.solve("1\n2\n").unwrap(), Solution::new(2, "two"));
+        assert_eq!(plugin.solve("").unwrap_err().to_string(), "No lines");
+    }
+
+    #[test]
+    fn test_names() {
+        let named = |name: &'static [u8]| {
+            let api = Box::leak(Box::new(PluginApi {
+                name: name.as_ptr() as *const c_char,
+                abi: ABI_VERSION,
+                version: 1,
+                day,
+                solve,
+                free_string,
+            }));
+            Plugin::new(api, None).map(|plugin| plugin.name().to_owned())
+        };
+
+        assert_eq!(named(b"fast-v2_1\0").unwrap(), "fast-v2_1");
+        assert!(named(b"\0").is_err());
+        assert!(named(b"builtin\0").is_err());
+        assert!(named(b"../lines\0").is_err());
+        assert!(named(b"a/b\0").is_err());
+        assert!(named(b"two words\0").is_err());
+    }
+
+    #[test]
+    fn test_select() {
+        let plugin = || Plugin::new(&LINE_COUNTER, None).unwrap();
+        let mut registry = Registry::builtin();
+        registry.add_plugins(vec![plugin()]).unwrap();
+
+        assert_eq!(registry.day(1).len(), 2);
+        assert!(registry.add_plugins(vec![plugin()]).is_err());
+
+        let swapped = registry.select("lines").unwrap();
+        assert_eq!(swapped[0].name(), "lines");
+        assert_eq!(swapped[1].name(), BUILTIN);
+    }
+}
@@ -0,0 +1,247 @@
+//! Progress for the few solvers that run for seconds, so that a long run doesn't look like a hang.
+//!
+//! Nothing is shown unless the binary turns reporting on with [`set_style`]: a bar with the time
+//! left when stderr is a terminal, otherwise a line every few seconds. Solvers make a [`Progress`]
+//! and call [`Progress::update`] or [`Progress::tick`] as they go. While reporting is off, or the
+//! run is too short to be worth reporting, each call is one comparison.
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::time::{Duration, Instant};
+
+static STYLE: AtomicU8 = AtomicU8::new(Style::Off as u8);
+
+/// How long a run goes before it's reported on at all.
+const QUIET: Duration = Duration::from_millis(500);
+const BAR_INTERVAL: Duration = Duration::from_millis(100);
+const LINE_INTERVAL: Duration = Duration::from_secs(2);
+const BAR_WIDTH: usize = 30;
+
+/// How many times a run with a known total looks at the clock.
+const CHECKS: u64 = 1000;
+/// How often a run without one does, in steps.
+const UNBOUNDED_STEP: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Off = 0,
+    /// A bar redrawn in place, for terminals.
+    Bar = 1,
+    /// A line now and then, for logs.
+    Lines = 2,
+}
+
+pub fn set_style(style: Style) {
+    STYLE.store(style as u8, Ordering::Relaxed);
+}
+
+fn style() -> Style {
+    match STYLE.load(Ordering::Relaxed) {
+        1 => Style::Bar,
+        2 => Style::Lines,
+        _ => Style::Off,
+    }
+}
+
+/// One long-running loop's progress.
+pub struct Progress {
+    /// Who is making progress, like "Day 15".
+    label: &'static str,
+    /// What is being counted, like "turns".
+    unit: &'static str,
+    total: Option<u64>,
+    style: Style,
+    done: u64,
+    /// The count at which to next look at the clock. Never reached while reporting is off.
+    next: u64,
+    step: u64,
+    start: Instant,
+    shown: Option<Instant>,
+}
+
+impl Progress {
+    /// Progress towards `total` steps.
+    pub fn new(label: &'static str, unit: &'static str, total: u64) -> Self {
+        Self::with_total(label, unit, Some(total))
+    }
+
+    /// Progress without a known end, shown as a count of steps.
+    pub fn unbounded(label: &'static str, unit: &'static str) -> Self {
+        Self::with_total(label, unit, None)
+    }
+
+    fn with_total(label: &'static str, unit: &'static str, total: Option<u64>) -> Self {
+        let style = style();
+        let step = total.map_or(UNBOUNDED_STEP, |total| (total / CHECKS).max(1));
+        Self {
+            label,
+            unit,
+            total,
+            style,
+            done: 0,
+            next: if style == Style::Off { u64::MAX } else { step },
+            step,
+            start: Instant::now(),
+            shown: None,
+        }
+    }
+
+    /// Record that `done` steps have been taken.
+    #[inline]
+    pub fn update(&mut self, done: u64) {
+        self.done = done;
+        if done >= self.next {
+            self.check();
+        }
+    }
+
+    /// Record one more step.
+    #[inline]
+    pub fn tick(&mut self) {
+        self.update(self.done + 1);
+    }
+
+    #[cold]
+    fn check(&mut self) {
+        self.next = self.done.saturating_add(self.step);
+        let now = Instant::now();
+        let elapsed = now - self.start;
+        let interval = match self.style {
+            Style::Bar => BAR_INTERVAL,
+            _ => LINE_INTERVAL,
+        };
+        let due = match self.shown {
+            Some(shown) => now - shown >= interval,
+            None => elapsed >= QUIET.max(interval),
+        };
+        if !due {
+            return;
+        }
+
+        self.shown = Some(now);
+        let mut stderr = io::stderr().lock();
+        let _ = match self.style {
+            Style::Bar => write!(stderr, "\r{}\x1b[K", self.bar(elapsed)),
+            _ => writeln!(stderr, "{}", self.line(elapsed)),
+        };
+        let _ = stderr.flush();
+    }
+
+    /// The time left at the rate so far, if there's a total to reach.
+    fn remaining(&self, elapsed: Duration) -> Option<Duration> {
+        let total = self.total?;
+        if self.done == 0 {
+            return None;
+        }
+        let left = total.saturating_sub(self.done) as f64 / self.done as f64;
+        Some(Duration::from_secs_f64(elapsed.as_secs_f64() * left))
+    }
+
+    fn bar(&self, elapsed: Duration) -> String {
+        match self.total {
+            Some(total) => {
+                let fraction = (self.done as f64 / total.max(1) as f64).min(1.0);
+                let filled = (fraction * BAR_WIDTH as f64) as usize;
+                format!(
+                    "{} [{}{}] {:3.0}% {} left",
+                    self.label,
+                    "#".repeat(filled),
+                    ".".repeat(BAR_WIDTH - filled),
+                    fraction * 100.0,
+                    format_duration(self.remaining(elapsed).unwrap_or_default())
+                )
+            }
+            None => format!(
+                "{}: {} {} in {}",
+                self.label,
+                self.done,
+                self.unit,
+                format_duration(elapsed)
+            ),
+        }
+    }
+
+    fn line(&self, elapsed: Duration) -> String {
+        match (self.total, self.remaining(elapsed)) {
+            (Some(total), Some(remaining)) => format!(
+                "{}: {} of {} {} after {}, about {} left",
+                self.label,
+                self.done,
+                total,
+                self.unit,
+                format_duration(elapsed),
+                format_duration(remaining)
+            ),
+            _ => format!(
+                "{}: {} {} after {}",
+                self.label,
+                self.done,
+                self.unit,
+                format_duration(elapsed)
+            ),
+        }
+    }
+}
+
+impl Drop for Progress {
+    /// Clear the bar, so that the answers don't land on the end of it.
+    fn drop(&mut self) {
+        if self.style == Style::Bar && self.shown.is_some() {
+            eprint!("\r\x1b[K");
+        }
+    }
+}
+
+/// Tenths of a second, which is all an estimate is good for.
+fn format_duration(duration: Duration) -> String {
+    format!("{:.1}s", duration.as_secs_f64())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn progress(total: Option<u64>, done: u64) -> Progress {
+        let mut progress = Progress::with_total("Day 15", "turns", total);
+        progress.done = done;
+        progress
+    }
+
+    #[test]
+    fn test_off() {
+        // Tests never turn reporting on, so nothing reaches the clock.
+        let mut progress = Progress::new("Day 15", "turns", 10);
+        for done in 0..100 {
+            progress.update(done);
+        }
+        progress.tick();
+        assert_eq!(progress.next, u64::MAX);
+        assert_eq!(progress.done, 100);
+        assert!(progress.shown.is_none());
+    }
+
+    #[test]
+    fn test_render() {
+        let second = Duration::from_secs(1);
+
+        assert_eq!(
+            progress(Some(200), 50).bar(second),
+            "Day 15 [#######.......................]  25% 3.0s left"
+        );
+        assert_eq!(
+            progress(Some(200), 50).line(second),
+            "Day 15: 50 of 200 turns after 1.0s, about 3.0s left"
+        );
+        assert_eq!(
+            progress(Some(200), 0).line(second),
+            "Day 15: 0 turns after 1.0s"
+        );
+        assert_eq!(
+            progress(None, 1234).bar(second * 2),
+            "Day 15: 1234 turns in 2.0s"
+        );
+        assert_eq!(
+            progress(Some(10), 10).bar(second),
+            format!("Day 15 [{}] 100% 0.0s left", "#".repeat(BAR_WIDTH))
+        );
+    }
+}
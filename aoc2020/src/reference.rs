@@ -0,0 +1,111 @@
+//! Brute-force reference solutions, for checking the optimized solvers against.
+//!
+//! Days opt in by implementing [`Reference`] and returning themselves from
+//! [`crate::Solver::reference`]. A reference solution should be the most obvious approach that
+//! works, sharing as little code with the real one as it can, so that a bug in one is unlikely to
+//! be repeated in the other. [`differ`] runs both on inputs from the day's [`Generate`]
+//! implementation and reports the first one they disagree on.
+use std::fmt;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+
+use crate::generate::{Generate, Rng};
+use crate::solution::Solution;
+use crate::Solver;
+
+pub trait Reference {
+    /// Solve `input` the slow and simple way.
+    fn solve_reference(&self, input: &str) -> Solution;
+}
+
+/// An input on which the two solutions disagree. Errors and panics count as a disagreement.
+pub struct Mismatch {
+    pub day: u8,
+    pub seed: u64,
+    pub input: String,
+    pub expected: Result<Solution, String>,
+    pub actual: Result<Solution, String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Day {:02} disagrees with its reference on the input from seed {}",
+            self.day, self.seed
+        )?;
+        for (name, result) in &[("Reference", &self.expected), ("Solver", &self.actual)] {
+            match result {
+                Ok(solution) => writeln!(f, "{}:\n{}", name, solution)?,
+                Err(error) => writeln!(f, "{}: {}", name, error)?,
+            }
+        }
+        write!(f, "Input:\n{}", self.input)
+    }
+}
+
+/// Solve the input generated from each seed both ways, stopping at the first mismatch.
+pub fn differ(
+    solver: &dyn Solver,
+    reference: &dyn Reference,
+    generator: &dyn Generate,
+    seeds: Range<u64>,
+    size: usize,
+) -> Result<(), Box<Mismatch>> {
+    for seed in seeds {
+        let input = generator.generate(&mut Rng::new(seed), size);
+        let expected = catch(|| Ok(reference.solve_reference(&input)));
+        let actual = catch(|| solver.solve(&input));
+
+        if expected.is_err() || expected != actual {
+            return Err(Box::new(Mismatch {
+                day: solver.day(),
+                seed,
+                input,
+                expected,
+                actual,
+            }));
+        }
+    }
+
+    Ok(())
+}
+
+fn catch(solve: impl FnOnce() -> anyhow::Result<Solution>) -> Result<Solution, String> {
+    match panic::catch_unwind(AssertUnwindSafe(solve)) {
+        Ok(result) => result.map_err(|error| format!("{:#}", error)),
+        Err(_) => Err(String::from("panicked")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::impls::Registry;
+
+    /// Days that always play tens of millions of turns, which takes their reference solutions
+    /// minutes in a debug build. `aoc2020 diff` on a release build covers them.
+    const SLOW: [u8; 2] = [15, 23];
+
+    #[test]
+    fn test_reference_solutions_agree() {
+        let registry = Registry::builtin();
+        for solver in registry.iter() {
+            if SLOW.contains(&solver.day()) {
+                continue;
+            }
+            // Alternatives are checked against their day's default's reference.
+            let default = registry.day(solver.day())[0];
+            let (reference, generator) = match (default.reference(), default.generator()) {
+                (Some(reference), Some(generator)) => (reference, generator),
+                _ => continue,
+            };
+
+            // Smaller than the real inputs, since the reference solutions are slow by design.
+            let size = (generator.default_size() / 4).max(1);
+            if let Err(mismatch) = differ(solver, reference, generator, 0..3, size) {
+                panic!("{}", mismatch);
+            }
+        }
+    }
+}
@@ -0,0 +1,452 @@
+//! A small HTTP service so that other tools can use the solvers over localhost without linking the
+//! crate.
+//!
+//! - `GET /days` lists the solvers: `{"days":[{"day":1,"version":1},...]}`.
+//! - `POST /solve/{day}` solves the request body as the day's input and answers with
+//!   `{"day":1,"part1":"...","part2":"...","timings":{"parse_us":...,"part1_us":...,
+//!   "part2_us":...,"total_us":...}}`. Answers are always strings, whatever the day returns.
+//!
+//! Anything that goes wrong is reported as `{"error":"..."}` with a matching status. Requests are
+//! handled one at a time and every connection is closed after its response.
+//!
+//! The days hash with an unseeded [hasher](crate::hash), so a crafted input can make one slow.
+//! Bodies are capped well below what that would need to matter, and a solve that still takes
+//! longer than `SOLVE_TIMEOUT` is answered with a 503 and left to finish on its own. Later
+//! solves get a 503 too until it has.
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::inputs::Input;
+use crate::solution::{Solution, Timings};
+use crate::Solver;
+
+/// More than twenty times the largest real input, but small enough that neither a stray upload nor
+/// keys crafted to collide can tie the service up for long.
+const MAX_BODY: usize = 1024 * 1024;
+
+/// How long a solve may take before its client is told to give up. Real inputs need well under a
+/// second in a release build.
+const SOLVE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The longest request or header line accepted, which also bounds how much a line can make us
+/// buffer.
+const MAX_LINE: usize = 8 * 1024;
+
+/// Limits on the headers as a whole, as a line cap alone would still let them go on forever.
+const MAX_HEADERS: usize = 100;
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// How long a client may take to send its request before it's dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+struct Request {
+    method: String,
+    path: String,
+    body: Vec<u8>,
+}
+
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn ok(body: String) -> Self {
+        Self { status: 200, body }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            body: format!("{{\"error\":{}}}", json_string(message)),
+        }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            411 => "Length Required",
+            413 => "Payload Too Large",
+            431 => "Request Header Fields Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write_to(&self, stream: &mut impl Write) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            self.body.len(),
+            self.body
+        )?;
+        stream.flush()
+    }
+}
+
+/// Answer requests on `listener` until it fails.
+pub fn serve(solvers: Vec<Box<dyn Solver>>, listener: TcpListener) -> anyhow::Result<()> {
+    let mut server = Server::new(solvers, SOLVE_TIMEOUT);
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+        if let Err(error) = handle_connection(&mut server, &mut stream) {
+            aoc_debug!("Dropped connection: {}", error);
+        }
+    }
+    Ok(())
+}
+
+/// Solves on a worker thread, so that a solve which runs too long can be abandoned.
+struct Server {
+    days: String,
+    jobs: mpsc::Sender<Job>,
+    timeout: Duration,
+    /// Where the answer to a solve that timed out will arrive, while it's still running.
+    overdue: Option<mpsc::Receiver<Response>>,
+}
+
+struct Job {
+    day: String,
+    body: Vec<u8>,
+    reply: mpsc::Sender<Response>,
+}
+
+impl Server {
+    fn new(solvers: Vec<Box<dyn Solver>>, timeout: Duration) -> Self {
+        let days = days(&solvers);
+        let (jobs, queue) = mpsc::channel::<Job>();
+        thread::spawn(move || {
+            for job in queue {
+                let _ = job.reply.send(solve(&solvers, &job.day, job.body));
+            }
+        });
+        Self {
+            days,
+            jobs,
+            timeout,
+            overdue: None,
+        }
+    }
+
+    fn route(&mut self, request: Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        match (request.method.as_str(), &segments[..]) {
+            ("GET", ["days"]) => Response::ok(self.days.clone()),
+            ("POST", ["solve", day]) => self.solve(day, request.body),
+            (_, ["days"]) | (_, ["solve", _]) => Response::error(405, "Method not allowed"),
+            _ => Response::error(404, "Not found"),
+        }
+    }
+
+    fn solve(&mut self, day: &str, body: Vec<u8>) -> Response {
+        if let Some(overdue) = &self.overdue {
+            if let Err(TryRecvError::Empty) = overdue.try_recv() {
+                return Response::error(503, "Still solving an input that timed out");
+            }
+            self.overdue = None;
+        }
+
+        let (reply, answer) = mpsc::channel();
+        let job = Job {
+            day: day.to_owned(),
+            body,
+            reply,
+        };
+        if self.jobs.send(job).is_err() {
+            return Response::error(500, "The solver thread has stopped");
+        }
+        match answer.recv_timeout(self.timeout) {
+            Ok(response) => response,
+            Err(RecvTimeoutError::Timeout) => {
+                self.overdue = Some(answer);
+                Response::error(503, "Solving took too long")
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                Response::error(500, "The solver thread has stopped")
+            }
+        }
+    }
+}
+
+fn handle_connection(server: &mut Server, stream: &mut TcpStream) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let response = match read_request(BufReader::new(&*stream))? {
+        Ok(request) => {
+            aoc_info!("{} {}", request.method, request.path);
+            server.route(request)
+        }
+        Err(response) => response,
+    };
+    response.write_to(stream)
+}
+
+/// The request, or the response to reject it with.
+fn read_request(mut reader: impl BufRead) -> io::Result<Result<Request, Response>> {
+    let mut line = String::new();
+    if read_line(&mut reader, &mut line)?.is_none() {
+        return Ok(Err(Response::error(400, "Request line too long")));
+    }
+    let (method, path) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+        [method, path, version] if version.starts_with("HTTP/") => {
+            (method.to_owned(), path.to_owned())
+        }
+        _ => return Ok(Err(Response::error(400, "Malformed request line"))),
+    };
+
+    let mut content_length = None;
+    let mut header_count = 0;
+    let mut header_bytes = 0;
+    loop {
+        let read = match read_line(&mut reader, &mut line)? {
+            Some(0) => return Ok(Err(Response::error(400, "Request ended in the headers"))),
+            Some(read) => read,
+            None => return Ok(Err(Response::error(431, "Header line too long"))),
+        };
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+
+        header_count += 1;
+        header_bytes += read;
+        if header_count > MAX_HEADERS || header_bytes > MAX_HEADER_BYTES {
+            return Ok(Err(Response::error(431, "Headers too large")));
+        }
+
+        let (name, value) = match header.split_once(':') {
+            Some(pair) => pair,
+            None => return Ok(Err(Response::error(400, "Malformed header"))),
+        };
+        if name.eq_ignore_ascii_case("content-length") {
+            match value.trim().parse::<usize>() {
+                Ok(length) => content_length = Some(length),
+                Err(_) => return Ok(Err(Response::error(400, "Invalid Content-Length"))),
+            }
+        } else if name.eq_ignore_ascii_case("transfer-encoding") {
+            return Ok(Err(Response::error(
+                411,
+                "Send the body with a Content-Length",
+            )));
+        }
+    }
+
+    let length = match (content_length, method.as_str()) {
+        (Some(length), _) => length,
+        (None, "POST") => return Ok(Err(Response::error(411, "Missing Content-Length"))),
+        (None, _) => 0,
+    };
+    if length > MAX_BODY {
+        return Ok(Err(Response::error(413, "Input too large")));
+    }
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    Ok(Ok(Request { method, path, body }))
+}
+
+/// Replace `line` with the next line and return its length, or `None` if it runs past
+/// `MAX_LINE` without ending.
+fn read_line(mut reader: impl BufRead, line: &mut String) -> io::Result<Option<usize>> {
+    line.clear();
+    let read = reader.by_ref().take(MAX_LINE as u64).read_line(line)?;
+    if read == MAX_LINE && !line.ends_with('\n') {
+        Ok(None)
+    } else {
+        Ok(Some(read))
+    }
+}
+
+fn days(solvers: &[Box<dyn Solver>]) -> String {
+    let days: Vec<String> = solvers
+        .iter()
+        .map(|solver| {
+            format!(
+                "{{\"day\":{},\"version\":{}}}",
+                solver.day(),
+                solver.version()
+            )
+        })
+        .collect();
+    format!("{{\"days\":[{}]}}", days.join(","))
+}
+
+fn solve(solvers: &[Box<dyn Solver>], day: &str, body: Vec<u8>) -> Response {
+    let solver = match day
+        .parse::<u8>()
+        .ok()
+        .and_then(|day| solvers.iter().find(|solver| solver.day() == day))
+    {
+        Some(solver) => solver,
+        None => return Response::error(404, &format!("No solver for day '{}'", day)),
+    };
+    let input = Input::from(body);
+
+    // A panicking solver is a bug, but it shouldn't take the service down with it.
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+        solver.solve_bytes_timed(input.as_bytes())
+    })) {
+        Ok(Ok((solution, timings))) => {
+            Response::ok(solution_json(solver.day(), &solution, &timings))
+        }
+        Ok(Err(error)) => Response::error(400, &format!("{:#}", error)),
+        Err(_) => Response::error(500, "Solver panicked"),
+    }
+}
+
+fn solution_json(day: u8, solution: &Solution, timings: &Timings) -> String {
+    format!(
+        "{{\"day\":{},\"part1\":{},\"part2\":{},\"timings\":{{\"parse_us\":{},\"part1_us\":{},\
+         \"part2_us\":{},\"total_us\":{}}}}}",
+        day,
+        json_string(solution.part1()),
+        json_string(solution.part2()),
+        timings.parse.as_micros(),
+        timings.part1.as_micros(),
+        timings.part2.as_micros(),
+        timings.total().as_micros()
+    )
+}
+
+fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::days::get_solvers;
+    use std::io::Cursor;
+    use std::net::Ipv4Addr;
+    use std::thread;
+
+    /// Send `request` to a fresh server on a loopback port and return the raw response.
+    fn exchange(request: &[u8]) -> String {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            let mut server = Server::new(get_solvers(), SOLVE_TIMEOUT);
+            let (mut stream, _) = listener.accept().unwrap();
+            handle_connection(&mut server, &mut stream).unwrap();
+        });
+
+        let mut client = TcpStream::connect(address).unwrap();
+        client.write_all(request).unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a\"b\\c\nd\u{1}"), r#""a\"b\\c\nd\u0001""#);
+    }
+
+    #[test]
+    fn test_days() {
+        let response = exchange(b"GET /days HTTP/1.1\r\nHost: localhost\r\n\r\n");
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\n\r\n{\"days\":[{\"day\":1,\"version\":1},{\"day\":2,"));
+        assert!(response.ends_with(",{\"day\":25,\"version\":1}]}"));
+    }
+
+    #[test]
+    fn test_solve() {
+        let input = "1721\r\n979\r\n366\r\n299\r\n675\r\n1456";
+        let request = format!(
+            "POST /solve/1 HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}",
+            input.len(),
+            input
+        );
+        let response = exchange(request.as_bytes());
+
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("{\"day\":1,\"part1\":\"514579\",\"part2\":\"241861950\""));
+        assert!(response.contains("\"total_us\":"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let bad_input = exchange(b"POST /solve/1 HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc");
+        let no_day = exchange(b"POST /solve/26 HTTP/1.1\r\nContent-Length: 0\r\n\r\n");
+        let wrong_method = exchange(b"GET /solve/1 HTTP/1.1\r\n\r\n");
+        let no_length = exchange(b"POST /solve/1 HTTP/1.1\r\n\r\n");
+
+        assert!(bad_input.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+        assert!(bad_input.contains("{\"error\":\""));
+        assert!(no_day.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(no_day.ends_with("{\"error\":\"No solver for day '26'\"}"));
+        assert!(wrong_method.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+        assert!(no_length.starts_with("HTTP/1.1 411 Length Required\r\n"));
+    }
+
+    /// The status `read_request` rejects `request` with, if it does.
+    fn rejection(request: &[u8]) -> Option<u16> {
+        read_request(Cursor::new(request))
+            .unwrap()
+            .err()
+            .map(|response| response.status)
+    }
+
+    #[test]
+    fn test_limits() {
+        let long_path = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE));
+        let long_header = format!("GET /days HTTP/1.1\r\nX: {}\r\n\r\n", "a".repeat(MAX_LINE));
+        let many_headers = format!(
+            "GET /days HTTP/1.1\r\n{}\r\n",
+            "X: a\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let big_headers = format!(
+            "GET /days HTTP/1.1\r\n{}\r\n",
+            format!("X: {}\r\n", "a".repeat(MAX_LINE / 2))
+                .repeat(MAX_HEADER_BYTES / MAX_LINE * 2 + 2)
+        );
+        let endless = "a".repeat(10 * MAX_LINE);
+
+        assert_eq!(rejection(b"GET /days HTTP/1.1\r\nX: a\r\n\r\n"), None);
+        assert_eq!(rejection(long_path.as_bytes()), Some(400));
+        assert_eq!(rejection(long_header.as_bytes()), Some(431));
+        assert_eq!(rejection(many_headers.as_bytes()), Some(431));
+        assert_eq!(rejection(big_headers.as_bytes()), Some(431));
+        assert_eq!(rejection(endless.as_bytes()), Some(400));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut server = Server::new(get_solvers(), Duration::from_millis(1));
+        let slow = server.solve("15", b"0,3,6\n".to_vec());
+        let busy = server.solve("1", b"1721\n979\n".to_vec());
+
+        assert_eq!(slow.status, 503);
+        assert!(slow.body.contains("took too long"));
+        assert_eq!(busy.status, 503);
+        assert!(busy.body.contains("Still solving"));
+    }
+}
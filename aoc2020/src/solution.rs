@@ -1,4 +1,5 @@
 use std::fmt;
+use std::time::Duration;
 
 #[derive(Default, PartialEq)]
 pub struct Solution {
@@ -7,6 +8,13 @@ pub struct Solution {
 }
 
 impl Solution {
+    pub fn new(part1: impl fmt::Display, part2: impl fmt::Display) -> Self {
+        Self {
+            part1: part1.to_string(),
+            part2: part2.to_string(),
+        }
+    }
+
     pub fn part1(&self) -> &str {
         &self.part1
     }
@@ -27,6 +35,33 @@ impl fmt::Debug for Solution {
     }
 }
 
+/// How long each stage of a solve took.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Timings {
+    pub parse: Duration,
+    pub part1: Duration,
+    pub part2: Duration,
+}
+
+impl Timings {
+    pub fn total(&self) -> Duration {
+        self.parse + self.part1 + self.part2
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "parse {:?}, part 1 {:?}, part 2 {:?} (total {:?})",
+            self.parse,
+            self.part1,
+            self.part2,
+            self.total()
+        )
+    }
+}
+
 macro_rules! impl_from {
     ($(($t:ty,$tt:ty)),+) => {
         $(impl std::convert::From<($t, $tt)> for Solution {
@@ -0,0 +1,311 @@
+//! Export pictures of a day's state as plain PBM/PPM images or SVG drawings.
+//!
+//! Days opt in by implementing [`Visualize`] and returning themselves from
+//! [`crate::Solver::visualizer`]. Frames are written as `<series>_<index>.<ext>`, so a simulation
+//! emits one numbered file per generation or step.
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+pub trait Visualize {
+    /// Emit the frames for `input`.
+    fn visualize(&self, input: &str, frames: &mut FrameWriter) -> io::Result<()>;
+}
+
+/// Writes frames into a directory, numbering each series separately.
+pub struct FrameWriter {
+    directory: PathBuf,
+    counts: HashMap<String, usize>,
+}
+
+impl FrameWriter {
+    /// Create a writer for `directory`, creating it if needed.
+    pub fn new(directory: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+
+        Ok(Self {
+            directory,
+            counts: HashMap::new(),
+        })
+    }
+
+    /// Write the next frame of `series` and return the path it was written to.
+    pub fn write(&mut self, series: &str, frame: impl Into<Frame>) -> io::Result<PathBuf> {
+        let frame = frame.into();
+        let count = self.counts.entry(series.to_owned()).or_insert(0);
+        let path = self
+            .directory
+            .join(format!("{}_{:04}.{}", series, count, frame.extension()));
+        *count += 1;
+
+        fs::write(&path, frame.to_string())?;
+        Ok(path)
+    }
+
+    /// Write every frame of `series` in order.
+    pub fn write_all<F: Into<Frame>>(
+        &mut self,
+        series: &str,
+        frames: impl IntoIterator<Item = F>,
+    ) -> io::Result<()> {
+        for frame in frames {
+            self.write(series, frame)?;
+        }
+        Ok(())
+    }
+}
+
+pub enum Frame {
+    Bitmap(Bitmap),
+    Pixmap(Pixmap),
+    Svg(Svg),
+}
+
+impl Frame {
+    fn extension(&self) -> &'static str {
+        match self {
+            Self::Bitmap(_) => "pbm",
+            Self::Pixmap(_) => "ppm",
+            Self::Svg(_) => "svg",
+        }
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bitmap(bitmap) => bitmap.fmt(f),
+            Self::Pixmap(pixmap) => pixmap.fmt(f),
+            Self::Svg(svg) => svg.fmt(f),
+        }
+    }
+}
+
+impl From<Bitmap> for Frame {
+    fn from(bitmap: Bitmap) -> Self {
+        Self::Bitmap(bitmap)
+    }
+}
+
+impl From<Pixmap> for Frame {
+    fn from(pixmap: Pixmap) -> Self {
+        Self::Pixmap(pixmap)
+    }
+}
+
+impl From<Svg> for Frame {
+    fn from(svg: Svg) -> Self {
+        Self::Svg(svg)
+    }
+}
+
+/// Black and white image, written as a plain (`P1`) PBM. `true` pixels are black.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, pixel: bool) {
+        self.pixels[y * self.width + x] = pixel;
+    }
+}
+
+impl fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "P1\n{} {}", self.width, self.height)?;
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let row: Vec<_> = row.iter().map(|&p| if p { "1" } else { "0" }).collect();
+            writeln!(f, "{}", row.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Colour image, written as a plain (`P3`) PPM.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pixmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 3]>,
+}
+
+impl Pixmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![[0; 3]; width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, rgb: [u8; 3]) {
+        self.pixels[y * self.width + x] = rgb;
+    }
+}
+
+impl fmt::Display for Pixmap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "P3\n{} {}\n255", self.width, self.height)?;
+        for row in self.pixels.chunks(self.width.max(1)) {
+            let row: Vec<_> = row
+                .iter()
+                .map(|[r, g, b]| format!("{} {} {}", r, g, b))
+                .collect();
+            writeln!(f, "{}", row.join("  "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Minimal SVG drawing. Coordinates are in user units inside the given view box.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Svg {
+    view_box: (f64, f64, f64, f64),
+    elements: Vec<String>,
+}
+
+impl Svg {
+    pub fn new(min_x: f64, min_y: f64, width: f64, height: f64) -> Self {
+        Self {
+            view_box: (min_x, min_y, width, height),
+            elements: Vec::new(),
+        }
+    }
+
+    /// Create a drawing whose view box fits `points` with `margin` to spare on every side.
+    pub fn fitting(points: impl IntoIterator<Item = (f64, f64)>, margin: f64) -> Self {
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        if min_x > max_x {
+            return Self::new(-margin, -margin, 2.0 * margin, 2.0 * margin);
+        }
+
+        Self::new(
+            min_x - margin,
+            min_y - margin,
+            max_x - min_x + 2.0 * margin,
+            max_y - min_y + 2.0 * margin,
+        )
+    }
+
+    pub fn polyline(&mut self, points: &[(f64, f64)], stroke: &str) {
+        self.elements.push(format!(
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1" vector-effect="non-scaling-stroke"/>"#,
+            svg_points(points),
+            stroke
+        ));
+    }
+
+    pub fn polygon(&mut self, points: &[(f64, f64)], fill: &str) {
+        self.elements.push(format!(
+            r#"<polygon points="{}" fill="{}"/>"#,
+            svg_points(points),
+            fill
+        ));
+    }
+
+    pub fn circle(&mut self, (x, y): (f64, f64), radius: f64, fill: &str) {
+        self.elements.push(format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+            x, y, radius, fill
+        ));
+    }
+}
+
+fn svg_points(points: &[(f64, f64)]) -> String {
+    let mut out = String::new();
+    for (x, y) in points {
+        let _ = write!(out, "{},{} ", x, y);
+    }
+    out.pop();
+    out
+}
+
+impl fmt::Display for Svg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (min_x, min_y, width, height) = self.view_box;
+        writeln!(
+            f,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="{} {} {} {}">"#,
+            min_x, min_y, width, height
+        )?;
+        for element in &self.elements {
+            writeln!(f, "  {}", element)?;
+        }
+        writeln!(f, "</svg>")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bitmap() {
+        let mut bitmap = Bitmap::new(3, 2);
+        bitmap.set(0, 0, true);
+        bitmap.set(2, 1, true);
+
+        let expected = "P1\n3 2\n1 0 0\n0 0 1\n";
+        assert_eq!(bitmap.to_string(), expected)
+    }
+
+    #[test]
+    fn test_pixmap() {
+        let mut pixmap = Pixmap::new(2, 1);
+        pixmap.set(1, 0, [255, 0, 10]);
+
+        let expected = "P3\n2 1\n255\n0 0 0  255 0 10\n";
+        assert_eq!(pixmap.to_string(), expected)
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut svg = Svg::fitting(vec![(0.0, 0.0), (4.0, -2.0)], 1.0);
+        svg.polyline(&[(0.0, 0.0), (4.0, -2.0)], "black");
+
+        let expected = "\
+<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"-1 -3 6 4\">
+  <polyline points=\"0,0 4,-2\" fill=\"none\" stroke=\"black\" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"/>
+</svg>
+";
+        assert_eq!(svg.to_string(), expected)
+    }
+
+    #[test]
+    fn test_frame_writer() {
+        let directory = std::env::temp_dir().join(format!("aoc2020-frames-{}", std::process::id()));
+        let mut frames = FrameWriter::new(&directory).unwrap();
+
+        let first = frames.write("part1", Bitmap::new(1, 1)).unwrap();
+        let second = frames.write("part1", Bitmap::new(1, 1)).unwrap();
+        let other = frames.write("part2", Pixmap::new(1, 1)).unwrap();
+
+        assert_eq!(first, directory.join("part1_0000.pbm"));
+        assert_eq!(second, directory.join("part1_0001.pbm"));
+        assert_eq!(other, directory.join("part2_0000.ppm"));
+        assert_eq!(fs::read_to_string(&other).unwrap(), "P3\n1 1\n255\n0 0 0\n");
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}